@@ -0,0 +1,5 @@
+-- GCRA state, see `axum_middleware::gcra`
+CREATE TABLE rate_limits (
+    key TEXT PRIMARY KEY NOT NULL,
+    tat INTEGER NOT NULL -- theoretical arrival time, nanoseconds since the unix epoch
+) WITHOUT ROWID;
@@ -0,0 +1,5 @@
+-- `jti` of the single-use tokens that have been used, see `signature::TokenLedger`
+CREATE TABLE consumed_tokens (
+    jti TEXT PRIMARY KEY NOT NULL,
+    exp INTEGER NOT NULL -- unix timestamp, after which the token is rejected anyway
+) WITHOUT ROWID;
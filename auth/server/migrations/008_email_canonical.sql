@@ -0,0 +1,6 @@
+-- see `email::Email::canonical`. Filled in for existing users on startup,
+-- since the canonical form depends on the configured `email::Canonicalization`.
+ALTER TABLE users
+ADD COLUMN email_canonical TEXT;
+
+CREATE INDEX idx__users__email_canonical ON users (email_canonical);
@@ -0,0 +1,6 @@
+-- see `validation::UsernamePolicy::canonical`. Filled in for existing users on startup,
+-- since the canonical form depends on whether `[username] allow_unicode` is set.
+ALTER TABLE users
+ADD COLUMN username_canonical TEXT;
+
+CREATE INDEX idx__users__username_canonical ON users (username_canonical);
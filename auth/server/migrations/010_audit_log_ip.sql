@@ -0,0 +1,4 @@
+-- the client IP of whoever assigned or revoked the permission, see `client_ip::ClientIpConfig`.
+-- NULL when it is unknown, and for entries written before.
+ALTER TABLE permissions_audit_log
+ADD COLUMN ip TEXT;
@@ -0,0 +1,4 @@
+-- see `validation::UsernamePolicy::canonical_kind`. `username_canonical` is recomputed on startup
+-- where it differs from the one of the configured policy, e.g. after turning on `[username] allow_unicode`.
+ALTER TABLE users
+ADD COLUMN username_canonical_kind TEXT;
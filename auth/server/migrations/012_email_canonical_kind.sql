@@ -0,0 +1,10 @@
+-- see `email::Canonicalization::kind`. `email_canonical` is recomputed on startup where it differs
+-- from the configured one, e.g. after turning on `[email] fold_local_part`.
+ALTER TABLE users
+ADD COLUMN email_canonical_kind TEXT;
+
+-- computed by an unknown canonicalization, and not necessarily unique
+UPDATE users SET email_canonical = NULL;
+
+DROP INDEX idx__users__email_canonical;
+CREATE UNIQUE INDEX idx__users__email_canonical ON users (email_canonical) WHERE email_canonical IS NOT NULL;
@@ -1,5 +1,6 @@
 INSERT INTO permissions (permission, description) VALUES
 ('post:/access-token/generate',         'Generate a new Access Token'),
+('get:/metrics',                        'Get Prometheus metrics'),
 ('get:/permissions',                    'Get a list of permissions held by the Principal'),
 ('post:/permissions/assign',            'Assign a permission to an Assignee'),
 ('post:/rotate-key',                    'Rotate the Secret key'),
@@ -21,6 +22,7 @@ WITH mapping([group], permission) AS (
     ('signup',    'post:/permissions/assign'),
 
     ('admin',     'post:/access-token/generate'),
+    ('admin',     'get:/metrics'),
     ('admin',     'get:/permissions'),
     ('admin',     'post:/permissions/assign'),
     ('admin',     'post:/rotate-key'),
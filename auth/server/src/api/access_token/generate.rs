@@ -1,7 +1,6 @@
 use std::time::Duration;
 
 use axum::{
-    Form,
     extract::State,
     http::StatusCode,
     response::IntoResponse,
@@ -14,7 +13,7 @@ use time::OffsetDateTime;
 
 use crate::{
     AppState,
-    core::{AccessToken, InsufficientPermissionsError, Principal},
+    core::{AccessToken, InsufficientPermissionsError, Payload, Principal},
 };
 
 pub const PATH: &str = "/access-token/generate";
@@ -39,8 +38,10 @@ pub fn method_router() -> MethodRouter<AppState> {
     path = PATH,
     operation_id = PATH,
     request_body(
-        content = Config,
-        content_type = "application/x-www-form-urlencoded",
+        content(
+            (Config = "application/json"),
+            (Config = "application/x-www-form-urlencoded"),
+        ),
     ),
     responses(
         (status = 200, description = "Access token generated successfully", body = String),
@@ -52,9 +53,9 @@ pub fn method_router() -> MethodRouter<AppState> {
 #[debug_handler]
 #[cfg_attr(feature = "tracing", tracing::instrument(fields(%principal, ?settings), skip_all))]
 pub async fn handler(
-    State(AppState { pool, .. }): State<AppState>,
+    State(AppState { pool, peppers, .. }): State<AppState>,
     principal: Principal,
-    Form(settings): Form<Config>,
+    Payload(settings): Payload<Config>,
 ) -> Result<(StatusCode, String), Error> {
     principal
         .require_permission::<Error>(&pool, "post:/access-token/generate")
@@ -63,7 +64,8 @@ pub async fn handler(
     let user_id = principal.user_id();
 
     let access_token = AccessToken::new();
-    let access_token_hash = access_token.hash_sha256();
+    let pepper = peppers.current().context("get pepper")?;
+    let access_token_hash = access_token.hash_hmac_sha256(&pepper.secret);
     let created_at = OffsetDateTime::now_utc();
     let expires_at = settings
         .ttl_sec
@@ -88,7 +90,7 @@ pub async fn handler(
     #[cfg(feature = "tracing")]
     tracing::info!(?expires_at, "access_token created");
 
-    Ok((StatusCode::CREATED, access_token.base64encoded()))
+    Ok((StatusCode::CREATED, access_token.prefixed()))
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -96,6 +98,9 @@ pub enum Error {
     #[error("{0}")]
     InsufficientPermissions(#[from] InsufficientPermissionsError),
 
+    #[error("{0}")]
+    Secrets(#[from] contextual::Error<crate::secrets::SecretStoreError>),
+
     #[error("{0}")]
     Sqlx(#[from] contextual::Error<sqlx::Error>),
 }
@@ -104,9 +109,9 @@ impl IntoResponse for Error {
     fn into_response(self) -> axum::response::Response {
         match self {
             Error::InsufficientPermissions(err) => err.into_response(),
-            Error::Sqlx(_err) => {
+            Error::Secrets(_) | Error::Sqlx(_) => {
                 #[cfg(feature = "tracing")]
-                tracing::error!("{:?}", _err);
+                tracing::error!("{:?}", self);
 
                 StatusCode::INTERNAL_SERVER_ERROR.into_response()
             }
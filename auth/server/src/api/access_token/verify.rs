@@ -39,14 +39,15 @@ pub fn method_router() -> MethodRouter<AppState> {
 ))]
 #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, ret))]
 pub async fn handler(
-    State(AppState { pool, .. }): State<AppState>,
+    State(AppState { pool, peppers, .. }): State<AppState>,
     headers: HeaderMap,
 ) -> Result<StatusCode, Error> {
     let access_token =
         AccessToken::try_from_headers(&headers)?.ok_or_else(|| Error::AccessTokenHeaderNotFound)?;
 
+    let peppers = peppers.versions().context("get pepper")?;
     let info = access_token
-        .info(&pool)
+        .info(&pool, &peppers)
         .await
         .context("AccessToken -> AccessTokenInfo")?
         .ok_or(Error::UnAssociatedAccessToken)?;
@@ -79,6 +80,9 @@ pub enum Error {
     #[error("{0}")]
     AccessTokenValidation(#[from] AccessTokenValidationError),
 
+    #[error("{0}")]
+    Secrets(#[from] contextual::Error<crate::secrets::SecretStoreError>),
+
     #[error("{0}")]
     Sqlx(#[from] contextual::Error<sqlx::Error>),
 }
@@ -92,6 +96,7 @@ impl error_kind::ErrorKind for Error {
             }
             Error::UnAssociatedAccessToken => "auth.access-token.unassociated".into(),
             Error::AccessTokenValidation(err) => err.kind(),
+            Error::Secrets(_) => "auth.access-token.secrets".into(),
             Error::Sqlx(_) => "auth.access-token.sqlx".into(),
         }
     }
@@ -116,9 +121,9 @@ impl IntoResponse for Error {
                     .into_response()
             }
             Error::AccessTokenValidation(err) => err.into_response(),
-            Error::Sqlx(_err) => {
+            Error::Secrets(_) | Error::Sqlx(_) => {
                 #[cfg(feature = "tracing")]
-                tracing::error!("{:?}", _err);
+                tracing::error!("{:?}", self);
 
                 StatusCode::INTERNAL_SERVER_ERROR.into_response()
             }
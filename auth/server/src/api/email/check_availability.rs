@@ -35,19 +35,25 @@ pub fn method_router() -> MethodRouter<AppState> {
     responses(
         (status = 200, description = "Email is available"),
         (status = 409, description = "Email already exists"),
-        (status = 400, description = "Invalid email address", body = ErrorResponse),
+        (status = 400, description = "Invalid email address, or one rejected by the email policy", body = ErrorResponse),
         (status = 500, description = "Internal server error"),
     ),
     tag = "check"
 ))]
 #[cfg_attr(feature = "tracing", tracing::instrument(fields(%email), skip_all, ret))]
 pub async fn handler(
-    State(AppState { pool, .. }): State<AppState>,
+    State(AppState {
+        pool,
+        email: email_config,
+        email_policy,
+        ..
+    }): State<AppState>,
     Query(QueryParams { email }): Query<QueryParams>,
 ) -> Result<StatusCode, Error> {
     let email = Email::try_from(email).map_err(Error::InvalidParams)?;
+    email_policy.check(&email)?;
 
-    match super::exists(&pool, &email)
+    match super::exists(&pool, &email, email_config.canonicalization())
         .await
         .context("check email availability")?
     {
@@ -61,6 +67,9 @@ pub enum Error {
     #[error("{0}")]
     InvalidParams(&'static str),
 
+    #[error("{0}")]
+    EmailPolicy(#[from] email::PolicyViolation),
+
     #[error("{0}")]
     Sqlx(#[from] contextual::Error<sqlx::Error>),
 }
@@ -69,6 +78,7 @@ impl error_kind::ErrorKind for Error {
     fn kind(&self) -> String {
         match self {
             Error::InvalidParams(_) => "email.invalid".into(),
+            Error::EmailPolicy(violation) => super::policy_violation_kind(violation).into(),
             Error::Sqlx(_) => "sqlx".into(),
         }
     }
@@ -77,7 +87,7 @@ impl error_kind::ErrorKind for Error {
 impl IntoResponse for Error {
     fn into_response(self) -> axum::response::Response {
         match self {
-            Error::InvalidParams(_) => {
+            Error::InvalidParams(_) | Error::EmailPolicy(_) => {
                 #[cfg(feature = "tracing")]
                 tracing::info!("{:?}", self);
 
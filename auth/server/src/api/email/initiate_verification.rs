@@ -1,7 +1,7 @@
 use std::str::FromStr;
 
 use axum::{
-    Form, Json,
+    Json,
     extract::State,
     response::IntoResponse,
     routing::{MethodRouter, post},
@@ -18,7 +18,7 @@ use serde::Deserialize;
 use super::{
     SendVerificationEmailError, send_verification_email, verification_link, verification_token,
 };
-use crate::AppState;
+use crate::{AppState, core::Payload};
 
 pub const PATH: &str = "/initiate-email-verification";
 
@@ -39,8 +39,10 @@ pub fn method_router() -> MethodRouter<AppState> {
     path = PATH,
     operation_id = PATH,
     request_body(
-        content = RequestBody,
-        content_type = "application/x-www-form-urlencoded",
+        content(
+            (RequestBody = "application/json"),
+            (RequestBody = "application/x-www-form-urlencoded"),
+        ),
     ),
     responses(
         (status = 200, description = "Verification email sent successfully"),
@@ -56,10 +58,13 @@ pub async fn handler(
         pool,
         smtp,
         secrets,
+
+        #[cfg(feature = "metrics")]
+        metrics,
         ..
     }): State<AppState>,
     Host(host): Host,
-    Form(RequestBody { email }): Form<RequestBody>,
+    Payload(RequestBody { email }): Payload<RequestBody>,
 ) -> Result<StatusCode, Error> {
     let email = Email::from_str(&email).map_err(Error::InvalidEmailFormat)?;
 
@@ -82,12 +87,17 @@ pub async fn handler(
         return Ok(StatusCode::OK);
     }
 
-    let hmac_secret = secrets.get("hmac").context("get HMAC key")?;
+    let hmac_key = secrets.current("hmac").context("get HMAC key")?;
     let verification_token = verification_token(email.clone());
-    let verification_link = verification_link(&hmac_secret, &host, &verification_token)
+    let verification_link = verification_link(&hmac_key, &host, verification_token)
         .context("base64 encode email verification link")?;
 
-    let response = send_verification_email(&smtp, &email, &verification_link).await?;
+    let response = send_verification_email(&smtp, &email, &verification_link).await;
+
+    #[cfg(feature = "metrics")]
+    metrics.smtp_sent(&response);
+
+    let response = response?;
     match response.is_positive() {
         true => {
             #[cfg(feature = "tracing")]
@@ -119,7 +129,7 @@ pub enum Error {
     SendVerificationEmail(#[from] SendVerificationEmailError),
 
     #[error("{0}")]
-    Io(#[from] contextual::Error<std::io::Error>),
+    Secrets(#[from] contextual::Error<crate::secrets::SecretStoreError>),
 
     #[error("{0}")]
     Sqlx(#[from] contextual::Error<sqlx::Error>),
@@ -132,7 +142,7 @@ impl ErrorKind for Error {
             Error::UnAssociatedEmail(_) => "email.unassociated".to_string(),
             Error::TokenEncodeError(_) => "email.verification.token.encode".to_string(),
             Error::SendVerificationEmail(_) => "email.verification.send".to_string(),
-            Error::Io(_) => "email.verification.io".to_string(),
+            Error::Secrets(_) => "email.verification.secrets".to_string(),
             Error::Sqlx(_) => "email.verification.sqlx".to_string(),
         }
     }
@@ -162,7 +172,7 @@ impl IntoResponse for Error {
                     .into_response()
             }
             Error::SendVerificationEmail(err) => err.into_response(),
-            Error::TokenEncodeError(_) | Error::Io(_) | Error::Sqlx(_) => {
+            Error::TokenEncodeError(_) | Error::Secrets(_) | Error::Sqlx(_) => {
                 #[cfg(feature = "tracing")]
                 tracing::error!("{:?}", self);
 
@@ -6,25 +6,46 @@ pub mod verify_email;
 #[cfg(feature = "smtp")]
 pub mod initiate_verification;
 
-use email::Email;
+use email::{Canonicalization, Email, PolicyViolation};
 use sqlx::{Executor, Sqlite};
 
+/// Shared by every endpoint that links an address to an account, and by [`check_availability`].
+pub fn policy_violation_kind(violation: &PolicyViolation) -> &'static str {
+    match violation {
+        PolicyViolation::Denied(_) => "email.policy.denied",
+        PolicyViolation::NotAllowed(_) => "email.policy.not-allowed",
+        PolicyViolation::Disposable(_) => "email.policy.disposable",
+    }
+}
+
+/// The token is encrypted with a key derived from `key`, so that the link doesn't reveal the email address.
 #[cfg(feature = "smtp")]
 pub fn verification_link(
-    secret: &[u8],
+    key: &crate::secrets::Secret,
     host: &str,
-    token: &signature::Signed<Email>,
+    token: signature::Encrypted<Email>,
 ) -> Result<String, signature::EncodeError> {
     Ok(format!(
         "{host}/{}?token={}",
         verify_email::PATH,
-        token.encode(secret)?
+        token
+            .with_kid(key.kid.clone())
+            .encrypt(&signature::EncryptionKey::derive(&key.secret))?
     ))
 }
 
+/// Verification tokens are only accepted by [`verify_email`],
+/// not by whatever else verifies tokens signed with the `hmac` key.
+#[cfg(feature = "smtp")]
+pub const VERIFICATION_PURPOSE: &str = "verify-email";
+
+/// Single-use, see [`verify_email`].
 #[cfg(feature = "smtp")]
-pub fn verification_token(email: Email) -> signature::Signed<Email> {
-    signature::Signed::new(email).with_ttl(std::time::Duration::from_secs(60 * 60))
+pub fn verification_token(email: Email) -> signature::Encrypted<Email> {
+    signature::Encrypted::new(email)
+        .with_ttl(std::time::Duration::from_secs(60 * 60))
+        .with_purpose(VERIFICATION_PURPOSE)
+        .with_jti(token::Token::<16>::random().base64encoded())
 }
 
 #[cfg(feature = "smtp")]
@@ -133,12 +154,27 @@ impl axum::response::IntoResponse for SendVerificationEmailError {
     }
 }
 
+/// Compares canonical forms, e.g. `Joe@Example.com` exists once `Joe@example.com` signed up.
+/// Users whose canonical form is missing or of another [`Canonicalization`] (see [`canonicalize_existing`])
+/// are compared as is.
 pub async fn exists<'a, E: Executor<'a, Database = Sqlite>>(
     ex: E,
     email: &Email,
+    canonicalization: Canonicalization,
 ) -> Result<bool, sqlx::Error> {
+    let canonical = email.canonical(canonicalization);
+    let canonical_kind = canonicalization.kind();
+
     let row = sqlx::query_scalar!(
-        r#"SELECT id as "user_id!" FROM users WHERE email = ? LIMIT 1"#,
+        r#"
+        SELECT id as "user_id!" FROM users
+        WHERE (email_canonical = ? AND email_canonical_kind = ?)
+            OR (email_canonical_kind IS NOT ? AND email = ?)
+        LIMIT 1
+        "#,
+        canonical,
+        canonical_kind,
+        canonical_kind,
         email
     )
     .fetch_optional(ex)
@@ -149,3 +185,90 @@ pub async fn exists<'a, E: Executor<'a, Database = Sqlite>>(
         None => Ok(false),
     }
 }
+
+/// Whether inserting or updating a user failed because the email, or its canonical form, is taken.
+/// [`exists`] is checked first, but two signups can still race past it.
+pub fn is_taken(err: &sqlx::Error) -> bool {
+    match err {
+        sqlx::Error::Database(err) => {
+            err.is_unique_violation() && err.message().contains("users.email")
+        }
+        _ => false,
+    }
+}
+
+/// Fills in the canonical form of the users that signed up before it was stored,
+/// and recomputes the ones of another [`Canonicalization`], e.g. after changing `[email] fold_local_part`.
+/// Users whose canonical form is already taken by an older one are left to be compared as is.
+pub async fn canonicalize_existing(pool: sqlx::Pool<Sqlite>, canonicalization: Canonicalization) {
+    let _result = try_canonicalize_existing(&pool, canonicalization).await;
+
+    #[cfg(feature = "tracing")]
+    match _result {
+        Ok(canonicalized) => tracing::info!(canonicalized, "existing emails canonicalized"),
+        Err(err) => tracing::error!("canonicalize existing emails :: {:?}", err),
+    }
+}
+
+async fn try_canonicalize_existing(
+    pool: &sqlx::Pool<Sqlite>,
+    canonicalization: Canonicalization,
+) -> Result<u64, contextual::Error<sqlx::Error>> {
+    use contextual::Context;
+
+    let canonical_kind = canonicalization.kind();
+
+    let users = sqlx::query!(
+        r#"SELECT id as "id!", email FROM users WHERE email_canonical_kind IS NOT ? ORDER BY id"#,
+        canonical_kind
+    )
+    .fetch_all(pool)
+    .await
+    .context("users without canonical email")?;
+
+    if users.is_empty() {
+        return Ok(0);
+    }
+
+    // so that the stale forms don't take the ones recomputed below
+    sqlx::query!(
+        r#"
+        UPDATE users SET email_canonical = NULL, email_canonical_kind = NULL
+        WHERE email_canonical_kind IS NOT ?
+        "#,
+        canonical_kind
+    )
+    .execute(pool)
+    .await
+    .context("clear stale canonical emails")?;
+
+    let mut canonicalized = 0;
+    for user in users {
+        let canonical = Email::try_from_sqlx(user.email)
+            .context("parse stored email")?
+            .canonical(canonicalization);
+
+        let result = sqlx::query!(
+            "UPDATE users SET email_canonical = ?, email_canonical_kind = ? WHERE id = ?",
+            canonical,
+            canonical_kind,
+            user.id
+        )
+        .execute(pool)
+        .await;
+
+        match result {
+            Ok(_) => canonicalized += 1,
+            Err(err) if is_taken(&err) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(
+                    user_id = user.id,
+                    "canonical email already taken by another user"
+                );
+            }
+            Err(err) => return Err(err).context("store canonical email"),
+        }
+    }
+
+    Ok(canonicalized)
+}
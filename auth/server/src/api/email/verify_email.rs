@@ -11,11 +11,16 @@ use error_kind::ErrorKind;
 use error_response::ErrorResponse;
 use http::StatusCode;
 use serde::Deserialize;
+use signature::{DecodeError, Encrypted, EncryptionKey, Signed, Validation, VerifyingKey};
 
-use crate::AppState;
+use super::VERIFICATION_PURPOSE;
+use crate::{AppState, ledger::SqliteLedger};
 
 pub const PATH: &str = "/verify-email";
 
+/// Tolerated between the clocks of the instances that issue and verify tokens.
+const CLOCK_SKEW: std::time::Duration = std::time::Duration::from_secs(30);
+
 #[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
 #[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
 #[derive(Deserialize)]
@@ -36,6 +41,7 @@ pub fn method_router() -> MethodRouter<AppState> {
         (status = 200, description = "Email verified successfully"),
         (status = 400, description = "Invalid or malformed token", body = ErrorResponse),
         (status = 404, description = "Token not found", body = ErrorResponse),
+        (status = 409, description = "Token already used", body = ErrorResponse),
         (status = 410, description = "Token expired", body = ErrorResponse),
         (status = 500, description = "Internal server error"),
     ),
@@ -49,18 +55,76 @@ pub async fn handler(
         token: token_base64_encoded,
     }): Query<QueryParams>,
 ) -> Result<StatusCode, Error> {
-    let hmac_secret = secrets.get("hmac").context("get HMAC key")?;
-    let signed_token = signature::Signed::<Email>::decode(&token_base64_encoded, &hmac_secret)?;
-    let email = signed_token.token()?;
+    // tokens signed before keys were versioned have no `kid` and are checked against every version
+    let hmac_keys = secrets.versions("hmac").context("get HMAC keys")?;
+    let validation = Validation::new()
+        .with_purpose(VERIFICATION_PURPOSE)
+        .with_leeway(CLOCK_SKEW);
+    let hmac_keys_for = |kid: Option<&str>| {
+        hmac_keys
+            .iter()
+            .filter(|key| kid.is_none_or(|kid| kid == key.kid))
+            .collect::<Vec<_>>()
+    };
+
+    let token: Signed<Email> = match Encrypted::<Email>::decrypt_with(
+        &token_base64_encoded,
+        |kid| {
+            hmac_keys_for(kid)
+                .into_iter()
+                .map(|key| EncryptionKey::derive(&key.secret))
+                .collect::<Vec<_>>()
+        },
+        &validation,
+    ) {
+        Ok(encrypted_token) => encrypted_token.into(),
+
+        // links sent before verification tokens were encrypted are signed ones
+        Err(DecodeError::InvalidFormat) => Signed::<Email>::verify_with(
+            &token_base64_encoded,
+            |kid| {
+                hmac_keys_for(kid)
+                    .into_iter()
+                    .map(|key| VerifyingKey::hs256(&key.secret))
+                    .collect::<Vec<_>>()
+            },
+            &validation,
+        )?,
+
+        Err(err) => return Err(err.into()),
+    };
+
+    // the link is only used up along with the email being verified
+    let mut tx = pool
+        .begin()
+        .await
+        .context("begin transaction :: verify email")?;
+
+    // links sent before verification tokens had a `jti` can't be recorded, and stop working once they expire
+    let email = match token.jti() {
+        Some(_) => {
+            let conn = &mut *tx;
+            token
+                .consume_with(&validation, |jti, exp| async move {
+                    Ok(SqliteLedger::consume_in(conn, &jti, exp).await?)
+                })
+                .await?
+        }
+        None => token.token_with(&validation)?,
+    };
 
     #[cfg(feature = "tracing")]
     tracing::Span::current().record("email", tracing::field::display(&email));
 
     sqlx::query!("UPDATE users SET email_verified = 1 WHERE email = ?", email)
-        .execute(&pool)
+        .execute(&mut *tx)
         .await
         .context("user email_verified")?;
 
+    tx.commit()
+        .await
+        .context("commit transaction :: verify email")?;
+
     Ok(StatusCode::OK)
 }
 
@@ -73,7 +137,10 @@ pub enum Error {
     TemporalTokenValidity(#[from] signature::TemporalValidityError),
 
     #[error("{0}")]
-    Io(#[from] contextual::Error<std::io::Error>),
+    Consume(#[from] signature::ConsumeError),
+
+    #[error("{0}")]
+    Secrets(#[from] contextual::Error<crate::secrets::SecretStoreError>),
 
     #[error("{0}")]
     Sqlx(#[from] contextual::Error<sqlx::Error>),
@@ -84,7 +151,8 @@ impl ErrorKind for Error {
         match self {
             Error::TokenDecode(_) => "token.decode".to_string(),
             Error::TemporalTokenValidity(_) => "token.validity".to_string(),
-            Error::Io(_) => "io".to_string(),
+            Error::Consume(_) => "token.consume".to_string(),
+            Error::Secrets(_) => "secrets".to_string(),
             Error::Sqlx(_) => "sqlx".to_string(),
         }
     }
@@ -108,6 +176,11 @@ impl IntoResponse for Error {
                         .into_response()
                 }
                 signature::DecodeError::MacMismatch(_)
+                | signature::DecodeError::SignatureMismatch
+                | signature::DecodeError::Decrypt
+                | signature::DecodeError::UnknownKid(_)
+                | signature::DecodeError::UnexpectedAlg(_)
+                | signature::DecodeError::Claim(_)
                 | signature::DecodeError::NonUTF8(_)
                 | signature::DecodeError::Serde(_)
                 | signature::DecodeError::Base64(_)
@@ -144,7 +217,31 @@ impl IntoResponse for Error {
                 )
                     .into_response()
             }
-            Error::Io(_) | Error::Sqlx(_) => {
+            Error::Consume(signature::ConsumeError::TemporalValidity(err)) => {
+                Error::TemporalTokenValidity(err).into_response()
+            }
+            Error::Consume(_err @ signature::ConsumeError::Reused(_)) => {
+                #[cfg(feature = "tracing")]
+                tracing::info!("{:?}", _err);
+
+                (
+                    StatusCode::CONFLICT,
+                    Json(
+                        ErrorResponse::new("Token already used".to_string())
+                            .with_kind("token.reused".to_string()),
+                    ),
+                )
+                    .into_response()
+            }
+            Error::Consume(
+                _err @ (signature::ConsumeError::MissingJti | signature::ConsumeError::Ledger(_)),
+            ) => {
+                #[cfg(feature = "tracing")]
+                tracing::error!("{:?}", _err);
+
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+            Error::Secrets(_) | Error::Sqlx(_) => {
                 #[cfg(feature = "tracing")]
                 tracing::error!("{:?}", self);
 
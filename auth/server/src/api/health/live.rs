@@ -0,0 +1,29 @@
+use axum::{
+    http::StatusCode,
+    routing::{MethodRouter, get},
+};
+use axum_macros::debug_handler;
+
+use crate::AppState;
+
+pub const PATH: &str = "/health/live";
+
+pub fn method_router() -> MethodRouter<AppState> {
+    get(handler)
+}
+
+/// The process is up and serving requests.
+/// Dependencies are deliberately not checked (see [`super::ready`]),
+/// so that a database outage doesn't get the pod restarted.
+#[debug_handler]
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = PATH,
+    operation_id = PATH,
+    responses((status = 200, description = "Alive")),
+    tag = "probe"
+))]
+#[cfg_attr(feature = "tracing", tracing::instrument(ret))]
+pub async fn handler() -> StatusCode {
+    StatusCode::OK
+}
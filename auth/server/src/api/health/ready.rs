@@ -0,0 +1,190 @@
+use std::{collections::BTreeMap, future::Future, time::Duration};
+
+use axum::{
+    Json,
+    extract::State,
+    routing::{MethodRouter, get},
+};
+use axum_macros::debug_handler;
+use contextual::Context;
+use http::StatusCode;
+use serde::Serialize;
+use sqlx::migrate::Migrator;
+
+use crate::{AppState, core::PEPPER, secrets::Secrets};
+
+pub const PATH: &str = "/health/ready";
+
+/// A check that takes longer than this counts as failed,
+/// so that a hung dependency can't hang the probe along with it.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+pub fn method_router() -> MethodRouter<AppState> {
+    get(handler)
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "openapi", schema(as = health::Readiness))]
+#[derive(Debug, Serialize)]
+pub struct Readiness {
+    pub ready: bool,
+
+    /// `shutdown`, `database`, `migrations`, `hmac`, `pepper` and, with the `smtp` feature, `smtp`
+    pub checks: BTreeMap<String, Check>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "openapi", schema(as = health::Check))]
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum Check {
+    Ok,
+    Failed { reason: String },
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = PATH,
+    operation_id = PATH,
+    responses(
+        (status = 200, description = "Ready to serve traffic", body = Readiness),
+        (status = 503, description = "A dependency is unavailable or the server is shutting down", body = Readiness),
+    ),
+    tag = "probe"
+))]
+#[debug_handler]
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub async fn handler(
+    State(AppState {
+        pool,
+        secrets,
+        shutdown,
+        #[cfg(feature = "smtp")]
+        smtp,
+        ..
+    }): State<AppState>,
+) -> (StatusCode, Json<Readiness>) {
+    let mut checks = BTreeMap::new();
+
+    checks.insert(
+        "shutdown".to_string(),
+        match shutdown.is_triggered() {
+            true => Check::Failed {
+                reason: "draining".into(),
+            },
+            false => Check::Ok,
+        },
+    );
+    checks.insert("database".to_string(), run(database(&pool)).await);
+    checks.insert("migrations".to_string(), run(migrations(&pool)).await);
+    checks.insert("hmac".to_string(), run(secret(&secrets, "hmac")).await);
+    checks.insert(PEPPER.to_string(), run(secret(&secrets, PEPPER)).await);
+
+    #[cfg(feature = "smtp")]
+    checks.insert("smtp".to_string(), run(smtp_relay(&smtp)).await);
+
+    let ready = checks.values().all(|check| matches!(check, Check::Ok));
+    let status = match ready {
+        true => StatusCode::OK,
+        false => StatusCode::SERVICE_UNAVAILABLE,
+    };
+
+    (status, Json(Readiness { ready, checks }))
+}
+
+async fn run(f: impl Future<Output = Result<(), Error>>) -> Check {
+    let result = match tokio::time::timeout(CHECK_TIMEOUT, f).await {
+        Ok(result) => result,
+        Err(_) => Err(Error::Timeout(CHECK_TIMEOUT)),
+    };
+
+    match result {
+        Ok(()) => Check::Ok,
+        Err(err) => {
+            #[cfg(feature = "tracing")]
+            tracing::warn!("readiness check failed: {:?}", err);
+
+            Check::Failed {
+                reason: err.to_string(),
+            }
+        }
+    }
+}
+
+async fn database(pool: &sqlx::Pool<sqlx::Sqlite>) -> Result<(), Error> {
+    sqlx::query("SELECT 1")
+        .execute(pool)
+        .await
+        .context("ping database")?;
+    Ok(())
+}
+
+/// The database must be at the latest migration this binary was built with,
+/// otherwise queries may hit tables or columns that don't exist yet.
+async fn migrations(pool: &sqlx::Pool<sqlx::Sqlite>) -> Result<(), Error> {
+    let expected = MIGRATOR
+        .iter()
+        .map(|migration| migration.version)
+        .max()
+        .unwrap_or_default();
+
+    // not `query_scalar!` because `_sqlx_migrations` is created by the migrator, not by a migration
+    let applied: Option<i64> =
+        sqlx::query_scalar("SELECT MAX(version) FROM _sqlx_migrations WHERE success = TRUE")
+            .fetch_one(pool)
+            .await
+            .context("applied migration version")?;
+
+    match applied == Some(expected) {
+        true => Ok(()),
+        false => Err(Error::MigrationVersion { applied, expected }),
+    }
+}
+
+async fn secret(secrets: &Secrets, key: &str) -> Result<(), Error> {
+    match secrets.get(key).context(format!("get `{key}`"))?.is_empty() {
+        true => Err(Error::EmptySecret(key.to_string())),
+        false => Ok(()),
+    }
+}
+
+#[cfg(feature = "smtp")]
+async fn smtp_relay(smtp: &crate::smtp::Smtp) -> Result<(), Error> {
+    match smtp
+        .transport
+        .test_connection()
+        .await
+        .context("connect smtp relay")?
+    {
+        true => Ok(()),
+        false => Err(Error::SmtpUnavailable),
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+enum Error {
+    #[error("{0}")]
+    Sqlx(#[from] contextual::Error<sqlx::Error>),
+
+    #[error("expected migration version {expected}, found {applied:?}")]
+    MigrationVersion { applied: Option<i64>, expected: i64 },
+
+    #[error("{0}")]
+    Secrets(#[from] contextual::Error<crate::secrets::SecretStoreError>),
+
+    #[error("`{0}` is empty")]
+    EmptySecret(String),
+
+    #[cfg(feature = "smtp")]
+    #[error("{0}")]
+    Smtp(#[from] contextual::Error<lettre::transport::smtp::Error>),
+
+    #[cfg(feature = "smtp")]
+    #[error("smtp relay did not respond to NOOP")]
+    SmtpUnavailable,
+
+    #[error("timed out after {0:?}")]
+    Timeout(Duration),
+}
@@ -1,15 +1,20 @@
+use std::time::Duration;
+
 use axum::{
-    Form,
+    Json,
     extract::State,
     response::IntoResponse,
     routing::{MethodRouter, post},
 };
+use error_kind::ErrorKind;
+use error_response::ErrorResponse;
 use http::StatusCode;
 use serde::Deserialize;
 
 use crate::{
     AppState,
-    core::{InsufficientPermissionsError, Principal},
+    core::{InsufficientPermissionsError, Payload, Principal},
+    secrets::SecretStoreError,
 };
 
 pub const PATH: &str = "/rotate-key";
@@ -18,10 +23,16 @@ pub const PATH: &str = "/rotate-key";
 #[cfg_attr(feature = "openapi", schema(as = key_rotation::RequestBody))]
 #[derive(Deserialize)]
 pub struct RequestBody {
-    #[cfg_attr(feature = "openapi", schema(example = "HMAC"))]
+    #[cfg_attr(feature = "openapi", schema(example = "hmac"))]
     pub key: String,
+
+    /// How long whatever was signed with the replaced version stays valid. Defaults to a day.
+    #[cfg_attr(feature = "openapi", schema(example = 86400))]
+    pub grace_period_secs: Option<u64>,
 }
 
+const DEFAULT_GRACE_PERIOD: Duration = Duration::from_secs(24 * 60 * 60);
+
 pub fn method_router() -> MethodRouter<AppState> {
     post(handler)
 }
@@ -31,13 +42,17 @@ pub fn method_router() -> MethodRouter<AppState> {
     path = PATH,
     operation_id = PATH,
     request_body(
-        content = RequestBody,
-        content_type = "application/x-www-form-urlencoded",
+        content(
+            (RequestBody = "application/json"),
+            (RequestBody = "application/x-www-form-urlencoded"),
+        ),
     ),
     responses(
         (status = 200, description = "Successfull Key Rotation"),
+        (status = 400, description = "Invalid key name", body = error_response::ErrorResponse),
         (status = 401, description = "Invalid credentials", body = error_response::ErrorResponse),
         (status = 403, description = "Insufficient permissions", body = error_response::ErrorResponse),
+        (status = 409, description = "Read-only secret store", body = error_response::ErrorResponse),
         (status = 500, description = "Internal server error"),
     ),
     tag = "secrets"
@@ -46,13 +61,24 @@ pub fn method_router() -> MethodRouter<AppState> {
 pub async fn handler(
     State(AppState { pool, secrets, .. }): State<AppState>,
     principal: Principal,
-    Form(RequestBody { key }): Form<RequestBody>,
+    Payload(RequestBody {
+        key,
+        grace_period_secs,
+    }): Payload<RequestBody>,
 ) -> Result<StatusCode, Error> {
     principal
         .require_permission::<Error>(&pool, "post:/rotate-key")
         .await?;
 
-    secrets.reset(&key)?;
+    let grace_period = grace_period_secs
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_GRACE_PERIOD);
+
+    let _current = secrets.rotate(&key, grace_period)?;
+
+    #[cfg(feature = "tracing")]
+    tracing::info!(kid = %_current.kid, ?grace_period, "key rotated");
+
     Ok(StatusCode::OK)
 }
 
@@ -62,17 +88,49 @@ pub enum Error {
     InsufficientPermissions(#[from] InsufficientPermissionsError),
 
     #[error("{0}")]
-    Io(#[from] std::io::Error),
+    Secrets(#[from] SecretStoreError),
 
     #[error("{0}")]
     Sqlx(#[from] contextual::Error<sqlx::Error>),
 }
 
+impl ErrorKind for Error {
+    fn kind(&self) -> String {
+        match self {
+            Error::InsufficientPermissions(err) => err.kind(),
+            Error::Secrets(SecretStoreError::InvalidName(_)) => "secrets.invalid-name".to_string(),
+            Error::Secrets(SecretStoreError::ReadOnly) => "secrets.read-only".to_string(),
+            Error::Secrets(_) => "secrets".to_string(),
+            Error::Sqlx(_) => "sqlx".to_string(),
+        }
+    }
+}
+
 impl IntoResponse for Error {
     fn into_response(self) -> axum::response::Response {
         match self {
             Error::InsufficientPermissions(err) => err.into_response(),
-            Error::Io(_) | Error::Sqlx(_) => {
+            Error::Secrets(SecretStoreError::InvalidName(_)) => {
+                #[cfg(feature = "tracing")]
+                tracing::info!("{:?}", self);
+
+                (
+                    StatusCode::BAD_REQUEST,
+                    Json(ErrorResponse::new(self.to_string()).with_kind(self.kind())),
+                )
+                    .into_response()
+            }
+            Error::Secrets(SecretStoreError::ReadOnly) => {
+                #[cfg(feature = "tracing")]
+                tracing::info!("{:?}", self);
+
+                (
+                    StatusCode::CONFLICT,
+                    Json(ErrorResponse::new(self.to_string()).with_kind(self.kind())),
+                )
+                    .into_response()
+            }
+            Error::Secrets(_) | Error::Sqlx(_) => {
                 #[cfg(feature = "tracing")]
                 tracing::error!("{:?}", self);
 
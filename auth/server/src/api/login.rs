@@ -1,5 +1,4 @@
 use axum::{
-    Form,
     extract::State,
     http::{HeaderMap, StatusCode, header::USER_AGENT},
     response::{IntoResponse, Response},
@@ -10,12 +9,14 @@ use axum_macros::debug_handler;
 use bcrypt::verify;
 use contextual::Context;
 use serde::Deserialize;
-use time::{Duration, OffsetDateTime};
+use time::OffsetDateTime;
 
-use crate::{AppState, core::SessionId};
+use crate::{
+    AppState,
+    core::{Payload, SessionId},
+};
 
 pub const PATH: &str = "/login";
-const COOKIE_DURATION: Duration = Duration::days(30);
 
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[cfg_attr(feature = "openapi", schema(as = login::Credentials))]
@@ -33,6 +34,9 @@ pub enum Error {
     #[error("invalid credentials")]
     InvalidCredentials,
 
+    #[error("{0}")]
+    Secrets(#[from] contextual::Error<crate::secrets::SecretStoreError>),
+
     #[error("{0}")]
     Sqlx(#[from] contextual::Error<sqlx::Error>),
 
@@ -49,8 +53,10 @@ pub fn method_router() -> MethodRouter<AppState> {
     path = PATH,
     operation_id = PATH,
     request_body(
-        content = Credentials,
-        content_type = "application/x-www-form-urlencoded",
+        content(
+            (Credentials = "application/json"),
+            (Credentials = "application/x-www-form-urlencoded"),
+        ),
     ),
     responses(
         (status = 200, description = "Login successful, session cookie set"),
@@ -62,10 +68,18 @@ pub fn method_router() -> MethodRouter<AppState> {
 #[debug_handler]
 #[cfg_attr(feature = "tracing", tracing::instrument(fields(%username), skip_all))]
 pub async fn handler(
-    State(AppState { pool, .. }): State<AppState>,
+    State(AppState {
+        pool,
+        peppers,
+        cookie,
+
+        #[cfg(feature = "metrics")]
+        metrics,
+        ..
+    }): State<AppState>,
     headers: HeaderMap,
     jar: CookieJar,
-    Form(Credentials { username, password }): Form<Credentials>,
+    Payload(Credentials { username, password }): Payload<Credentials>,
 ) -> Result<(CookieJar, StatusCode), Error> {
     #[derive(Debug, Clone)]
     struct User {
@@ -73,6 +87,9 @@ pub async fn handler(
         password_hash: String,
     }
 
+    // as stored on signup, see `validation::UsernamePolicy::check`
+    let username = validation::normalize_username(&username);
+
     let user = sqlx::query_as!(
         User,
         r#"SELECT id as "id!", password_hash FROM users WHERE username = ?"#,
@@ -81,21 +98,28 @@ pub async fn handler(
     .fetch_optional(&pool)
     .await;
 
-    let user = user
-        .context("username -> User { id, password_hash }")?
-        .ok_or(Error::InvalidCredentials)?;
+    let Some(user) = user.context("username -> User { id, password_hash }")? else {
+        #[cfg(feature = "metrics")]
+        metrics.login_failed();
+
+        return Err(Error::InvalidCredentials);
+    };
 
     #[cfg(feature = "tracing")]
     tracing::info!("user_id={}", user.id);
 
     if !verify(password, &user.password_hash).context("verify password hash")? {
+        #[cfg(feature = "metrics")]
+        metrics.login_failed();
+
         return Err(Error::InvalidCredentials);
     };
 
     let session_id = SessionId::new();
-    let session_id_hash = session_id.hash_sha256();
+    let pepper = peppers.current().context("get pepper")?;
+    let session_id_hash = session_id.hash_hmac_sha256(&pepper.secret);
     let created_at = OffsetDateTime::now_utc();
-    let expires_at = created_at + COOKIE_DURATION;
+    let expires_at = created_at + cookie.max_age();
     let user_agent = headers.get(USER_AGENT).and_then(|val| val.to_str().ok());
 
     sqlx::query!(
@@ -117,9 +141,12 @@ pub async fn handler(
     #[cfg(feature = "tracing")]
     tracing::info!(?expires_at, ?user_agent, "session created");
 
-    let session_cookie = session_id.into_cookie(COOKIE_DURATION);
+    let session_cookie = session_id.into_cookie(&cookie);
     let jar = jar.add(session_cookie);
 
+    #[cfg(feature = "metrics")]
+    metrics.login_succeeded();
+
     Ok((jar, StatusCode::OK))
 }
 
@@ -132,7 +159,7 @@ impl IntoResponse for Error {
 
                 StatusCode::UNAUTHORIZED.into_response()
             }
-            Error::Sqlx(_) | Error::Bcrypt(_) => {
+            Error::Secrets(_) | Error::Sqlx(_) | Error::Bcrypt(_) => {
                 #[cfg(feature = "tracing")]
                 tracing::error!("{:?}", self);
 
@@ -3,17 +3,23 @@ use axum::{extract::State, response::IntoResponse};
 use axum_extra::extract::CookieJar;
 use axum_macros::debug_handler;
 use contextual::Context;
-use http::{HeaderMap, StatusCode};
+use http::{HeaderMap, Method, StatusCode};
 
 use crate::{
     AppState,
-    core::{Credentials, SessionId, expired_session_cookie},
+    core::{Credentials, CsrfError, LookupHashes, SessionId, expired_session_cookie},
 };
 
 pub const PATH: &str = "/logout";
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
+    #[error("{0}")]
+    Csrf(#[from] CsrfError),
+
+    #[error("{0}")]
+    Secrets(#[from] contextual::Error<crate::secrets::SecretStoreError>),
+
     #[error("{0}")]
     Sqlx(#[from] contextual::Error<sqlx::Error>),
 }
@@ -26,32 +32,50 @@ pub fn method_router() -> MethodRouter<AppState> {
     post,
     path = PATH,
     operation_id = PATH,
-    responses((status = 200, description = "Session invalidated and Cookie removed")),
+    responses(
+        (status = 200, description = "Session invalidated and Cookie removed"),
+        (status = 403, description = "Cross-site request rejected", body = error_response::ErrorResponse),
+    ),
     tag = "auth"
 ))]
 #[cfg_attr(feature = "tracing", tracing::instrument(fields(user_id = tracing::field::Empty), skip_all))]
 #[debug_handler]
 pub async fn handler(
-    State(AppState { pool, .. }): State<AppState>,
+    State(AppState {
+        pool,
+        peppers,
+        csrf,
+        cookie,
+        ..
+    }): State<AppState>,
     headers: HeaderMap,
     jar: CookieJar,
 ) -> Result<(StatusCode, CookieJar), Error> {
     if let Ok(Some(session_id)) = SessionId::try_from_headers(&headers) {
-        let session_id_hash = session_id.hash_sha256();
+        csrf.verify(&Method::POST, &headers)?;
 
-        let _record = sqlx::query!(
-            r#"
-            DELETE FROM sessions WHERE session_id_hash = ?
-            RETURNING user_id
-            "#,
-            session_id_hash
-        )
-        .fetch_optional(&pool)
-        .await
-        .context("delete session")?;
+        let peppers = peppers.versions().context("get pepper")?;
+
+        let mut record = None;
+        for session_id_hash in LookupHashes::new(&*session_id, &peppers).all() {
+            record = sqlx::query!(
+                r#"
+                DELETE FROM sessions WHERE session_id_hash = ?
+                RETURNING user_id
+                "#,
+                session_id_hash
+            )
+            .fetch_optional(&pool)
+            .await
+            .context("delete session")?;
+
+            if record.is_some() {
+                break;
+            }
+        }
 
         #[cfg(feature = "tracing")]
-        match _record {
+        match record {
             Some(record) => {
                 tracing::Span::current().record("user_id", tracing::field::display(record.user_id));
                 tracing::info!("session invalidated")
@@ -60,16 +84,17 @@ pub async fn handler(
         };
     }
 
-    let jar = jar.add(expired_session_cookie());
+    let jar = jar.add(expired_session_cookie(&cookie));
     Ok((StatusCode::OK, jar))
 }
 
 impl IntoResponse for Error {
     fn into_response(self) -> axum::response::Response {
         match self {
-            Error::Sqlx(_err) => {
+            Error::Csrf(err) => err.into_response(),
+            Error::Secrets(_) | Error::Sqlx(_) => {
                 #[cfg(feature = "tracing")]
-                tracing::error!("{:?}", _err);
+                tracing::error!("{:?}", self);
 
                 StatusCode::INTERNAL_SERVER_ERROR.into_response()
             }
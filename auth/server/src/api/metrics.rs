@@ -0,0 +1,71 @@
+use axum::{
+    extract::State,
+    response::IntoResponse,
+    routing::{MethodRouter, get},
+};
+use axum_macros::debug_handler;
+use http::{StatusCode, header::CONTENT_TYPE};
+
+use crate::{
+    AppState,
+    core::{InsufficientPermissionsError, Principal},
+    metrics::MetricsError,
+};
+
+pub const PATH: &str = "/metrics";
+
+pub fn method_router() -> MethodRouter<AppState> {
+    get(handler)
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = PATH,
+    operation_id = PATH,
+    responses(
+        (status = 200, description = "Metrics in the Prometheus text format", content_type = "text/plain"),
+        (status = 401, description = "Invalid credentials", body = error_response::ErrorResponse),
+        (status = 403, description = "Insufficient permissions", body = error_response::ErrorResponse),
+        (status = 500, description = "Internal server error"),
+    ),
+    tag = "probe"
+))]
+#[debug_handler]
+#[cfg_attr(feature = "tracing", tracing::instrument(fields(%principal), skip_all))]
+pub async fn handler(
+    State(AppState { pool, metrics, .. }): State<AppState>,
+    principal: Principal,
+) -> Result<impl IntoResponse, Error> {
+    principal
+        .require_permission::<Error>(&pool, "get:/metrics")
+        .await?;
+
+    let body = metrics.render(&pool).await?;
+    Ok(([(CONTENT_TYPE, prometheus::TEXT_FORMAT)], body))
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("{0}")]
+    InsufficientPermissions(#[from] InsufficientPermissionsError),
+
+    #[error("{0}")]
+    Sqlx(#[from] contextual::Error<sqlx::Error>),
+
+    #[error("{0}")]
+    Metrics(#[from] MetricsError),
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> axum::response::Response {
+        match self {
+            Error::InsufficientPermissions(err) => err.into_response(),
+            Error::Sqlx(_) | Error::Metrics(_) => {
+                #[cfg(feature = "tracing")]
+                tracing::error!("{:?}", self);
+
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
+    }
+}
@@ -1,10 +1,13 @@
 pub mod access_token;
 pub mod email;
+pub mod health;
 pub mod heartbeat;
 pub mod introspect;
 pub mod key_rotation;
 pub mod login;
 pub mod logout;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 pub mod permissions;
 pub mod private;
 pub mod signup;
@@ -21,6 +24,8 @@ pub const OPEN_API_DOCS_PATH: &str = "/api-docs/openapi.json";
         access_token::generate::handler,
         access_token::verify::handler,
         email::check_availability::handler,
+        health::live::handler,
+        health::ready::handler,
         heartbeat::handler,
         key_rotation::handler,
         login::handler,
@@ -34,6 +39,8 @@ pub const OPEN_API_DOCS_PATH: &str = "/api-docs/openapi.json";
     components(schemas(
         access_token::generate::Config,
         crate::core::Permission,
+        health::ready::Check,
+        health::ready::Readiness,
         key_rotation::RequestBody,
         login::Credentials,
         permissions::assign::RequestBody,
@@ -48,6 +55,11 @@ struct OpenApiDoc;
 #[openapi(paths(email::verify_email::handler, email::initiate_verification::handler,))]
 struct SmtpOpenApiDoc;
 
+#[cfg(all(feature = "openapi", feature = "metrics"))]
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(metrics::handler))]
+struct MetricsOpenApiDoc;
+
 #[cfg(feature = "openapi")]
 pub fn openapi() -> utoipa::openapi::OpenApi {
     use utoipa::OpenApi;
@@ -58,5 +70,8 @@ pub fn openapi() -> utoipa::openapi::OpenApi {
     #[cfg(feature = "smtp")]
     openapi.merge(SmtpOpenApiDoc::openapi());
 
+    #[cfg(feature = "metrics")]
+    openapi.merge(MetricsOpenApiDoc::openapi());
+
     openapi
 }
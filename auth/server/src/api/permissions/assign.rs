@@ -4,6 +4,7 @@ use axum::{
     http::StatusCode,
     routing::{MethodRouter, post},
 };
+use client_ip::ClientIp;
 use contextual::Context;
 use error_kind::ErrorKind;
 use error_response::ErrorResponse;
@@ -12,7 +13,7 @@ use time::OffsetDateTime;
 
 use crate::{
     AppState, HELP,
-    core::{InsufficientPermissionsError, Principal},
+    core::{InsufficientPermissionsError, Payload, Principal},
 };
 
 // TODO: mark this as admin endpoint. maybe using tags
@@ -21,6 +22,7 @@ pub const PATH: &str = "/permissions";
 
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Deserialize)]
+#[serde(try_from = "RequestBodyRepr")]
 pub struct RequestBody {
     pub permission: String,
     pub assignee: Assignee,
@@ -43,6 +45,31 @@ pub enum Assignee {
     },
 }
 
+/// `application/json` nests the assignee (`{"assignee": {"user": {"username": "joe"}}}`)
+/// but `application/x-www-form-urlencoded` cannot, so forms name the assignee type
+/// and spread its fields instead (`permission=...&assignee=user&username=joe`).
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RequestBodyRepr {
+    Nested {
+        permission: String,
+        assignee: Assignee,
+    },
+    Flat {
+        permission: String,
+        assignee: AssigneeType,
+        username: String,
+        token_name: Option<String>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum AssigneeType {
+    AccessToken,
+    User,
+}
+
 pub fn method_router() -> MethodRouter<AppState> {
     post(handler)
 }
@@ -50,7 +77,12 @@ pub fn method_router() -> MethodRouter<AppState> {
 #[cfg_attr(feature = "openapi", utoipa::path(
     post,
     path = PATH,
-    request_body = RequestBody,
+    request_body(
+        content(
+            (RequestBody = "application/json"),
+            (RequestBody = "application/x-www-form-urlencoded"),
+        ),
+    ),
     responses(
         (status = 201, description = "Permission assigned successfully"),
         (status = 400, description = "Invalid request"),
@@ -64,7 +96,8 @@ pub fn method_router() -> MethodRouter<AppState> {
 pub async fn handler(
     State(AppState { pool, .. }): State<AppState>,
     principal: Principal,
-    Json(request_body): Json<RequestBody>,
+    client_ip: Option<ClientIp>,
+    Payload(request_body): Payload<RequestBody>,
 ) -> Result<StatusCode, Error> {
     principal
         .require_permission::<Error>(&pool, "post:/permissions")
@@ -144,6 +177,7 @@ pub async fn handler(
     };
 
     let now = OffsetDateTime::now_utc();
+    let ip = client_ip.map(|ClientIp(ip)| ip.to_string());
     sqlx::query!(
         r#"
         INSERT INTO permissions_audit_log
@@ -154,9 +188,10 @@ pub async fn handler(
             assignee_id,
             permission_id,
             action,
-            datetime
+            datetime,
+            ip
         )
-        VALUES (?, ?, ?, ?, ?, ?, ?)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
         "#,
         assigner_type,
         assigner_id,
@@ -164,7 +199,8 @@ pub async fn handler(
         assignee_id,
         permission_id,
         "assign",
-        now
+        now,
+        ip
     )
     .execute(&mut *tx)
     .await
@@ -192,6 +228,43 @@ pub enum Error {
     Sqlx(#[from] contextual::Error<sqlx::Error>),
 }
 
+impl TryFrom<RequestBodyRepr> for RequestBody {
+    type Error = &'static str;
+
+    fn try_from(repr: RequestBodyRepr) -> Result<Self, Self::Error> {
+        match repr {
+            RequestBodyRepr::Nested {
+                permission,
+                assignee,
+            } => Ok(RequestBody {
+                permission,
+                assignee,
+            }),
+            RequestBodyRepr::Flat {
+                permission,
+                assignee: AssigneeType::User,
+                username,
+                ..
+            } => Ok(RequestBody {
+                permission,
+                assignee: Assignee::User { username },
+            }),
+            RequestBodyRepr::Flat {
+                permission,
+                assignee: AssigneeType::AccessToken,
+                username,
+                token_name,
+            } => Ok(RequestBody {
+                permission,
+                assignee: Assignee::AccessToken {
+                    username,
+                    token_name: token_name.ok_or("missing field `token_name`")?,
+                },
+            }),
+        }
+    }
+}
+
 impl error_kind::ErrorKind for Error {
     fn kind(&self) -> String {
         match self {
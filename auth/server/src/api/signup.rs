@@ -1,5 +1,5 @@
 use axum::{
-    Form, Json,
+    Json,
     extract::State,
     http::StatusCode,
     response::{IntoResponse, Response},
@@ -10,31 +10,34 @@ use email::Email;
 use error_kind::ErrorKind;
 use error_response::ErrorResponse;
 use serde::Deserialize;
-use validation::{validate_password, validate_username};
+use validation::Validate;
 
-use crate::{AppState, HELP, core::assign_permission_group};
+use crate::{
+    AppState, HELP,
+    core::{Payload, Validated, assign_permission_group},
+};
 
 pub const PATH: &str = "/signup";
 
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[cfg_attr(feature = "openapi", schema(as = signup::RequestBody))]
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
 pub struct RequestBody {
+    #[validate(username)]
     #[cfg_attr(feature = "openapi", schema(examples("joe")))]
     pub username: String,
 
+    #[validate(email)]
     #[cfg_attr(feature = "openapi", schema(examples("joe@smith.com")))]
     pub email: String,
 
+    #[validate(password(user_inputs(username, email)))]
     #[cfg_attr(feature = "openapi", schema(examples("h?P7o]37")))]
     pub password: String,
 }
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
-    #[error("{0}")]
-    InvalidUsername(&'static str),
-
     #[error("username `{0}` is not available")]
     UsernameExists(String),
 
@@ -44,9 +47,6 @@ pub enum Error {
     #[error("email `{0}` already linked to another account")]
     EmailExists(Email),
 
-    #[error("{0}")]
-    WeakPassword(&'static str),
-
     #[error("{0}")]
     Sqlx(#[from] contextual::Error<sqlx::Error>),
 
@@ -63,12 +63,14 @@ pub fn method_router() -> MethodRouter<AppState> {
     path = PATH,
     operation_id = PATH,
     request_body(
-        content = RequestBody,
-        content_type = "application/x-www-form-urlencoded",
+        content(
+            (RequestBody = "application/json"),
+            (RequestBody = "application/x-www-form-urlencoded"),
+        ),
     ),
     responses(
         (status = 201, description = "User created"),
-        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 400, description = "Invalid input, every invalid field is listed in `fields`", body = ErrorResponse),
         (status = 409, description = "Username or email already exists", body = ErrorResponse),
         (status = 500, description = "Internal server error"),
     ),
@@ -78,35 +80,49 @@ pub fn method_router() -> MethodRouter<AppState> {
 pub async fn handler(
     State(AppState {
         pool,
+        email: email_config,
+        username_policy,
 
         #[cfg(feature = "smtp")]
         secrets,
 
         #[cfg(feature = "smtp")]
         smtp,
+
+        #[cfg(feature = "smtp")]
+        tasks,
+
+        #[cfg(all(feature = "smtp", feature = "metrics"))]
+        metrics,
         ..
     }): State<AppState>,
     #[cfg(feature = "smtp")] axum_extra::extract::Host(host): axum_extra::extract::Host,
-    Form(RequestBody {
+    Validated(Payload(RequestBody {
         username,
         email,
         password,
-    }): Form<RequestBody>,
+    })): Validated<Payload<RequestBody>>,
 ) -> Result<StatusCode, Error> {
-    let username = validate_username(username).map_err(Error::InvalidUsername)?;
-    let password = validate_password(password).map_err(Error::WeakPassword)?;
+    // already checked against the username and email policies,
+    // and the password against the username as it is stored: NFKC normalized
+    let username = validation::normalize_username(&username);
     let email = Email::try_from(email).map_err(Error::InvalidEmailFormat)?;
 
     let mut tx = pool.begin().await.context("begin transaction :: signup")?;
 
-    if super::username::exists(&mut *tx, &username)
+    if super::username::exists(&mut *tx, &username, &username_policy)
         .await
         .context("username exists")?
     {
         return Err(Error::UsernameExists(username));
     }
 
-    if super::email::exists(&mut *tx, &email)
+    let username_canonical = username_policy.canonical(&username);
+    let username_canonical_kind = username_policy.canonical_kind();
+    let email_canonical = email.canonical(email_config.canonicalization());
+    let email_canonical_kind = email_config.canonicalization().kind();
+
+    if super::email::exists(&mut *tx, &email, email_config.canonicalization())
         .await
         .context("email exists")?
     {
@@ -115,21 +131,36 @@ pub async fn handler(
 
     let password_hash = bcrypt::hash(password, bcrypt::DEFAULT_COST).context("hash password")?;
 
-    let user_id = sqlx::query!(
+    let inserted = sqlx::query!(
         r#"
         INSERT INTO users
-        (username, email, password_hash)
-        VALUES (?, ?, ?)
+        (
+            username,
+            username_canonical,
+            username_canonical_kind,
+            email,
+            email_canonical,
+            email_canonical_kind,
+            password_hash
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?)
         RETURNING id as "user_id!"
         "#,
         username,
+        username_canonical,
+        username_canonical_kind,
         email,
+        email_canonical,
+        email_canonical_kind,
         password_hash,
     )
     .fetch_one(&mut *tx)
-    .await
-    .context("insert user")?
-    .user_id;
+    .await;
+
+    let user_id = match inserted {
+        Err(err) if super::email::is_taken(&err) => return Err(Error::EmailExists(email)),
+        inserted => inserted.context("insert user")?.user_id,
+    };
 
     assign_permission_group(&mut *tx, user_id, "signup")
         .await
@@ -152,9 +183,9 @@ pub async fn handler(
             host: &str,
             email: Email,
         ) -> Result<SmtpResponse, InitiateEmailVerificationError> {
-            let hmac_secret = secrets.get("hmac").context("get HMAC key")?;
+            let hmac_key = secrets.current("hmac").context("get HMAC key")?;
             let verification_token = verification_token(email.clone());
-            let verification_link = verification_link(&hmac_secret, &host, &verification_token)
+            let verification_link = verification_link(&hmac_key, &host, verification_token)
                 .context("base64 encode email verification link")?;
             let response = send_verification_email(&smtp, &email, &verification_link).await?;
             Ok(response)
@@ -169,16 +200,19 @@ pub async fn handler(
             SendVerificationEmail(#[from] SendVerificationEmailError),
 
             #[error("{0}")]
-            Io(#[from] contextual::Error<std::io::Error>),
+            Secrets(#[from] contextual::Error<crate::secrets::SecretStoreError>),
         }
 
-        let _handle = tokio::spawn({
+        let _handle = tasks.spawn({
             #[cfg(feature = "tracing")]
             tracing::info!("spawn task to send verification email for {email}");
 
             let fut = async move {
                 let _res = initiate_email_verification(&smtp, &secrets, &host, email).await;
 
+                #[cfg(feature = "metrics")]
+                metrics.smtp_sent(&_res);
+
                 #[cfg(feature = "tracing")]
                 match _res {
                     Ok(response) => match response.is_positive() {
@@ -214,9 +248,7 @@ pub async fn handler(
 impl error_kind::ErrorKind for Error {
     fn kind(&self) -> String {
         match self {
-            Error::InvalidUsername(_) => "username.invalid".into(),
             Error::InvalidEmailFormat(_) => "email.invalid".into(),
-            Error::WeakPassword(_) => "password.weak".into(),
             Error::UsernameExists(_) => "username.exists".into(),
             Error::EmailExists(_) => "email.exists".into(),
             Error::Sqlx(_) => "sqlx".into(),
@@ -228,7 +260,7 @@ impl error_kind::ErrorKind for Error {
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
         match self {
-            Error::InvalidUsername(_) | Error::InvalidEmailFormat(_) | Error::WeakPassword(_) => {
+            Error::InvalidEmailFormat(_) => {
                 #[cfg(feature = "tracing")]
                 tracing::info!("{:?}", self);
 
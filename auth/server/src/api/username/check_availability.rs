@@ -9,7 +9,7 @@ use contextual::Context;
 use error_kind::ErrorKind;
 use error_response::ErrorResponse;
 use serde::Deserialize;
-use validation::validate_username;
+use validation::UsernameError;
 
 use crate::{AppState, HELP};
 
@@ -42,12 +42,18 @@ pub fn method_router() -> MethodRouter<AppState> {
 ))]
 #[cfg_attr(feature = "tracing", tracing::instrument(fields(%username), skip_all, ret))]
 pub async fn handler(
-    State(AppState { pool, .. }): State<AppState>,
+    State(AppState {
+        pool,
+        username_policy,
+        ..
+    }): State<AppState>,
     Query(QueryParams { username }): Query<QueryParams>,
 ) -> Result<StatusCode, Error> {
-    let username = validate_username(username).map_err(Error::InvalidParams)?;
+    let username = username_policy
+        .check(&username)
+        .map_err(Error::InvalidParams)?;
 
-    match super::exists(&pool, &username)
+    match super::exists(&pool, &username, &username_policy)
         .await
         .context("check username availability")?
     {
@@ -59,7 +65,7 @@ pub async fn handler(
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("{0}")]
-    InvalidParams(&'static str),
+    InvalidParams(UsernameError),
 
     #[error("{0}")]
     Sqlx(#[from] contextual::Error<sqlx::Error>),
@@ -68,6 +74,7 @@ pub enum Error {
 impl error_kind::ErrorKind for Error {
     fn kind(&self) -> String {
         match self {
+            Error::InvalidParams(UsernameError::Reserved(_)) => "username.reserved".into(),
             Error::InvalidParams(_) => "username.invalid".into(),
             Error::Sqlx(_) => "sqlx".into(),
         }
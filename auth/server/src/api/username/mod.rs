@@ -1,13 +1,28 @@
 use sqlx::{Executor, Sqlite};
+use validation::UsernamePolicy;
 
 pub mod check_availability;
 
+/// `username` as returned by [`UsernamePolicy::check`].
 pub async fn exists<'a, E: Executor<'a, Database = Sqlite>>(
     ex: E,
     username: &str,
+    policy: &UsernamePolicy,
 ) -> Result<bool, sqlx::Error> {
+    let canonical = policy.canonical(username);
+    let canonical_kind = policy.canonical_kind();
+
+    // those of another policy are compared as is until recomputed by `canonicalize_existing`
     let row = sqlx::query_scalar!(
-        r#"SELECT id as "user_id!" FROM users WHERE username = ? LIMIT 1"#,
+        r#"
+        SELECT id as "user_id!" FROM users
+        WHERE (username_canonical = ? AND username_canonical_kind = ?)
+            OR (username_canonical_kind IS NOT ? AND username = ?)
+        LIMIT 1
+        "#,
+        canonical,
+        canonical_kind,
+        canonical_kind,
         username
     )
     .fetch_optional(ex)
@@ -18,3 +33,51 @@ pub async fn exists<'a, E: Executor<'a, Database = Sqlite>>(
         None => Ok(false),
     }
 }
+
+/// Fills in the canonical form of the users that signed up before it was stored,
+/// and recomputes the ones of another policy, e.g. after changing `[username] allow_unicode`.
+pub async fn canonicalize_existing(pool: sqlx::Pool<Sqlite>, policy: UsernamePolicy) {
+    let _result = try_canonicalize_existing(&pool, &policy).await;
+
+    #[cfg(feature = "tracing")]
+    match _result {
+        Ok(canonicalized) => tracing::info!(canonicalized, "existing usernames canonicalized"),
+        Err(err) => tracing::error!("canonicalize existing usernames :: {:?}", err),
+    }
+}
+
+async fn try_canonicalize_existing(
+    pool: &sqlx::Pool<Sqlite>,
+    policy: &UsernamePolicy,
+) -> Result<u64, contextual::Error<sqlx::Error>> {
+    use contextual::Context;
+
+    let canonical_kind = policy.canonical_kind();
+
+    let users = sqlx::query!(
+        r#"SELECT id as "id!", username FROM users WHERE username_canonical_kind IS NOT ?"#,
+        canonical_kind
+    )
+    .fetch_all(pool)
+    .await
+    .context("users without canonical username")?;
+
+    let mut canonicalized = 0;
+    for user in users {
+        let canonical = policy.canonical(&user.username);
+
+        sqlx::query!(
+            "UPDATE users SET username_canonical = ?, username_canonical_kind = ? WHERE id = ?",
+            canonical,
+            canonical_kind,
+            user.id
+        )
+        .execute(pool)
+        .await
+        .context("store canonical username")?;
+
+        canonicalized += 1;
+    }
+
+    Ok(canonicalized)
+}
@@ -8,13 +8,17 @@ use error_kind::ErrorKind;
 use error_response::ErrorResponse;
 use http::StatusCode;
 use time::OffsetDateTime;
-use token::Token;
+use token::{PrefixedTokenError, Token};
 
 use crate::{
     HELP,
-    core::{Credentials, Permission, Verified, permission::Authorizable},
+    core::{Credentials, LookupHashes, Permission, Verified, permission::Authorizable},
+    secrets::Secret,
 };
 
+/// Lets secret scanners recognize leaked access tokens, see [`Token::prefixed`].
+pub const PREFIX: &str = "mona_at_";
+
 pub struct AccessToken(Token<32>);
 
 impl Credentials for AccessToken {
@@ -33,8 +37,14 @@ impl Credentials for AccessToken {
             return Ok(None);
         };
 
-        let token = Token::base64decode(token_value)
-            .map_err(|_| AccessTokenAuthorizationExtractionError::Base64Decode)?;
+        // access tokens generated before they were prefixed are still valid
+        let token =
+            Token::from_prefixed_or_legacy(token_value, PREFIX).map_err(|err| match err {
+                PrefixedTokenError::Base64Decode => {
+                    AccessTokenAuthorizationExtractionError::Base64Decode
+                }
+                err => AccessTokenAuthorizationExtractionError::Malformed(err),
+            })?;
 
         Ok(Some(AccessToken::from(token)))
     }
@@ -47,6 +57,9 @@ pub enum AccessTokenAuthorizationExtractionError {
 
     #[error("cannot base64 decode :: Authorization: Token xxx")]
     Base64Decode,
+
+    #[error("malformed access token :: {0}")]
+    Malformed(PrefixedTokenError),
 }
 
 #[derive(Debug, Clone)]
@@ -69,23 +82,55 @@ impl AccessToken {
         Self(Token::random())
     }
 
+    /// What is handed out to the user, e.g. `mona_at_...`.
+    pub fn prefixed(&self) -> String {
+        self.0.prefixed(PREFIX)
+    }
+
+    /// `peppers` are the versions of the [`crate::core::PEPPER`], see [`LookupHashes`].
     pub async fn info(
         &self,
         pool: &sqlx::Pool<sqlx::Sqlite>,
+        peppers: &[Secret],
     ) -> Result<Option<AccessTokenInfo>, sqlx::Error> {
-        let access_token_hash = self.hash_sha256();
+        let LookupHashes { current, outdated } = LookupHashes::new(&self.0, peppers);
 
-        sqlx::query_as!(
+        let info = sqlx::query_as!(
             AccessTokenInfo,
             r#"
             SELECT id as "id!", name, user_id, created_at, expires_at
             FROM access_tokens
             WHERE access_token_hash = ?
             "#,
-            access_token_hash
+            current
         )
         .fetch_optional(pool)
-        .await
+        .await?;
+
+        if info.is_some() {
+            return Ok(info);
+        }
+
+        for hash in outdated {
+            let info = sqlx::query_as!(
+                AccessTokenInfo,
+                r#"
+                UPDATE access_tokens SET access_token_hash = ? WHERE access_token_hash = ?
+                RETURNING id as "id!", name, user_id, created_at as "created_at: OffsetDateTime",
+                expires_at as "expires_at: OffsetDateTime"
+                "#,
+                current,
+                hash
+            )
+            .fetch_optional(pool)
+            .await?;
+
+            if info.is_some() {
+                return Ok(info);
+            }
+        }
+
+        Ok(None)
     }
 }
 
@@ -213,6 +258,9 @@ impl error_kind::ErrorKind for AccessTokenAuthorizationExtractionError {
             AccessTokenAuthorizationExtractionError::Base64Decode => {
                 "auth.access-token.authorization-header.base64-decode".into()
             }
+            AccessTokenAuthorizationExtractionError::Malformed(_) => {
+                "auth.access-token.authorization-header.malformed".into()
+            }
         }
     }
 }
@@ -221,7 +269,8 @@ impl IntoResponse for AccessTokenAuthorizationExtractionError {
     fn into_response(self) -> Response {
         match self {
             AccessTokenAuthorizationExtractionError::NonUTF8HeaderValue
-            | AccessTokenAuthorizationExtractionError::Base64Decode => {
+            | AccessTokenAuthorizationExtractionError::Base64Decode
+            | AccessTokenAuthorizationExtractionError::Malformed(_) => {
                 #[cfg(feature = "tracing")]
                 tracing::info!("{:?}", self);
 
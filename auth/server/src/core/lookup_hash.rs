@@ -0,0 +1,66 @@
+use std::{sync::Arc, time::Duration};
+
+use token::Token;
+
+use crate::secrets::{CachedVersions, Secret, SecretStoreError, Secrets};
+
+/// Key of the secret that session ids and access tokens are hashed with before being stored.
+pub const PEPPER: &str = "pepper";
+
+/// The versions of the [`PEPPER`], needed on every authenticated request and therefore cached.
+/// Rotating it through the server is seen right away, and through another instance within a minute.
+#[derive(Clone)]
+pub struct Peppers(CachedVersions);
+
+impl Peppers {
+    const TTL: Duration = Duration::from_secs(60);
+
+    pub fn new(secrets: &Secrets) -> Self {
+        Self(secrets.cached(PEPPER, Self::TTL))
+    }
+
+    /// The current version first, see [`LookupHashes::new`].
+    pub fn versions(&self) -> Result<Arc<[Secret]>, SecretStoreError> {
+        self.0.get()
+    }
+
+    /// The current version, to hash newly issued tokens with.
+    pub fn current(&self) -> Result<Secret, SecretStoreError> {
+        Ok(self.versions()?[0].clone())
+    }
+}
+
+/// The hashes a token may be stored under.
+///
+/// Tokens are stored under the HMAC of the current version of the [`PEPPER`].
+/// Those stored before it was rotated, or before tokens were peppered at all,
+/// are found by their `outdated` hashes and then re-stored under the `current` one.
+pub struct LookupHashes {
+    pub current: Vec<u8>,
+
+    /// Keyed with the previous versions of the pepper, newest first, then the plain SHA-256.
+    pub outdated: Vec<Vec<u8>>,
+}
+
+impl LookupHashes {
+    /// `peppers` are the [`crate::secrets::Secrets::versions`] of the [`PEPPER`], current first.
+    pub fn new<const N: usize>(token: &Token<N>, peppers: &[Secret]) -> Self {
+        let (current, previous) = peppers
+            .split_first()
+            .expect("`Secrets::versions` starts with the current version");
+
+        Self {
+            current: token.hash_hmac_sha256(&current.secret),
+            outdated: previous
+                .iter()
+                .map(|pepper| token.hash_hmac_sha256(&pepper.secret))
+                .chain(std::iter::once(token.hash_sha256()))
+                .collect(),
+        }
+    }
+
+    /// `current` first.
+    pub fn all(&self) -> impl Iterator<Item = &Vec<u8>> {
+        std::iter::once(&self.current).chain(&self.outdated)
+    }
+}
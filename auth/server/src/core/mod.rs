@@ -1,10 +1,14 @@
 mod access_token;
 mod basic;
 mod credentials;
+mod csrf;
+mod lookup_hash;
+mod payload;
 mod permission;
 mod principal;
 mod session;
 mod user;
+mod validated;
 
 pub use access_token::{
     AccessToken, AccessTokenAuthorizationExtractionError, AccessTokenInfo,
@@ -12,6 +16,9 @@ pub use access_token::{
 };
 pub use basic::{Basic, BasicAuthorizationExtractionError};
 pub use credentials::Credentials;
+pub use csrf::{CsrfError, CsrfGuard};
+pub use lookup_hash::{LookupHashes, PEPPER, Peppers};
+pub use payload::Payload;
 pub use permission::{Authorizable, InsufficientPermissionsError, Permission};
 pub use principal::{Principal, PrincipalError};
 pub use session::{
@@ -19,6 +26,7 @@ pub use session::{
     expired_session_cookie,
 };
 pub use user::UserInfo;
+pub use validated::Validated;
 
 pub struct Verified<T>(T);
 
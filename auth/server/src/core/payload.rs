@@ -0,0 +1,108 @@
+use axum::{
+    Form, Json,
+    extract::{
+        FromRequest, Request,
+        rejection::{FormRejection, JsonRejection},
+    },
+    response::{IntoResponse, Response},
+};
+use error_kind::ErrorKind;
+use error_response::ErrorResponse;
+use http::{StatusCode, header::CONTENT_TYPE};
+use serde::de::DeserializeOwned;
+
+use crate::HELP;
+
+/// Request body extractor that deserializes either `application/json`
+/// or `application/x-www-form-urlencoded` based on the `Content-Type` header.
+pub struct Payload<T>(pub T);
+
+impl<T> std::ops::Deref for Payload<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum PayloadRejection {
+    #[error(
+        "unsupported content type :: expected `application/json` or `application/x-www-form-urlencoded`"
+    )]
+    UnsupportedMediaType,
+
+    #[error("{0}")]
+    Json(#[from] JsonRejection),
+
+    #[error("{0}")]
+    Form(#[from] FormRejection),
+}
+
+impl<S, T> FromRequest<S> for Payload<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned,
+{
+    type Rejection = PayloadRejection;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let mime = request
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .map(|mime| mime.trim().to_ascii_lowercase())
+            .ok_or(PayloadRejection::UnsupportedMediaType)?;
+
+        if mime == "application/json" || mime.ends_with("+json") {
+            let Json(payload) = Json::<T>::from_request(request, state).await?;
+            return Ok(Payload(payload));
+        }
+
+        if mime == "application/x-www-form-urlencoded" {
+            let Form(payload) = Form::<T>::from_request(request, state).await?;
+            return Ok(Payload(payload));
+        }
+
+        Err(PayloadRejection::UnsupportedMediaType)
+    }
+}
+
+impl error_kind::ErrorKind for PayloadRejection {
+    fn kind(&self) -> String {
+        match self {
+            PayloadRejection::UnsupportedMediaType => "payload.content-type.unsupported".into(),
+            PayloadRejection::Json(JsonRejection::JsonSyntaxError(_)) => {
+                "payload.json.syntax".into()
+            }
+            PayloadRejection::Json(JsonRejection::JsonDataError(_)) => "payload.json.data".into(),
+            PayloadRejection::Json(_) => "payload.json".into(),
+            PayloadRejection::Form(_) => "payload.form".into(),
+        }
+    }
+}
+
+impl IntoResponse for PayloadRejection {
+    fn into_response(self) -> Response {
+        #[cfg(feature = "tracing")]
+        tracing::info!("{:?}", self);
+
+        let status = match &self {
+            PayloadRejection::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            PayloadRejection::Json(rejection) => rejection.status(),
+            PayloadRejection::Form(rejection) => rejection.status(),
+        };
+
+        (
+            status,
+            Json(
+                ErrorResponse::new(self.to_string())
+                    .with_kind(self.kind())
+                    .with_help(HELP.into()),
+            ),
+        )
+            .into_response()
+    }
+}
@@ -15,9 +15,11 @@ use crate::{
     core::{
         AccessToken, AccessTokenAuthorizationExtractionError, AccessTokenInfo,
         AccessTokenValidationError, Basic, BasicAuthorizationExtractionError, Credentials,
-        InsufficientPermissionsError, Permission, SessionCookieExtractionError, SessionId,
-        SessionInfo, SessionValidationError, UserInfo, Verified, permission::Authorizable,
+        CsrfError, CsrfGuard, InsufficientPermissionsError, Peppers, Permission,
+        SessionCookieExtractionError, SessionId, SessionInfo, SessionValidationError, UserInfo,
+        Verified, permission::Authorizable,
     },
+    secrets::SecretStoreError,
 };
 
 pub enum Principal {
@@ -58,6 +60,12 @@ pub enum PrincipalError {
     #[error("no credentials provided")]
     NoCredentialsProvided,
 
+    #[error("{0}")]
+    Csrf(#[from] CsrfError),
+
+    #[error("{0}")]
+    Secrets(#[from] contextual::Error<SecretStoreError>),
+
     #[error("{0}")]
     Sqlx(#[from] contextual::Error<sqlx::Error>),
 
@@ -117,10 +125,12 @@ impl Principal {
     pub async fn from(
         headers: &HeaderMap,
         pool: &sqlx::Pool<sqlx::Sqlite>,
+        peppers: &Peppers,
     ) -> Result<Self, PrincipalError> {
         if let Some(access_token) = AccessToken::try_from_headers(headers)? {
+            let peppers = peppers.versions().context("get pepper")?;
             let info = access_token
-                .info(pool)
+                .info(pool, &peppers)
                 .await
                 .context("AccessToken -> AccessTokenInfo")?
                 .ok_or(PrincipalError::UnAssociatedAccessToken)?;
@@ -141,8 +151,9 @@ impl Principal {
         }
 
         if let Some(session_id) = SessionId::try_from_headers(headers)? {
+            let peppers = peppers.versions().context("get pepper")?;
             let info = session_id
-                .info(pool)
+                .info(pool, &peppers)
                 .await
                 .context("SessionId -> SessionInfo")?
                 .ok_or(PrincipalError::UnAssociatedSessionId)?;
@@ -158,14 +169,31 @@ impl<S> FromRequestParts<S> for Principal
 where
     S: Send + Sync,
     sqlx::Pool<sqlx::Sqlite>: FromRef<S>,
+    CsrfGuard: FromRef<S>,
+    Peppers: FromRef<S>,
 {
     type Rejection = PrincipalError;
 
     async fn from_request_parts(
-        Parts { headers, .. }: &mut Parts,
+        Parts {
+            method, headers, ..
+        }: &mut Parts,
         state: &S,
     ) -> Result<Self, Self::Rejection> {
-        Principal::from(headers, &sqlx::Pool::<sqlx::Sqlite>::from_ref(state)).await
+        let principal = Principal::from(
+            headers,
+            &sqlx::Pool::<sqlx::Sqlite>::from_ref(state),
+            &Peppers::from_ref(state),
+        )
+        .await?;
+
+        // only the session cookie is sent ambiently by the browser.
+        // `Token` and `Basic` credentials must be attached explicitly, so they are exempt.
+        if let Principal::Session(_) = principal {
+            CsrfGuard::from_ref(state).verify(method, headers)?;
+        }
+
+        Ok(principal)
     }
 }
 
@@ -176,12 +204,14 @@ impl error_kind::ErrorKind for PrincipalError {
             PrincipalError::UnAssociatedSessionId => "auth.session.id.unassociated".into(),
             PrincipalError::InvalidBasicCredentials => "auth.basic.invalid-credentials".into(),
             PrincipalError::NoCredentialsProvided => "auth.no-credentials".into(),
+            PrincipalError::Csrf(err) => err.kind(),
             PrincipalError::UsernameNotFound(_) => "auth.basic.username.not-found".into(),
             PrincipalError::AccessTokenAuthorizationExtraction(err) => err.kind(),
             PrincipalError::BasicAuthorizationExtraction(err) => err.kind(),
             PrincipalError::SessionCookieExtraction(err) => err.kind(),
             PrincipalError::AccessTokenValidation(err) => err.kind(),
             PrincipalError::SessionIdValidation(err) => err.kind(),
+            PrincipalError::Secrets(_) => "auth.secrets".into(),
             PrincipalError::Sqlx(_) => "auth.sqlx".into(),
             PrincipalError::Bcrypt(_) => "auth.bcrypt".into(),
         }
@@ -214,7 +244,8 @@ impl IntoResponse for PrincipalError {
             PrincipalError::SessionCookieExtraction(err) => err.into_response(),
             PrincipalError::AccessTokenValidation(err) => err.into_response(),
             PrincipalError::SessionIdValidation(err) => err.into_response(),
-            PrincipalError::Sqlx(_) | PrincipalError::Bcrypt(_) => {
+            PrincipalError::Csrf(err) => err.into_response(),
+            PrincipalError::Secrets(_) | PrincipalError::Sqlx(_) | PrincipalError::Bcrypt(_) => {
                 #[cfg(feature = "tracing")]
                 tracing::error!("{:?}", self);
                 StatusCode::INTERNAL_SERVER_ERROR.into_response()
@@ -4,20 +4,24 @@ use axum::{
     Json,
     response::{IntoResponse, Response},
 };
-use cookie::{Cookie, SameSite, time::Duration};
+use cookie::{Cookie, time::Duration};
 use error_kind::ErrorKind;
 use error_response::ErrorResponse;
 use http::{StatusCode, header::COOKIE};
 use time::OffsetDateTime;
-use token::Token;
+use token::{PrefixedTokenError, Token};
 
 use crate::{
-    HELP,
-    core::{Credentials, Permission, Verified, permission::Authorizable},
+    CookieConfig, HELP,
+    core::{Credentials, LookupHashes, Permission, Verified, permission::Authorizable},
+    secrets::Secret,
 };
 
 const SESSION_ID: &str = "session_id";
 
+/// Lets secret scanners recognize leaked session ids, see [`Token::prefixed`].
+pub const PREFIX: &str = "mona_sess_";
+
 pub struct SessionId(Token<32>);
 
 impl Credentials for SessionId {
@@ -36,8 +40,11 @@ impl Credentials for SessionId {
             .filter_map(|cookie_str| Cookie::parse(cookie_str).ok())
             .find(|cookie| cookie.name() == SESSION_ID)
             .map(|cookie| {
-                Token::base64decode(cookie.value())
-                    .map_err(|_| SessionCookieExtractionError::Base64Decode)
+                // sessions started before session ids were prefixed are still valid
+                Token::from_prefixed_or_legacy(cookie.value(), PREFIX).map_err(|err| match err {
+                    PrefixedTokenError::Base64Decode => SessionCookieExtractionError::Base64Decode,
+                    err => SessionCookieExtractionError::Malformed(err),
+                })
             })
             .transpose()?
             .map(SessionId))
@@ -48,6 +55,9 @@ impl Credentials for SessionId {
 pub enum SessionCookieExtractionError {
     #[error("cannot base64 decode :: Session Cookie")]
     Base64Decode,
+
+    #[error("malformed session id :: {0}")]
+    Malformed(PrefixedTokenError),
 }
 
 #[derive(Debug, Clone)]
@@ -69,43 +79,77 @@ impl SessionId {
         Self(Token::random())
     }
 
-    pub fn into_cookie(self, max_age: Duration) -> Cookie<'static> {
-        Cookie::build((SESSION_ID, self.base64encoded()))
-            .path("/")
-            .same_site(SameSite::Strict)
-            .max_age(max_age)
-            .http_only(true)
-            .secure(true)
-            .build()
+    pub fn into_cookie(self, config: &CookieConfig) -> Cookie<'static> {
+        session_cookie(self.0.prefixed(PREFIX), config.max_age(), config)
     }
 
+    /// `peppers` are the versions of the [`crate::core::PEPPER`], see [`LookupHashes`].
     pub async fn info(
         &self,
         pool: &sqlx::Pool<sqlx::Sqlite>,
+        peppers: &[Secret],
     ) -> Result<Option<SessionInfo>, sqlx::Error> {
-        let session_id_hash = self.hash_sha256();
+        let LookupHashes { current, outdated } = LookupHashes::new(&self.0, peppers);
 
-        sqlx::query_as!(
+        let info = sqlx::query_as!(
             SessionInfo,
             r#"
             SELECT user_id, created_at, expires_at, user_agent
             FROM sessions WHERE session_id_hash = ?
             "#,
-            session_id_hash
+            current
         )
         .fetch_optional(pool)
-        .await
+        .await?;
+
+        if info.is_some() {
+            return Ok(info);
+        }
+
+        for hash in outdated {
+            let info = sqlx::query_as!(
+                SessionInfo,
+                r#"
+                UPDATE sessions SET session_id_hash = ? WHERE session_id_hash = ?
+                RETURNING user_id, created_at as "created_at: OffsetDateTime",
+                expires_at as "expires_at: OffsetDateTime", user_agent
+                "#,
+                current,
+                hash
+            )
+            .fetch_optional(pool)
+            .await?;
+
+            if info.is_some() {
+                return Ok(info);
+            }
+        }
+
+        Ok(None)
     }
 }
 
-pub fn expired_session_cookie() -> Cookie<'static> {
-    Cookie::build((SESSION_ID, ""))
+pub fn expired_session_cookie(config: &CookieConfig) -> Cookie<'static> {
+    session_cookie(
+        String::new(),
+        Duration::seconds(-3600), /* Expire 1 hour ago */
+        config,
+    )
+}
+
+fn session_cookie(value: String, max_age: Duration, config: &CookieConfig) -> Cookie<'static> {
+    let mut cookie = Cookie::build((SESSION_ID, value))
         .path("/")
-        .same_site(SameSite::Strict)
-        .max_age(Duration::seconds(-3600)) // Expire 1 hour ago
+        .same_site(config.same_site.into())
+        .max_age(max_age)
         .http_only(true)
-        .secure(true)
-        .build()
+        .secure(config.secure);
+
+    if let Some(domain) = &config.domain {
+        cookie = cookie.domain(domain.clone());
+    }
+
+    cookie.build()
 }
 
 impl Default for SessionId {
@@ -224,6 +268,7 @@ impl error_kind::ErrorKind for SessionCookieExtractionError {
             SessionCookieExtractionError::Base64Decode => {
                 "auth.session.cookie.base64-decode".into()
             }
+            SessionCookieExtractionError::Malformed(_) => "auth.session.cookie.malformed".into(),
         }
     }
 }
@@ -231,7 +276,8 @@ impl error_kind::ErrorKind for SessionCookieExtractionError {
 impl IntoResponse for SessionCookieExtractionError {
     fn into_response(self) -> Response {
         match self {
-            SessionCookieExtractionError::Base64Decode => {
+            SessionCookieExtractionError::Base64Decode
+            | SessionCookieExtractionError::Malformed(_) => {
                 #[cfg(feature = "tracing")]
                 tracing::info!("{:?}", self);
 
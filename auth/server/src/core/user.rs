@@ -58,6 +58,9 @@ impl UserInfo {
             password_hash: String,
         }
 
+        // as stored on signup, see `validation::UsernamePolicy::check`
+        let username = validation::normalize_username(username);
+
         let record = sqlx::query_as!(
             Row,
             r#"
@@ -0,0 +1,112 @@
+use axum::{
+    Json,
+    extract::{FromRequest, Request},
+    response::{IntoResponse, Response},
+};
+use error_kind::ErrorKind;
+use error_response::{ErrorResponse, FieldError};
+use http::StatusCode;
+use validation::{EmailRule, Policies, Validate, ValidationErrors};
+
+use crate::{AppState, HELP, api::email::policy_violation_kind};
+
+/// Extracts `E` (e.g. [`Payload`](super::Payload) or [`axum::Form`]) and then checks it with
+/// [`Validate`] against the configured username, password and email policies,
+/// rejecting it with every field error at once.
+pub struct Validated<E>(pub E);
+
+#[derive(Debug)]
+pub enum ValidatedRejection<R> {
+    Extract(R),
+    Invalid(ValidationErrors),
+}
+
+impl<E> FromRequest<AppState> for Validated<E>
+where
+    E: FromRequest<AppState> + std::ops::Deref,
+    E::Target: Validate,
+{
+    type Rejection = ValidatedRejection<E::Rejection>;
+
+    async fn from_request(request: Request, state: &AppState) -> Result<Self, Self::Rejection> {
+        let extracted = E::from_request(request, state)
+            .await
+            .map_err(ValidatedRejection::Extract)?;
+
+        extracted
+            .validate_with(Policies {
+                username: &state.username_policy,
+                password: &state.password_policy,
+                email: Some(&EmailPolicyRule(&state.email_policy)),
+            })
+            .map_err(ValidatedRejection::Invalid)?;
+
+        Ok(Validated(extracted))
+    }
+}
+
+/// The configured [`email::EmailPolicy`] as the rule of `#[validate(email)]`.
+#[derive(Debug)]
+struct EmailPolicyRule<'a>(&'a email::EmailPolicy);
+
+impl EmailRule for EmailPolicyRule<'_> {
+    fn check(&self, errors: &mut ValidationErrors, field: &'static str, email: &str) {
+        let Ok(email) = email.parse::<email::Email>() else {
+            return;
+        };
+        if let Err(violation) = self.0.check(&email) {
+            errors.push(field, policy_violation_kind(&violation), violation);
+        }
+    }
+}
+
+impl<R: ErrorKind> ErrorKind for ValidatedRejection<R> {
+    fn kind(&self) -> String {
+        match self {
+            ValidatedRejection::Extract(rejection) => rejection.kind(),
+            ValidatedRejection::Invalid(errors) => kind(errors),
+        }
+    }
+}
+
+impl<R: IntoResponse> IntoResponse for ValidatedRejection<R> {
+    fn into_response(self) -> Response {
+        match self {
+            ValidatedRejection::Extract(rejection) => rejection.into_response(),
+            ValidatedRejection::Invalid(errors) => {
+                #[cfg(feature = "tracing")]
+                tracing::info!("{:?}", errors);
+
+                (
+                    StatusCode::BAD_REQUEST,
+                    Json(
+                        ErrorResponse::new(errors.to_string())
+                            .with_kind(kind(&errors))
+                            .with_help(HELP.into())
+                            .with_fields(
+                                errors
+                                    .0
+                                    .into_iter()
+                                    .map(|error| FieldError {
+                                        field: error.field.into(),
+                                        kind: error.kind,
+                                        message: error.message,
+                                    })
+                                    .collect(),
+                            ),
+                    ),
+                )
+                    .into_response()
+            }
+        }
+    }
+}
+
+/// Of the first error, for clients that only look at one.
+fn kind(errors: &ValidationErrors) -> String {
+    errors
+        .iter()
+        .next()
+        .map(|error| error.kind.clone())
+        .unwrap_or_else(|| "validation".into())
+}
@@ -0,0 +1,98 @@
+use std::{future::Future, pin::Pin, sync::Arc, time::Duration};
+
+use contextual::Context;
+use signature::{TokenLedger, TokenLedgerError};
+use sqlx::SqliteConnection;
+use time::OffsetDateTime;
+
+/// How often expired tokens are evicted from the ledger.
+pub const EVICTION_INTERVAL: Duration = Duration::from_secs(60 * 10);
+
+/// Shares the used tokens between the processes using the same database, and across restarts.
+pub struct SqliteLedger {
+    pool: sqlx::Pool<sqlx::Sqlite>,
+}
+
+impl SqliteLedger {
+    pub fn new(pool: sqlx::Pool<sqlx::Sqlite>) -> Self {
+        Self { pool }
+    }
+
+    /// Like [`TokenLedger::consume`], on `conn`, so that it is undone along with a transaction that fails
+    /// (e.g. `signature::Signed::consume_with`).
+    /// The primary key on `jti` makes concurrent uses of the same token race for a single row.
+    pub async fn consume_in(
+        conn: &mut SqliteConnection,
+        jti: &str,
+        exp: OffsetDateTime,
+    ) -> Result<bool, contextual::Error<sqlx::Error>> {
+        let exp = exp.unix_timestamp();
+
+        let inserted = sqlx::query!(
+            "INSERT INTO consumed_tokens (jti, exp) VALUES (?, ?) ON CONFLICT DO NOTHING",
+            jti,
+            exp
+        )
+        .execute(conn)
+        .await
+        .context("insert consumed token")?;
+
+        Ok(inserted.rows_affected() == 1)
+    }
+
+    async fn try_consume(
+        &self,
+        jti: &str,
+        exp: OffsetDateTime,
+    ) -> Result<bool, contextual::Error<sqlx::Error>> {
+        let mut conn = self
+            .pool
+            .acquire()
+            .await
+            .context("acquire connection :: consume token")?;
+        Self::consume_in(&mut conn, jti, exp).await
+    }
+
+    async fn try_evict(&self) -> Result<u64, contextual::Error<sqlx::Error>> {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+
+        let evicted = sqlx::query!("DELETE FROM consumed_tokens WHERE exp < ?", now)
+            .execute(&self.pool)
+            .await
+            .context("evict consumed tokens")?;
+
+        Ok(evicted.rows_affected())
+    }
+}
+
+impl TokenLedger for SqliteLedger {
+    fn consume(
+        &self,
+        jti: String,
+        exp: OffsetDateTime,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, TokenLedgerError>> + Send + '_>> {
+        Box::pin(async move { Ok(self.try_consume(&jti, exp).await?) })
+    }
+
+    fn evict(&self) -> Pin<Box<dyn Future<Output = Result<u64, TokenLedgerError>> + Send + '_>> {
+        Box::pin(async move { Ok(self.try_evict().await?) })
+    }
+}
+
+/// Periodically evicts expired tokens until `shutdown` is triggered.
+pub async fn evict_expired(ledger: Arc<dyn TokenLedger>, shutdown: crate::Shutdown) {
+    loop {
+        tokio::select! {
+            () = shutdown.triggered() => return,
+            () = tokio::time::sleep(EVICTION_INTERVAL) => {}
+        }
+
+        let _result = ledger.evict().await;
+
+        #[cfg(feature = "tracing")]
+        match _result {
+            Ok(evicted) => tracing::debug!(evicted, "expired tokens evicted"),
+            Err(err) => tracing::error!("token ledger :: {:?}", err),
+        }
+    }
+}
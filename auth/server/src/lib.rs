@@ -1,34 +1,105 @@
 mod api;
 mod core;
-mod secrets;
+pub mod secrets;
 
 #[cfg(feature = "tracing")]
 mod span;
 
+#[cfg(feature = "metrics")]
+mod metrics;
+
+#[cfg(feature = "otel")]
+pub mod otel;
+
+#[cfg(feature = "rate-limit")]
+mod rate_limit;
+
+#[cfg(feature = "smtp")]
+mod ledger;
+
 #[cfg(feature = "smtp")]
 mod smtp;
 
-use std::net::SocketAddr;
+use std::{net::SocketAddr, time::Duration};
 
 use axum::{Router, extract::FromRef, middleware::from_fn};
 use contextual::Context;
-use http::HeaderName;
-use tokio::net::TcpListener;
+use http::{HeaderName, HeaderValue, header::CACHE_CONTROL};
+use serde::{Deserialize, Serialize};
+use tokio::{net::TcpListener, task::JoinHandle};
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
 use tower::ServiceBuilder;
-use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tower_http::{
+    cors::CorsLayer,
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    set_header::SetResponseHeaderLayer,
+};
+
+use crate::{
+    core::{CsrfGuard, PEPPER, Peppers},
+    secrets::{SecretStore, SecretStoreError, Secrets},
+};
 
-use crate::secrets::Secrets;
+pub use client_ip::{ClientIpConfig, ClientIpHeader};
 
 const HELP: &str = "Please check the response headers for `x-trace-id`, include the datetime and raise a support ticket.";
 
-#[derive(Debug)]
+const X_TRACE_ID: HeaderName = HeaderName::from_static("x-trace-id");
+
+#[cfg(feature = "serve-dir")]
+const SERVE_DIR_CSP: &str = "default-src 'self'; script-src 'self' 'wasm-unsafe-eval'; \
+    style-src 'self'; img-src 'self' data:; connect-src 'self'; object-src 'none'; \
+    base-uri 'self'; form-action 'self'; frame-ancestors 'self'";
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct ServerOpts {
     pub database: DatabaseConfig,
     pub secrets_dir: std::path::PathBuf,
 
+    #[serde(default)]
+    pub secret_store: SecretStoreConfig,
+
+    #[serde(default)]
+    pub csrf: CsrfConfig,
+
+    #[serde(default)]
+    pub cors: CorsConfig,
+
+    #[serde(default)]
+    pub cookie: CookieConfig,
+
+    #[serde(default)]
+    pub shutdown: ShutdownConfig,
+
+    #[serde(default)]
+    pub email: EmailConfig,
+
+    #[serde(default)]
+    pub username: UsernameConfig,
+
+    #[serde(default)]
+    pub password: PasswordConfig,
+
+    /// Who the client is behind reverse proxies, for rate limits, logs and the audit log.
+    #[serde(default)]
+    pub client_ip: ClientIpConfig,
+
+    #[cfg(feature = "otel")]
+    #[serde(default)]
+    pub otel: OtelConfig,
+
     #[cfg(feature = "rate-limit")]
     pub rate_limiter: RateLimiterConfig,
 
+    #[cfg(feature = "rate-limit")]
+    #[serde(default)]
+    pub rate_limits: Vec<RateLimitRule>,
+
+    #[cfg(feature = "rate-limit")]
+    #[serde(default)]
+    pub rate_limit_store: RateLimitStoreKind,
+
     #[cfg(feature = "serve-dir")]
     pub serve_dir: std::path::PathBuf,
 
@@ -36,25 +107,217 @@ pub struct ServerOpts {
     pub smtp: SmtpConfig,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct DatabaseConfig {
     pub url: String,
 }
 
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CsrfConfig {
+    /// Origins other than the server's own (e.g. `https://app.example.com`)
+    /// allowed to make cookie-authenticated state-changing requests.
+    pub trusted_origins: Vec<String>,
+}
+
+/// CORS is disabled when `allowed_origins` is empty.
+/// `*` may be used in any of the lists to allow everything,
+/// except together with `allow_credentials`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    pub allowed_headers: Vec<String>,
+    pub allow_credentials: bool,
+}
+
+/// Attributes of the session cookie issued on login.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CookieConfig {
+    /// Shares the cookie with subdomains of `domain`. The cookie is host-only when unset.
+    pub domain: Option<String>,
+    pub secure: bool,
+    pub same_site: SameSite,
+
+    /// Lifetime of both the cookie and the session behind it.
+    pub max_age_secs: u64,
+}
+
+/// How email addresses are compared, e.g. to tell whether one is already linked to an account,
+/// and which ones are accepted at all. See [`email::Canonicalization`] and [`email::EmailPolicy`].
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmailConfig {
+    /// `Joe@example.com` is the same address as `joe@example.com`.
+    pub fold_local_part: bool,
+
+    /// `joe+news@gmail.com` is the same address as `joe@gmail.com`, for the providers known to support it.
+    pub strip_plus_tags: bool,
+
+    /// Only addresses on these domains (or their subdomains) are accepted. Any domain is when empty.
+    pub allowed_domains: Vec<String>,
+
+    /// Addresses on these domains (or their subdomains) are rejected, even if allowed.
+    pub denied_domains: Vec<String>,
+
+    /// Accept addresses of disposable email providers (e.g. `mailinator.com`).
+    pub allow_disposable: bool,
+
+    /// Replaces the bundled list of disposable email providers, see [`email::DISPOSABLE_DOMAINS`].
+    pub disposable_domains_file: Option<std::path::PathBuf>,
+}
+
+/// Which usernames are accepted on signup, and when two of them are the same account.
+/// See [`validation::UsernamePolicy`].
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UsernameConfig {
+    /// Accept letters and digits of any script, compared by their confusable skeleton
+    /// (e.g. `Joe` is the same username as `joe`), rather than only `A-Z` `a-z` `0-9` and `_` compared as is.
+    pub allow_unicode: bool,
+
+    /// Replaces the bundled list of reserved usernames, see [`validation::RESERVED_USERNAMES`].
+    pub reserved_names_file: Option<std::path::PathBuf>,
+}
+
+/// What passwords are accepted on signup. See [`validation::PasswordPolicy`].
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PasswordConfig {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+    pub require_special: bool,
+
+    /// How hard the password must be to guess, from 0 to 4. See [`validation::Strength::score`].
+    pub min_score: u8,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+/// Where secrets (e.g. the `hmac` key) are kept.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SecretStoreConfig {
+    pub kind: SecretStoreKind,
+
+    /// `env` only. Secrets are read from `<env_prefix><NAME>`, e.g. `AUTH_SECRET_HMAC`.
+    pub env_prefix: Option<String>,
+
+    /// `keystore` only. Defaults to `<secrets_dir>/keystore`.
+    pub keystore_path: Option<std::path::PathBuf>,
+
+    /// `keystore` only. File descriptor to read the master key from.
+    /// The `AUTH_MASTER_KEY` environment variable is used when unset.
+    pub master_key_fd: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SecretStoreKind {
+    /// Plaintext files in `secrets_dir`.
+    #[default]
+    Dir,
+
+    /// Base64 encoded environment variables. Read-only.
+    Env,
+
+    /// A single file encrypted with a master key.
+    Keystore,
+
+    /// Lost on restart. A random `hmac` key and `pepper` are generated on startup.
+    Memory,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShutdownConfig {
+    /// How long in-flight requests and background tasks
+    /// are given to finish once a shutdown is triggered.
+    pub drain_timeout_secs: u64,
+}
+
+#[cfg(feature = "otel")]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OtelConfig {
+    /// OTLP/HTTP traces endpoint of the collector, e.g. `http://localhost:4318/v1/traces`.
+    /// Falls back to the standard `OTEL_EXPORTER_OTLP_*` environment variables when unset.
+    pub endpoint: Option<String>,
+    pub service_name: String,
+}
+
 #[cfg(feature = "rate-limit")]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
 pub struct RateLimiterConfig {
     pub limit: usize,
     pub interval: std::time::Duration,
 }
 
+/// `<route> <limit>/<unit> per <key>`, e.g. `/login 5/min per username`.
+/// `route` is an exact path, a prefix ending in `*` (e.g. `/access-token/*`) or `*` for every route.
+#[cfg(feature = "rate-limit")]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct RateLimitRule {
+    pub route: String,
+    pub rate: RateLimiterConfig,
+    pub key: RateLimitKey,
+}
+
+/// Where rate limit state is kept.
+#[cfg(feature = "rate-limit")]
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitStoreKind {
+    /// Per process.
+    #[default]
+    Memory,
+
+    /// In the database, shared by every process using it.
+    Sqlite,
+}
+
+/// What a [`RateLimitRule`] counts requests by.
+/// Requests that don't carry the key (e.g. anonymous requests for `user_id`) are not limited by the rule.
+#[cfg(feature = "rate-limit")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitKey {
+    Ip,
+
+    /// From `Authorization: Basic` or the json/form body, e.g. `/login` and `/signup`.
+    Username,
+
+    /// Of the session or access token.
+    UserId,
+
+    /// The access token itself.
+    Token,
+}
+
 #[cfg(feature = "smtp")]
-#[derive(Debug)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct SmtpConfig {
     pub relay: String,
     pub port: Option<u16>,
     pub username: Option<String>,
+
+    #[serde(serialize_with = "redact")]
     pub password: Option<String>,
+
     pub senders_dir: std::path::PathBuf,
     pub templates_dir: std::path::PathBuf,
 }
@@ -63,15 +326,53 @@ pub struct SmtpConfig {
 pub struct AppState {
     pub pool: sqlx::Pool<sqlx::Sqlite>,
     pub secrets: Secrets,
+    pub peppers: Peppers,
+    pub csrf: CsrfGuard,
+    pub cookie: std::sync::Arc<CookieConfig>,
+    pub email: std::sync::Arc<EmailConfig>,
+    pub email_policy: std::sync::Arc<email::EmailPolicy>,
+    pub username_policy: std::sync::Arc<validation::UsernamePolicy>,
+    pub password_policy: std::sync::Arc<validation::PasswordPolicy>,
+
+    /// Background work that outlives its request (e.g. sending emails).
+    /// Spawn it here rather than with `tokio::spawn` so that shutdown waits for it.
+    pub tasks: TaskTracker,
+
+    /// Triggered once the server starts draining, see [`serve`].
+    pub shutdown: Shutdown,
+
+    #[cfg(feature = "metrics")]
+    pub metrics: crate::metrics::Metrics,
 
     #[cfg(feature = "smtp")]
     pub smtp: crate::smtp::Smtp,
 }
 
-pub async fn router(opts: ServerOpts) -> Result<Router, ServerError> {
+/// A router along with what [`serve`] needs to shut it down gracefully.
+pub struct Server {
+    pub router: Router,
+    pub shutdown: Shutdown,
+    pub secrets: Secrets,
+    tasks: TaskTracker,
+    drain_timeout: Duration,
+}
+
+/// A server running in the background, see [`serve`].
+pub struct Serving {
+    pub local_addr: SocketAddr,
+    pub shutdown: Shutdown,
+    task: JoinHandle<Result<(), ServerError>>,
+}
+
+/// Stops a [`Serving`] server from accepting new connections
+/// and lets it drain in-flight requests and background tasks.
+#[derive(Debug, Clone, Default)]
+pub struct Shutdown(CancellationToken);
+
+pub async fn router(opts: ServerOpts) -> Result<Server, ServerError> {
     use crate::api::{
-        access_token, email, heartbeat, key_rotation, login, logout, permissions, private, signup,
-        sysinfo, username,
+        access_token, email, health, heartbeat, key_rotation, login, logout, permissions, private,
+        signup, sysinfo, username,
     };
 
     let router = Router::new()
@@ -115,57 +416,198 @@ pub async fn router(opts: ServerOpts) -> Result<Router, ServerError> {
             email::verify_email::method_router(),
         );
 
+    #[cfg(feature = "metrics")]
+    let router = router.route(api::metrics::PATH, api::metrics::method_router());
+
     #[cfg(feature = "openapi")]
     let router = router.route(
         api::OPEN_API_DOCS_PATH,
         axum::routing::get(axum::Json(api::openapi())),
     );
 
+    // applies only to the routes registered so far, i.e. not to the `serve-dir` fallback
+    let router = router.route_layer(SetResponseHeaderLayer::overriding(
+        CACHE_CONTROL,
+        HeaderValue::from_static("no-store"),
+    ));
+
     #[cfg(feature = "serve-dir")]
-    let router = router.fallback_service(tower_http::services::ServeDir::new(&opts.serve_dir));
+    let router = router.fallback_service(
+        ServiceBuilder::new()
+            .layer(SetResponseHeaderLayer::if_not_present(
+                http::header::CONTENT_SECURITY_POLICY,
+                HeaderValue::from_static(SERVE_DIR_CSP),
+            ))
+            .service(tower_http::services::ServeDir::new(&opts.serve_dir)),
+    );
+
+    let cors = match opts.cors.allowed_origins.is_empty() {
+        true => None,
+        false => Some(CorsLayer::try_from(opts.cors)?),
+    };
 
-    const X_TRACE_ID: HeaderName = HeaderName::from_static("x-trace-id");
     let middleware = ServiceBuilder::new()
         .layer(SetRequestIdLayer::new(X_TRACE_ID, MakeRequestUuid))
-        .layer(PropagateRequestIdLayer::new(X_TRACE_ID));
+        .layer(PropagateRequestIdLayer::new(X_TRACE_ID))
+        .option_layer(cors)
+        .layer(from_fn(axum_middleware::security_headers))
+        .layer(axum::middleware::from_fn_with_state(
+            std::sync::Arc::new(opts.client_ip),
+            client_ip::resolve,
+        ));
 
     #[cfg(feature = "tracing")]
     let middleware = middleware
         .layer(tower_http::trace::TraceLayer::new_for_http().make_span_with(span::span))
         .layer(from_fn(axum_middleware::latency_ms));
 
-    let middleware = middleware.layer(from_fn(axum_middleware::handle_leaked_5xx));
+    #[cfg(feature = "otel")]
+    let middleware = middleware.layer(from_fn(otel::trace_context));
 
-    #[cfg(feature = "rate-limit")]
+    #[cfg(feature = "metrics")]
+    let metrics = crate::metrics::Metrics::new().context("register metrics")?;
+
+    #[cfg(feature = "metrics")]
     let middleware = middleware.layer(axum::middleware::from_fn_with_state(
-        std::sync::Arc::new(opts.rate_limiter.into()),
-        axum_middleware::rate_limiter,
+        metrics.clone(),
+        crate::metrics::track,
     ));
 
+    let middleware = middleware.layer(from_fn(axum_middleware::handle_leaked_5xx));
+
+    let tasks = TaskTracker::new();
+    let shutdown = Shutdown::default();
+
+    let pool = opts
+        .database
+        .pool()
+        .await
+        .context(format!("connect database :: {}", opts.database.url))?;
+
+    let secrets = Secrets::new(
+        opts.secret_store
+            .store(&opts.secrets_dir)
+            .context("open secret store")?,
+    );
+
+    let peppers = Peppers::new(&secrets);
+
+    match opts.secret_store.kind {
+        SecretStoreKind::Memory => {
+            secrets.reset("hmac").context("generate HMAC key")?;
+            secrets.reset(PEPPER).context("generate pepper")?;
+        }
+
+        // provisioned by hand, and without it every login and authenticated request fails
+        SecretStoreKind::Dir | SecretStoreKind::Env => {
+            peppers.versions().context("get pepper")?;
+        }
+
+        // left to `/health/ready`, which also reports a keystore opened with the wrong master key
+        SecretStoreKind::Keystore => {}
+    }
+
+    #[cfg(feature = "rate-limit")]
+    let middleware = {
+        let store: std::sync::Arc<dyn axum_middleware::RateLimitStore> = match opts.rate_limit_store
+        {
+            RateLimitStoreKind::Memory => std::sync::Arc::new(axum_middleware::MemoryStore::new()),
+            RateLimitStoreKind::Sqlite => {
+                std::sync::Arc::new(rate_limit::SqliteStore::new(pool.clone()))
+            }
+        };
+
+        tasks.spawn(rate_limit::evict_idle(store.clone(), shutdown.clone()));
+
+        let rate_limits = rate_limit::RateLimits::new(
+            opts.rate_limiter,
+            opts.rate_limits,
+            store,
+            pool.clone(),
+            peppers.clone(),
+        );
+
+        #[cfg(feature = "metrics")]
+        let rate_limits = {
+            let metrics = metrics.clone();
+            rate_limits.on_reject(move || metrics.rate_limited())
+        };
+
+        middleware.layer(axum::middleware::from_fn_with_state(
+            std::sync::Arc::new(rate_limits),
+            rate_limit::rate_limit,
+        ))
+    };
+
     let router = router.layer(middleware);
 
+    // registered after the middleware so that probes are neither rate limited
+    // nor stripped of their 503 breakdown by `handle_leaked_5xx`
+    let router = router
+        .route(health::live::PATH, health::live::method_router())
+        .route(health::ready::PATH, health::ready::method_router());
+
+    let email_policy = opts.email.policy()?;
+    let username_policy = opts.username.policy()?;
+
+    tasks.spawn(api::email::canonicalize_existing(
+        pool.clone(),
+        opts.email.canonicalization(),
+    ));
+    tasks.spawn(api::username::canonicalize_existing(
+        pool.clone(),
+        username_policy.clone(),
+    ));
+
+    // used single-use tokens (e.g. email verification links) are recorded along with what they are used for,
+    // see `ledger::SqliteLedger::consume_in`, and only evicted from here
+    #[cfg(feature = "smtp")]
+    tasks.spawn(ledger::evict_expired(
+        std::sync::Arc::new(ledger::SqliteLedger::new(pool.clone())),
+        shutdown.clone(),
+    ));
+
     let router = router.with_state(AppState {
-        pool: opts
-            .database
-            .pool()
-            .await
-            .context(format!("connect database :: {}", opts.database.url))?,
-        secrets: Secrets::new(opts.secrets_dir),
+        pool,
+        secrets: secrets.clone(),
+        peppers,
+        csrf: CsrfGuard::new(opts.csrf.trusted_origins),
+        cookie: std::sync::Arc::new(opts.cookie),
+        email: std::sync::Arc::new(opts.email),
+        email_policy: std::sync::Arc::new(email_policy),
+        username_policy: std::sync::Arc::new(username_policy),
+        password_policy: std::sync::Arc::new(opts.password.into()),
+        tasks: tasks.clone(),
+        shutdown: shutdown.clone(),
+        #[cfg(feature = "metrics")]
+        metrics,
         #[cfg(feature = "smtp")]
         smtp: crate::smtp::Smtp::try_from(opts.smtp)?,
     });
 
-    Ok(router)
+    Ok(Server {
+        router,
+        shutdown,
+        secrets,
+        tasks,
+        drain_timeout: Duration::from_secs(opts.shutdown.drain_timeout_secs),
+    })
 }
 
-/// Returns the local address that the listener is bound to.
-/// This can be useful, for example, when binding to port 0 to figure out which port was actually bound.
-pub async fn serve(server: Router, port: u16) -> Result<SocketAddr, ServerError> {
-    #[cfg(feature = "client-ip")]
-    let app = server.into_make_service_with_connect_info::<SocketAddr>();
+/// Binds the listener and serves in the background until [`Shutdown::trigger`] is called.
+/// [`Serving::local_addr`] can be useful, for example, when binding to port 0
+/// to figure out which port was actually bound.
+pub async fn serve(server: Server, port: u16) -> Result<Serving, ServerError> {
+    let Server {
+        router,
+        shutdown,
+        tasks,
+        drain_timeout,
+        ..
+    } = server;
 
-    #[cfg(not(feature = "client-ip"))]
-    let app = server.into_make_service();
+    // for `client_ip::resolve`
+    let app = router.into_make_service_with_connect_info::<SocketAddr>();
 
     let listener = TcpListener::bind(SocketAddr::from(([0, 0, 0, 0], port)))
         .await
@@ -176,8 +618,72 @@ pub async fn serve(server: Router, port: u16) -> Result<SocketAddr, ServerError>
     #[cfg(feature = "tracing")]
     tracing::info!("listening on {}", local_addr);
 
-    axum::serve(listener, app).await.context("axum::serve")?;
-    Ok(local_addr)
+    let task = tokio::spawn({
+        let shutdown = shutdown.clone();
+
+        async move {
+            let drain = {
+                let tasks = tasks.clone();
+                let signal = shutdown.0.clone().cancelled_owned();
+
+                async move {
+                    axum::serve(listener, app)
+                        .with_graceful_shutdown(signal)
+                        .await
+                        .context("axum::serve")?;
+
+                    tasks.close();
+                    tasks.wait().await;
+                    Ok(())
+                }
+            };
+
+            let deadline = async {
+                shutdown.triggered().await;
+
+                #[cfg(feature = "tracing")]
+                tracing::info!(?drain_timeout, "shutting down");
+
+                tokio::time::sleep(drain_timeout).await;
+            };
+
+            tokio::select! {
+                result = drain => result,
+                () = deadline => Err(ServerError::DrainTimeout {
+                    timeout: drain_timeout,
+                    pending_tasks: tasks.len(),
+                }),
+            }
+        }
+    });
+
+    Ok(Serving {
+        local_addr,
+        shutdown,
+        task,
+    })
+}
+
+impl Serving {
+    /// Resolves once the server has shut down, i.e. in-flight requests and
+    /// background tasks have finished or the drain timeout has elapsed.
+    pub async fn wait(self) -> Result<(), ServerError> {
+        self.task.await.context("join server task")?
+    }
+}
+
+impl Shutdown {
+    pub fn trigger(&self) {
+        self.0.cancel()
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.0.is_cancelled()
+    }
+
+    pub async fn triggered(&self) {
+        self.0.cancelled().await
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -191,6 +697,42 @@ pub enum ServerError {
 
     #[error("{0}")]
     Io(#[from] contextual::Error<std::io::Error>),
+
+    #[error("{0}")]
+    Secrets(#[from] contextual::Error<SecretStoreError>),
+
+    #[error("{0}")]
+    InvalidCorsConfig(#[from] InvalidCorsConfigError),
+
+    #[error("{0}")]
+    Join(#[from] contextual::Error<tokio::task::JoinError>),
+
+    #[cfg(feature = "metrics")]
+    #[error("{0}")]
+    Metrics(#[from] contextual::Error<prometheus::Error>),
+
+    #[error(
+        "shutdown drain timeout of {timeout:?} elapsed with {pending_tasks} background task(s) pending"
+    )]
+    DrainTimeout {
+        timeout: Duration,
+        pending_tasks: usize,
+    },
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum InvalidCorsConfigError {
+    #[error("invalid CORS origin `{0}`")]
+    Origin(String),
+
+    #[error("invalid CORS method `{0}`")]
+    Method(String),
+
+    #[error("invalid CORS header `{0}`")]
+    Header(String),
+
+    #[error("CORS `allow_credentials` cannot be combined with a `*` origin, method or header")]
+    WildcardWithCredentials,
 }
 
 #[cfg(feature = "smtp")]
@@ -211,16 +753,281 @@ impl FromRef<AppState> for sqlx::Pool<sqlx::Sqlite> {
     }
 }
 
+impl FromRef<AppState> for CsrfGuard {
+    fn from_ref(app_state: &AppState) -> Self {
+        app_state.csrf.clone()
+    }
+}
+
+impl FromRef<AppState> for Secrets {
+    fn from_ref(app_state: &AppState) -> Self {
+        app_state.secrets.clone()
+    }
+}
+
+impl FromRef<AppState> for Peppers {
+    fn from_ref(app_state: &AppState) -> Self {
+        app_state.peppers.clone()
+    }
+}
+
+impl ServerOpts {
+    /// Checks everything that can be checked without starting the server,
+    /// i.e. all but the database connection.
+    pub fn validate(&self) -> Result<(), ServerError> {
+        let _ = CorsLayer::try_from(self.cors.clone())?;
+
+        self.secret_store
+            .store(&self.secrets_dir)
+            .context("open secret store")?;
+
+        #[cfg(feature = "smtp")]
+        crate::smtp::Smtp::try_from(self.smtp.clone())?;
+
+        Ok(())
+    }
+}
+
 impl DatabaseConfig {
     pub async fn pool(&self) -> Result<sqlx::Pool<sqlx::Sqlite>, sqlx::Error> {
         sqlx::Pool::<sqlx::Sqlite>::connect(&self.url).await
     }
 }
 
-#[cfg(feature = "rate-limit")]
-impl From<RateLimiterConfig> for axum_middleware::RateLimiter {
-    fn from(config: RateLimiterConfig) -> Self {
-        Self::new(config.limit, config.interval)
+impl CookieConfig {
+    pub fn max_age(&self) -> time::Duration {
+        time::Duration::seconds(i64::try_from(self.max_age_secs).unwrap_or(i64::MAX))
+    }
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            drain_timeout_secs: 30,
+        }
+    }
+}
+
+#[cfg(feature = "otel")]
+impl Default for OtelConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            service_name: "auth".into(),
+        }
+    }
+}
+
+impl Default for CookieConfig {
+    fn default() -> Self {
+        Self {
+            domain: None,
+            secure: true,
+            same_site: SameSite::Strict,
+            max_age_secs: 30 * 24 * 60 * 60,
+        }
+    }
+}
+
+impl EmailConfig {
+    pub fn canonicalization(&self) -> email::Canonicalization {
+        email::Canonicalization {
+            fold_local_part: self.fold_local_part,
+            strip_plus_tags: self.strip_plus_tags,
+        }
+    }
+
+    pub fn policy(&self) -> Result<email::EmailPolicy, contextual::Error<std::io::Error>> {
+        let mut policy = email::EmailPolicy::permissive()
+            .with_allowed_domains(&self.allowed_domains)
+            .with_denied_domains(&self.denied_domains);
+
+        if !self.allow_disposable {
+            policy = match &self.disposable_domains_file {
+                Some(path) => {
+                    policy.with_disposable_domains(&std::fs::read_to_string(path).context(
+                        format!("read disposable email domains :: {}", path.display()),
+                    )?)
+                }
+                None => policy.with_disposable_domains(email::DISPOSABLE_DOMAINS),
+            };
+        }
+
+        Ok(policy)
+    }
+}
+
+impl UsernameConfig {
+    pub fn policy(&self) -> Result<validation::UsernamePolicy, contextual::Error<std::io::Error>> {
+        let policy = match self.allow_unicode {
+            true => validation::UsernamePolicy::unicode(),
+            false => validation::UsernamePolicy::ascii(),
+        };
+
+        Ok(match &self.reserved_names_file {
+            Some(path) => policy.with_reserved_names(
+                &std::fs::read_to_string(path)
+                    .context(format!("read reserved usernames :: {}", path.display()))?,
+            ),
+            None => policy,
+        })
+    }
+}
+
+impl Default for PasswordConfig {
+    fn default() -> Self {
+        validation::PasswordPolicy::default().into()
+    }
+}
+
+impl From<validation::PasswordPolicy> for PasswordConfig {
+    fn from(policy: validation::PasswordPolicy) -> Self {
+        Self {
+            min_length: policy.min_length,
+            max_length: policy.max_length,
+            require_lowercase: policy.require_lowercase,
+            require_uppercase: policy.require_uppercase,
+            require_digit: policy.require_digit,
+            require_special: policy.require_special,
+            min_score: policy.min_score,
+        }
+    }
+}
+
+impl From<PasswordConfig> for validation::PasswordPolicy {
+    fn from(config: PasswordConfig) -> Self {
+        Self {
+            min_length: config.min_length,
+            max_length: config.max_length,
+            require_lowercase: config.require_lowercase,
+            require_uppercase: config.require_uppercase,
+            require_digit: config.require_digit,
+            require_special: config.require_special,
+            min_score: config.min_score,
+        }
+    }
+}
+
+impl From<SameSite> for cookie::SameSite {
+    fn from(same_site: SameSite) -> Self {
+        match same_site {
+            SameSite::Strict => cookie::SameSite::Strict,
+            SameSite::Lax => cookie::SameSite::Lax,
+            SameSite::None => cookie::SameSite::None,
+        }
+    }
+}
+
+impl SecretStoreConfig {
+    pub const DEFAULT_ENV_PREFIX: &str = "AUTH_SECRET_";
+    pub const MASTER_KEY_ENV: &str = "AUTH_MASTER_KEY";
+
+    pub fn store(
+        &self,
+        secrets_dir: &std::path::Path,
+    ) -> Result<std::sync::Arc<dyn SecretStore>, SecretStoreError> {
+        use crate::secrets::{DirStore, EnvStore, KeystoreStore, MasterKey, MemoryStore};
+
+        Ok(match self.kind {
+            SecretStoreKind::Dir => std::sync::Arc::new(DirStore::new(secrets_dir.to_owned())),
+            SecretStoreKind::Env => std::sync::Arc::new(EnvStore::new(
+                self.env_prefix
+                    .clone()
+                    .unwrap_or_else(|| Self::DEFAULT_ENV_PREFIX.into()),
+            )),
+            SecretStoreKind::Keystore => {
+                let master_key = match self.master_key_fd {
+                    Some(fd) => MasterKey::from_fd(fd)?,
+                    None => MasterKey::from_env(Self::MASTER_KEY_ENV)?,
+                };
+
+                let path = self
+                    .keystore_path
+                    .clone()
+                    .unwrap_or_else(|| secrets_dir.join("keystore"));
+
+                std::sync::Arc::new(KeystoreStore::new(path, master_key))
+            }
+            SecretStoreKind::Memory => std::sync::Arc::new(MemoryStore::new()),
+        })
+    }
+}
+
+#[cfg(feature = "smtp")]
+fn redact<S: serde::Serializer>(value: &Option<String>, serializer: S) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(_) => serializer.serialize_some("<redacted>"),
+        None => serializer.serialize_none(),
+    }
+}
+
+impl TryFrom<CorsConfig> for CorsLayer {
+    type Error = InvalidCorsConfigError;
+
+    fn try_from(config: CorsConfig) -> Result<Self, Self::Error> {
+        use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin};
+
+        fn is_wildcard(values: &[String]) -> bool {
+            values.iter().any(|value| value.trim() == "*")
+        }
+
+        if config.allow_credentials
+            && (is_wildcard(&config.allowed_origins)
+                || is_wildcard(&config.allowed_methods)
+                || is_wildcard(&config.allowed_headers))
+        {
+            return Err(InvalidCorsConfigError::WildcardWithCredentials);
+        }
+
+        let allow_origin = match is_wildcard(&config.allowed_origins) {
+            true => AllowOrigin::any(),
+            false => AllowOrigin::list(
+                config
+                    .allowed_origins
+                    .into_iter()
+                    .map(|origin| {
+                        HeaderValue::try_from(origin.trim().trim_end_matches('/'))
+                            .map_err(|_| InvalidCorsConfigError::Origin(origin))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+        };
+
+        let allow_methods = match is_wildcard(&config.allowed_methods) {
+            true => AllowMethods::any(),
+            false => AllowMethods::list(
+                config
+                    .allowed_methods
+                    .into_iter()
+                    .map(|method| {
+                        http::Method::from_bytes(method.trim().to_ascii_uppercase().as_bytes())
+                            .map_err(|_| InvalidCorsConfigError::Method(method))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+        };
+
+        let allow_headers = match is_wildcard(&config.allowed_headers) {
+            true => AllowHeaders::any(),
+            false => AllowHeaders::list(
+                config
+                    .allowed_headers
+                    .into_iter()
+                    .map(|header| {
+                        HeaderName::try_from(header.trim())
+                            .map_err(|_| InvalidCorsConfigError::Header(header))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+        };
+
+        Ok(CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods(allow_methods)
+            .allow_headers(allow_headers)
+            .allow_credentials(config.allow_credentials)
+            // so that cross-origin clients can quote it in support tickets (see `HELP`)
+            .expose_headers([X_TRACE_ID]))
     }
 }
 
@@ -287,6 +1094,116 @@ impl std::str::FromStr for RateLimiterConfig {
     }
 }
 
+#[cfg(feature = "rate-limit")]
+impl TryFrom<String> for RateLimiterConfig {
+    type Error = ParseRateLimiterConfigError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+#[cfg(feature = "rate-limit")]
+impl std::fmt::Display for RateLimiterConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.interval.as_secs() {
+            1 => write!(f, "{}/s", self.limit),
+            60 => write!(f, "{}/min", self.limit),
+            3600 => write!(f, "{}/hour", self.limit),
+            _ => write!(f, "{}/{:?}", self.limit, self.interval),
+        }
+    }
+}
+
+#[cfg(feature = "rate-limit")]
+impl Serialize for RateLimiterConfig {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "rate-limit")]
+impl std::str::FromStr for RateLimitRule {
+    type Err = ParseRateLimitRuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let [route, rate, "per", key] = s.split_whitespace().collect::<Vec<_>>()[..] else {
+            return Err(ParseRateLimitRuleError::Format);
+        };
+
+        Ok(Self {
+            route: route.into(),
+            rate: rate.parse()?,
+            key: key.parse()?,
+        })
+    }
+}
+
+#[cfg(feature = "rate-limit")]
+impl TryFrom<String> for RateLimitRule {
+    type Error = ParseRateLimitRuleError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+#[cfg(feature = "rate-limit")]
+impl std::fmt::Display for RateLimitRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} per {}", self.route, self.rate, self.key)
+    }
+}
+
+#[cfg(feature = "rate-limit")]
+impl Serialize for RateLimitRule {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "rate-limit")]
+impl std::str::FromStr for RateLimitKey {
+    type Err = ParseRateLimitRuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ip" => Ok(Self::Ip),
+            "username" => Ok(Self::Username),
+            "user_id" => Ok(Self::UserId),
+            "token" => Ok(Self::Token),
+            _ => Err(ParseRateLimitRuleError::InvalidKey),
+        }
+    }
+}
+
+#[cfg(feature = "rate-limit")]
+impl std::fmt::Display for RateLimitKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RateLimitKey::Ip => write!(f, "ip"),
+            RateLimitKey::Username => write!(f, "username"),
+            RateLimitKey::UserId => write!(f, "user_id"),
+            RateLimitKey::Token => write!(f, "token"),
+        }
+    }
+}
+
+#[cfg(feature = "rate-limit")]
+#[derive(thiserror::Error, Debug)]
+pub enum ParseRateLimitRuleError {
+    #[error(
+        r#"expected <route> <number>/<unit> per <key> :: "/login 5/min per username", "* 100/s per user_id", ..."#
+    )]
+    Format,
+
+    #[error("{0}")]
+    Rate(#[from] ParseRateLimiterConfigError),
+
+    #[error(r#"invalid key :: expected "ip", "username", "user_id", "token""#)]
+    InvalidKey,
+}
+
 #[cfg(feature = "rate-limit")]
 #[derive(thiserror::Error, Debug)]
 pub enum ParseRateLimiterConfigError {
@@ -1,4 +1,7 @@
-use clap::Parser;
+use std::path::PathBuf;
+
+use clap::{Parser, builder::BoolishValueParser};
+use contextual::Context;
 
 // TODO: introduce other databases, like postgres and mysql
 // TODO: use Zeroize for access tokens, session ids, passwords, etc...
@@ -7,10 +10,12 @@ use clap::Parser;
 const FEATURES: &[&str] = &[
     #[cfg(feature = "await-tasks")]
     "await-tasks",
-    #[cfg(feature = "client-ip")]
-    "client-ip",
+    #[cfg(feature = "metrics")]
+    "metrics",
     #[cfg(feature = "openapi")]
     "openapi",
+    #[cfg(feature = "otel")]
+    "otel",
     #[cfg(feature = "profiles")]
     "profiles",
     #[cfg(feature = "rate-limit")]
@@ -46,42 +51,218 @@ fn warn_dangerous_features() {
 
 #[derive(Debug, clap::Parser)]
 struct Serve {
+    /// Path to a TOML file containing any of the settings below, laid out like `auth::ServerOpts`.
+    /// Command line arguments and environment variables take precedence over the file.
+    /// Example: `./auth.toml`
+    #[arg(long, env("CONFIG_FILE"))]
+    config: Option<PathBuf>,
+
     /// The port number on which the server will listen for incoming connections.
     /// Example: `8080`
     #[arg(long, env("PORT"))]
-    #[cfg_attr(debug_assertions, arg(default_value_t = 0))]
-    port: u16,
+    port: Option<u16>,
 
     /// The database connection URL used by the server.
     /// Example: `sqlite:///tmp/data/data.db` (or) `/tmp/data/data.db` (or) `./data.db`
     #[arg(long, env("DATABASE_URL"))]
-    database_url: String,
+    database_url: Option<String>,
 
     /// The directory where the server's secrets are located.
     /// Example: `./secrets` or `/var/www/secrets`
     #[arg(long, env("SECRETS_DIR"))]
-    secrets_dir: std::path::PathBuf,
+    secrets_dir: Option<PathBuf>,
+
+    /// Where secrets are kept: `dir` (plaintext files in `secrets_dir`, the default),
+    /// `env` (base64 environment variables, read-only), `keystore` (a file encrypted with a master key)
+    /// or `memory` (lost on restart).
+    #[arg(long, env("SECRET_STORE"))]
+    secret_store: Option<String>,
+
+    /// Prefix of the environment variables read by the `env` secret store.
+    /// Defaults to `AUTH_SECRET_`, i.e. the `hmac` key is read from `AUTH_SECRET_HMAC`.
+    #[arg(long, env("SECRET_STORE_ENV_PREFIX"))]
+    secret_store_env_prefix: Option<String>,
+
+    /// The encrypted file of the `keystore` secret store. Defaults to `<secrets_dir>/keystore`.
+    #[arg(long, env("KEYSTORE_PATH"))]
+    keystore_path: Option<PathBuf>,
+
+    /// File descriptor the `keystore` master key (32 base64 encoded bytes) is read from,
+    /// e.g. `3` with `3<master.key`. The `AUTH_MASTER_KEY` environment variable is used when unset.
+    #[arg(long, env("MASTER_KEY_FD"))]
+    master_key_fd: Option<i32>,
+
+    /// Comma separated origins, besides the server's own, that may send
+    /// cookie-authenticated state-changing requests (e.g. a UI on a sibling subdomain).
+    /// Example: `https://app.example.com,https://admin.example.com`
+    #[arg(long, env("CSRF_TRUSTED_ORIGINS"), value_delimiter = ',')]
+    csrf_trusted_origins: Option<Vec<String>>,
+
+    /// Comma separated origins allowed to make cross-origin (CORS) requests.
+    /// Use `*` to allow any origin. CORS is disabled when empty.
+    /// Example: `https://app.example.com,https://admin.example.com`
+    #[arg(long, env("CORS_ALLOWED_ORIGINS"), value_delimiter = ',')]
+    cors_allowed_origins: Option<Vec<String>>,
+
+    /// Comma separated HTTP methods allowed in cross-origin requests.
+    /// Defaults to `GET,POST`.
+    #[arg(long, env("CORS_ALLOWED_METHODS"), value_delimiter = ',')]
+    cors_allowed_methods: Option<Vec<String>>,
+
+    /// Comma separated request headers allowed in cross-origin requests.
+    /// Defaults to `authorization,content-type`.
+    #[arg(long, env("CORS_ALLOWED_HEADERS"), value_delimiter = ',')]
+    cors_allowed_headers: Option<Vec<String>>,
+
+    /// Allow cross-origin requests to carry credentials (cookies, `Authorization` header).
+    /// Cannot be combined with a `*` origin, method or header.
+    #[arg(long, env("CORS_ALLOW_CREDENTIALS"), num_args = 0..=1, default_missing_value = "true", value_parser = BoolishValueParser::new())]
+    cors_allow_credentials: Option<bool>,
+
+    /// Domain attribute of the session cookie, to share it with subdomains.
+    /// The cookie is host-only when unset.
+    /// Example: `example.com`
+    #[arg(long, env("COOKIE_DOMAIN"))]
+    cookie_domain: Option<String>,
+
+    /// Whether the session cookie is only sent over HTTPS. Defaults to `true`.
+    #[arg(long, env("COOKIE_SECURE"), num_args = 0..=1, default_missing_value = "true", value_parser = BoolishValueParser::new())]
+    cookie_secure: Option<bool>,
+
+    /// SameSite attribute of the session cookie: `strict`, `lax` or `none`. Defaults to `strict`.
+    #[arg(long, env("COOKIE_SAME_SITE"))]
+    cookie_same_site: Option<String>,
+
+    /// Lifetime of the session cookie (and the session) in seconds. Defaults to 30 days.
+    /// Example: `86400`
+    #[arg(long, env("COOKIE_MAX_AGE_SECS"), value_parser = clap::value_parser!(i64).range(0..))]
+    cookie_max_age_secs: Option<i64>,
+
+    /// Compare email addresses regardless of the case of their local part,
+    /// i.e. `Joe@example.com` is already linked to the account of `joe@example.com`. Defaults to `false`.
+    #[arg(long, env("EMAIL_FOLD_LOCAL_PART"), num_args = 0..=1, default_missing_value = "true", value_parser = BoolishValueParser::new())]
+    email_fold_local_part: Option<bool>,
+
+    /// Compare email addresses without their plus tag for the providers known to support them,
+    /// i.e. `joe+news@gmail.com` is already linked to the account of `joe@gmail.com`. Defaults to `false`.
+    #[arg(long, env("EMAIL_STRIP_PLUS_TAGS"), num_args = 0..=1, default_missing_value = "true", value_parser = BoolishValueParser::new())]
+    email_strip_plus_tags: Option<bool>,
+
+    /// Comma separated domains that email addresses must belong to (subdomains included), e.g. for an internal deployment.
+    /// Any domain is allowed when empty.
+    /// Example: `ourcompany.com,partner.example`
+    #[arg(long, env("EMAIL_ALLOWED_DOMAINS"), value_delimiter = ',')]
+    email_allowed_domains: Option<Vec<String>>,
+
+    /// Comma separated domains that email addresses must not belong to (subdomains included).
+    /// Example: `competitor.example`
+    #[arg(long, env("EMAIL_DENIED_DOMAINS"), value_delimiter = ',')]
+    email_denied_domains: Option<Vec<String>>,
+
+    /// Accept email addresses of disposable email providers (e.g. `mailinator.com`). Defaults to `false`.
+    #[arg(long, env("EMAIL_ALLOW_DISPOSABLE"), num_args = 0..=1, default_missing_value = "true", value_parser = BoolishValueParser::new())]
+    email_allow_disposable: Option<bool>,
+
+    /// File listing disposable email domains, one per line, that replaces the bundled list.
+    #[arg(long, env("EMAIL_DISPOSABLE_DOMAINS_FILE"))]
+    email_disposable_domains_file: Option<PathBuf>,
+
+    /// Accept usernames with letters and digits of any script (NFKC normalized, not mixing scripts),
+    /// compared by their confusable skeleton so that `Joe` is already taken by `joe`. Defaults to `false`.
+    #[arg(long, env("USERNAME_ALLOW_UNICODE"), num_args = 0..=1, default_missing_value = "true", value_parser = BoolishValueParser::new())]
+    username_allow_unicode: Option<bool>,
+
+    /// File listing reserved usernames, one per line, that replaces the bundled list.
+    #[arg(long, env("USERNAME_RESERVED_NAMES_FILE"))]
+    username_reserved_names_file: Option<PathBuf>,
+
+    /// Minimum length of passwords, in characters. Defaults to `8`.
+    #[arg(long, env("PASSWORD_MIN_LENGTH"))]
+    password_min_length: Option<usize>,
+
+    /// Maximum length of passwords, in characters. Defaults to `64`.
+    #[arg(long, env("PASSWORD_MAX_LENGTH"))]
+    password_max_length: Option<usize>,
+
+    /// Require at least one lowercase letter in passwords. Defaults to `false`.
+    #[arg(long, env("PASSWORD_REQUIRE_LOWERCASE"), num_args = 0..=1, default_missing_value = "true", value_parser = BoolishValueParser::new())]
+    password_require_lowercase: Option<bool>,
+
+    /// Require at least one uppercase letter in passwords. Defaults to `false`.
+    #[arg(long, env("PASSWORD_REQUIRE_UPPERCASE"), num_args = 0..=1, default_missing_value = "true", value_parser = BoolishValueParser::new())]
+    password_require_uppercase: Option<bool>,
+
+    /// Require at least one digit in passwords. Defaults to `false`.
+    #[arg(long, env("PASSWORD_REQUIRE_DIGIT"), num_args = 0..=1, default_missing_value = "true", value_parser = BoolishValueParser::new())]
+    password_require_digit: Option<bool>,
+
+    /// Require at least one special character in passwords. Defaults to `false`.
+    #[arg(long, env("PASSWORD_REQUIRE_SPECIAL"), num_args = 0..=1, default_missing_value = "true", value_parser = BoolishValueParser::new())]
+    password_require_special: Option<bool>,
+
+    /// How hard passwords must be to guess, from `0` (anything goes) to `4`. Defaults to `3`.
+    #[arg(long, env("PASSWORD_MIN_SCORE"), value_parser = clap::value_parser!(u8).range(0..=4))]
+    password_min_score: Option<u8>,
+
+    /// Header the reverse proxies in `--trusted-proxies` put the client IP in:
+    /// `forwarded`, `x-forwarded-for`, `x-real-ip` or `none` (the connected peer, the default).
+    #[arg(long, env("CLIENT_IP_HEADER"))]
+    client_ip_header: Option<String>,
+
+    /// Comma separated CIDR ranges of the reverse proxies whose `--client-ip-header` is believed.
+    /// Example: `10.0.0.0/8,fd00::/8`
+    #[arg(long, env("TRUSTED_PROXIES"), value_delimiter = ',')]
+    trusted_proxies: Option<Vec<String>>,
+
+    /// Seconds that in-flight requests and background tasks (e.g. verification emails)
+    /// are given to finish on SIGTERM/SIGINT before the server exits anyway. Defaults to `30`.
+    #[arg(long, env("SHUTDOWN_DRAIN_TIMEOUT_SECS"), value_parser = clap::value_parser!(i64).range(0..))]
+    shutdown_drain_timeout_secs: Option<i64>,
+
+    #[cfg(feature = "otel")]
+    /// OTLP/HTTP traces endpoint of the OpenTelemetry collector.
+    /// Falls back to the standard `OTEL_EXPORTER_OTLP_*` environment variables when unset.
+    /// Example: `http://localhost:4318/v1/traces`
+    #[arg(long, env("OTEL_ENDPOINT"))]
+    otel_endpoint: Option<String>,
+
+    #[cfg(feature = "otel")]
+    /// `service.name` of the exported spans. Defaults to `auth`.
+    #[arg(long, env("OTEL_SERVICE_NAME"))]
+    otel_service_name: Option<String>,
 
     #[cfg(feature = "serve-dir")]
     /// The directory where the server's UI files are located.
     /// This should point to a valid local path containing frontend assets.
     /// Example: `./ui` or `/var/www/html`
     #[arg(long, env("SERVE_DIR"))]
-    serve_dir: std::path::PathBuf,
+    serve_dir: Option<PathBuf>,
 
     #[cfg(feature = "rate-limit")]
     /// The rate limit in the form of a string, e.g. "1/s", "10/min", "100/hour".
     /// Example: "10/min"
     #[arg(long, env("RATE_LIMIT"))]
-    #[cfg_attr(debug_assertions, arg(default_value = "100/s"))]
-    rate_limit: auth::RateLimiterConfig,
+    rate_limit: Option<auth::RateLimiterConfig>,
+
+    #[cfg(feature = "rate-limit")]
+    /// Comma separated rules of the form `<route> <limit>/<unit> per <key>`, applied on top of `--rate-limit`.
+    /// `<key>` is one of `ip`, `username`, `user_id` or `token`, `<route>` may end in `*` to match a prefix.
+    /// Example: `/login 5/min per ip,/login 5/min per username,/signup 3/hour per ip`
+    #[arg(long, env("RATE_LIMITS"), value_delimiter = ',')]
+    rate_limits: Option<Vec<auth::RateLimitRule>>,
+
+    #[cfg(feature = "rate-limit")]
+    /// Where rate limit state is kept: `memory` (per process, the default)
+    /// or `sqlite` (in the database, shared by every process using it).
+    #[arg(long, env("RATE_LIMIT_STORE"))]
+    rate_limit_store: Option<String>,
 
     #[cfg(feature = "smtp")]
     /// The SMTP relay server used for sending emails.
     /// This should be a valid SMTP server address.
     /// Example: `"smtp.gmail.com"`
     #[arg(long, env("SMTP_RELAY"))]
-    smtp_relay: String,
+    smtp_relay: Option<String>,
 
     #[cfg(feature = "smtp")]
     /// The port on which the SMTP relay server listens.
@@ -112,7 +293,7 @@ struct Serve {
     ///
     /// Example: senders/noreply.txt (contains: noreply@yourdomain.com)
     #[arg(long, env("SMTP_SENDERS_DIR"))]
-    smtp_senders_dir: std::path::PathBuf,
+    smtp_senders_dir: Option<PathBuf>,
 
     #[cfg(feature = "smtp")]
     /// Directory containing email templates.
@@ -120,8 +301,19 @@ struct Serve {
     /// Each file in this directory should be a valid HTML template
     /// that can be rendered by the server's templating engine.
     #[arg(long, env("SMTP_TEMPLATES_DIR"))]
-    #[cfg_attr(debug_assertions, arg(default_value_os_t = std::path::PathBuf::from("./templates/")))]
-    smtp_templates_dir: std::path::PathBuf,
+    smtp_templates_dir: Option<PathBuf>,
+}
+
+#[derive(thiserror::Error, Debug)]
+enum ConfigError {
+    #[error("{0}")]
+    Io(#[from] contextual::Error<std::io::Error>),
+
+    #[error("{0}")]
+    Toml(#[from] contextual::Error<toml::de::Error>),
+
+    #[error("missing field `port`")]
+    MissingPort,
 }
 
 #[tokio::main]
@@ -135,12 +327,23 @@ async fn main() {
         return;
     }
 
+    // the exporter needs the configuration, which is only loaded further down
+    #[cfg(feature = "otel")]
+    let (otel_layer, otel_reload_handle) =
+        tracing_subscriber::reload::Layer::<Option<auth::otel::Layer>, _>::new(None);
+
     #[cfg(feature = "tracing")]
     {
-        use tracing_subscriber::{EnvFilter, fmt};
+        use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 
-        fmt()
-            .with_env_filter(EnvFilter::from_default_env(/* RUST_LOG env var sets logging level */))
+        let registry = tracing_subscriber::registry();
+
+        #[cfg(feature = "otel")]
+        let registry = registry.with(otel_layer);
+
+        registry
+            .with(EnvFilter::from_default_env(/* RUST_LOG env var sets logging level */))
+            .with(fmt::layer())
             .init()
     };
 
@@ -150,13 +353,79 @@ async fn main() {
     #[cfg(feature = "profiles")]
     load_profile();
 
+    if args_os.next_if(|arg| arg == "config").is_some() {
+        if args_os.next_if(|arg| arg == "check").is_none() {
+            eprintln!("usage: auth config check [--config <FILE>] [OPTIONS]");
+            std::process::exit(2);
+        }
+
+        let args = Serve::parse_from(std::iter::once("auth config check".into()).chain(args_os));
+        return check_config(args);
+    }
+
     let args = Serve::parse();
+    let (port, opts) = load_config(args).unwrap_or_else(|e| exit(e));
+
+    #[cfg(feature = "otel")]
+    let tracer_provider = {
+        let provider = auth::otel::tracer_provider(&opts.otel).unwrap_or_else(|e| exit(e));
+        otel_reload_handle
+            .reload(Some(auth::otel::layer(&provider)))
+            .unwrap_or_else(|e| exit(e));
+        provider
+    };
+
+    let server = auth::router(opts).await.unwrap_or_else(|e| exit(e));
+    let serving = auth::serve(server, port).await.unwrap_or_else(|e| exit(e));
+
+    tokio::spawn({
+        let shutdown = serving.shutdown.clone();
+        async move {
+            shutdown_signal().await;
+            shutdown.trigger();
+        }
+    });
 
-    let port = args.port;
-    let opts = auth::ServerOpts::from(args);
+    let result = serving.wait().await;
 
-    let router = auth::router(opts).await.unwrap_or_else(|e| exit(e));
-    auth::serve(router, port).await.unwrap_or_else(|e| exit(e));
+    #[cfg(feature = "otel")]
+    if let Err(err) = tracer_provider.shutdown() {
+        eprintln!("{err}");
+    }
+
+    result.unwrap_or_else(|e| exit(e));
+}
+
+/// Resolves on SIGINT (Ctrl+C) or, on unix, SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            exit(err)
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(err) => exit(err),
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {},
+        () = terminate => {},
+    }
+
+    #[cfg(feature = "tracing")]
+    tracing::info!("shutdown signal received");
 }
 
 #[cfg(feature = "profiles")]
@@ -208,30 +477,272 @@ fn exit(err: impl std::error::Error) -> ! {
     std::process::exit(1)
 }
 
-impl From<Serve> for auth::ServerOpts {
-    fn from(serve: Serve) -> Self {
-        auth::ServerOpts {
-            database: auth::DatabaseConfig {
-                url: serve.database_url,
-            },
-
-            secrets_dir: serve.secrets_dir,
-
-            #[cfg(feature = "rate-limit")]
-            rate_limiter: serve.rate_limit,
-
-            #[cfg(feature = "serve-dir")]
-            serve_dir: serve.serve_dir,
-
-            #[cfg(feature = "smtp")]
-            smtp: auth::SmtpConfig {
-                relay: serve.smtp_relay,
-                port: serve.smtp_port,
-                username: serve.smtp_username,
-                password: serve.smtp_password,
-                senders_dir: serve.smtp_senders_dir,
-                templates_dir: serve.smtp_templates_dir,
-            },
+/// Validates the effective configuration and prints it, with secrets redacted.
+fn check_config(args: Serve) {
+    #[derive(serde::Serialize)]
+    struct Effective<'a> {
+        port: u16,
+
+        #[serde(flatten)]
+        opts: &'a auth::ServerOpts,
+    }
+
+    let (port, opts) = load_config(args).unwrap_or_else(|e| exit(e));
+    opts.validate().unwrap_or_else(|e| exit(e));
+
+    let effective =
+        toml::to_string_pretty(&Effective { port, opts: &opts }).unwrap_or_else(|e| exit(e));
+    print!("{effective}");
+}
+
+/// Resolves the configuration from, in order of precedence,
+/// command line arguments, environment variables, the `--config` file and the defaults.
+fn load_config(args: Serve) -> Result<(u16, auth::ServerOpts), ConfigError> {
+    let mut config = defaults();
+
+    if let Some(path) = &args.config {
+        let file = std::fs::read_to_string(path)
+            .context(format!("read config file :: {}", path.display()))?
+            .parse::<toml::Table>()
+            .context(format!("parse config file :: {}", path.display()))?;
+
+        merge(&mut config, file);
+    }
+
+    merge(&mut config, args.into_table());
+
+    let port = config
+        .remove("port")
+        .ok_or(ConfigError::MissingPort)?
+        .try_into::<u16>()
+        .context("port")?;
+
+    let opts = toml::Value::Table(config)
+        .try_into::<auth::ServerOpts>()
+        .context("server options")?;
+
+    Ok((port, opts))
+}
+
+/// Values used when neither the command line, the environment nor the config file set them.
+fn defaults() -> toml::Table {
+    let mut table = toml::Table::new();
+
+    #[cfg(debug_assertions)]
+    insert(&mut table, "port", Some(0));
+
+    insert(&mut table, "cors.allowed_methods", Some(["GET", "POST"]));
+    insert(
+        &mut table,
+        "cors.allowed_headers",
+        Some(["authorization", "content-type"]),
+    );
+
+    #[cfg(all(debug_assertions, feature = "rate-limit"))]
+    insert(&mut table, "rate_limiter", Some("100/s"));
+
+    #[cfg(all(debug_assertions, feature = "smtp"))]
+    insert(&mut table, "smtp.templates_dir", Some("./templates/"));
+
+    table
+}
+
+/// Recursively overwrites `base` with `overrides`, keeping the keys that are only in `base`.
+fn merge(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overrides)) => {
+                merge(base, overrides)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Inserts `value` at the dotted `path` (e.g. `smtp.relay`), creating intermediate tables.
+fn insert(table: &mut toml::Table, path: &str, value: Option<impl serde::Serialize>) {
+    let Some(value) = value.and_then(|value| toml::Value::try_from(value).ok()) else {
+        return;
+    };
+
+    let mut keys = path.split('.').peekable();
+    let mut table = table;
+    while let Some(key) = keys.next() {
+        if keys.peek().is_none() {
+            table.insert(key.into(), value);
+            return;
+        }
+
+        let entry = table
+            .entry(key)
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        if !entry.is_table() {
+            *entry = toml::Value::Table(toml::Table::new());
         }
+        let Some(next) = entry.as_table_mut() else {
+            return;
+        };
+        table = next;
+    }
+}
+
+impl Serve {
+    /// Only the values that were actually passed, laid out like the config file.
+    fn into_table(self) -> toml::Table {
+        let mut table = toml::Table::new();
+
+        insert(&mut table, "port", self.port);
+        insert(&mut table, "database.url", self.database_url);
+        insert(&mut table, "secrets_dir", self.secrets_dir);
+
+        insert(&mut table, "secret_store.kind", self.secret_store);
+        insert(
+            &mut table,
+            "secret_store.env_prefix",
+            self.secret_store_env_prefix,
+        );
+        insert(&mut table, "secret_store.keystore_path", self.keystore_path);
+        insert(&mut table, "secret_store.master_key_fd", self.master_key_fd);
+
+        insert(
+            &mut table,
+            "csrf.trusted_origins",
+            self.csrf_trusted_origins,
+        );
+
+        insert(
+            &mut table,
+            "cors.allowed_origins",
+            self.cors_allowed_origins,
+        );
+        insert(
+            &mut table,
+            "cors.allowed_methods",
+            self.cors_allowed_methods,
+        );
+        insert(
+            &mut table,
+            "cors.allowed_headers",
+            self.cors_allowed_headers,
+        );
+        insert(
+            &mut table,
+            "cors.allow_credentials",
+            self.cors_allow_credentials,
+        );
+
+        insert(&mut table, "cookie.domain", self.cookie_domain);
+        insert(&mut table, "cookie.secure", self.cookie_secure);
+        insert(&mut table, "cookie.same_site", self.cookie_same_site);
+        insert(&mut table, "cookie.max_age_secs", self.cookie_max_age_secs);
+
+        insert(
+            &mut table,
+            "email.fold_local_part",
+            self.email_fold_local_part,
+        );
+        insert(
+            &mut table,
+            "email.strip_plus_tags",
+            self.email_strip_plus_tags,
+        );
+        insert(
+            &mut table,
+            "email.allowed_domains",
+            self.email_allowed_domains,
+        );
+        insert(
+            &mut table,
+            "email.denied_domains",
+            self.email_denied_domains,
+        );
+        insert(
+            &mut table,
+            "email.allow_disposable",
+            self.email_allow_disposable,
+        );
+        insert(
+            &mut table,
+            "email.disposable_domains_file",
+            self.email_disposable_domains_file,
+        );
+
+        insert(
+            &mut table,
+            "username.allow_unicode",
+            self.username_allow_unicode,
+        );
+        insert(
+            &mut table,
+            "username.reserved_names_file",
+            self.username_reserved_names_file,
+        );
+
+        insert(&mut table, "password.min_length", self.password_min_length);
+        insert(&mut table, "password.max_length", self.password_max_length);
+        insert(
+            &mut table,
+            "password.require_lowercase",
+            self.password_require_lowercase,
+        );
+        insert(
+            &mut table,
+            "password.require_uppercase",
+            self.password_require_uppercase,
+        );
+        insert(
+            &mut table,
+            "password.require_digit",
+            self.password_require_digit,
+        );
+        insert(
+            &mut table,
+            "password.require_special",
+            self.password_require_special,
+        );
+        insert(&mut table, "password.min_score", self.password_min_score);
+
+        insert(&mut table, "client_ip.header", self.client_ip_header);
+        insert(
+            &mut table,
+            "client_ip.trusted_proxies",
+            self.trusted_proxies,
+        );
+
+        insert(
+            &mut table,
+            "shutdown.drain_timeout_secs",
+            self.shutdown_drain_timeout_secs,
+        );
+
+        #[cfg(feature = "otel")]
+        {
+            insert(&mut table, "otel.endpoint", self.otel_endpoint);
+            insert(&mut table, "otel.service_name", self.otel_service_name);
+        }
+
+        #[cfg(feature = "rate-limit")]
+        {
+            insert(&mut table, "rate_limiter", self.rate_limit);
+            insert(&mut table, "rate_limits", self.rate_limits);
+            insert(&mut table, "rate_limit_store", self.rate_limit_store);
+        }
+
+        #[cfg(feature = "serve-dir")]
+        insert(&mut table, "serve_dir", self.serve_dir);
+
+        #[cfg(feature = "smtp")]
+        {
+            insert(&mut table, "smtp.relay", self.smtp_relay);
+            insert(&mut table, "smtp.port", self.smtp_port);
+            insert(&mut table, "smtp.username", self.smtp_username);
+            insert(&mut table, "smtp.password", self.smtp_password);
+            insert(&mut table, "smtp.senders_dir", self.smtp_senders_dir);
+            insert(&mut table, "smtp.templates_dir", self.smtp_templates_dir);
+        }
+
+        table
     }
 }
@@ -0,0 +1,178 @@
+use std::{sync::Arc, time::Instant};
+
+use axum::{
+    body::Body,
+    extract::{MatchedPath, State},
+    middleware::Next,
+};
+use http::{Request, Response};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+
+#[derive(Clone)]
+pub struct Metrics(Arc<Inner>);
+
+struct Inner {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    logins: IntCounterVec,
+    rate_limit_rejections: IntCounter,
+    smtp_sends: IntCounterVec,
+    sqlite_pool_connections: IntGaugeVec,
+    active_sessions: IntGauge,
+}
+
+impl Metrics {
+    pub fn new() -> Result<Self, prometheus::Error> {
+        let registry = Registry::new();
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests handled"),
+            &["method", "route", "status"],
+        )?;
+        registry.register(Box::new(http_requests.clone()))?;
+
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "HTTP request latency in seconds",
+            ),
+            &["method", "route", "status"],
+        )?;
+        registry.register(Box::new(http_request_duration.clone()))?;
+
+        let logins = IntCounterVec::new(
+            Opts::new("auth_logins_total", "Login attempts"),
+            &["outcome"],
+        )?;
+        registry.register(Box::new(logins.clone()))?;
+
+        let rate_limit_rejections = IntCounter::new(
+            "auth_rate_limit_rejections_total",
+            "Requests rejected by the rate limiter",
+        )?;
+        registry.register(Box::new(rate_limit_rejections.clone()))?;
+
+        let smtp_sends = IntCounterVec::new(
+            Opts::new("auth_smtp_sends_total", "Emails handed to the SMTP relay"),
+            &["outcome"],
+        )?;
+        registry.register(Box::new(smtp_sends.clone()))?;
+
+        let sqlite_pool_connections = IntGaugeVec::new(
+            Opts::new("auth_sqlite_pool_connections", "SQLite pool connections"),
+            &["state"],
+        )?;
+        registry.register(Box::new(sqlite_pool_connections.clone()))?;
+
+        let active_sessions = IntGauge::new("auth_sessions_active", "Sessions not yet expired")?;
+        registry.register(Box::new(active_sessions.clone()))?;
+
+        Ok(Self(Arc::new(Inner {
+            registry,
+            http_requests,
+            http_request_duration,
+            logins,
+            rate_limit_rejections,
+            smtp_sends,
+            sqlite_pool_connections,
+            active_sessions,
+        })))
+    }
+
+    pub fn login_succeeded(&self) {
+        self.0.logins.with_label_values(&["success"]).inc();
+    }
+
+    pub fn login_failed(&self) {
+        self.0.logins.with_label_values(&["failure"]).inc();
+    }
+
+    pub fn rate_limited(&self) {
+        self.0.rate_limit_rejections.inc();
+    }
+
+    /// `accepted` and `rejected` are the relay's verdict, `error` means it could not be reached.
+    #[cfg(feature = "smtp")]
+    pub fn smtp_sent<E>(&self, result: &Result<lettre::transport::smtp::response::Response, E>) {
+        let outcome = match result {
+            Ok(response) if response.is_positive() => "accepted",
+            Ok(_) => "rejected",
+            Err(_) => "error",
+        };
+        self.0.smtp_sends.with_label_values(&[outcome]).inc();
+    }
+
+    /// Samples the gauges and encodes everything in the Prometheus text format.
+    pub async fn render(&self, pool: &sqlx::Pool<sqlx::Sqlite>) -> Result<String, MetricsError> {
+        use contextual::Context;
+
+        let idle = pool.num_idle() as i64;
+        let size = pool.size() as i64;
+        let max = pool.options().get_max_connections() as i64;
+
+        let connections = &self.0.sqlite_pool_connections;
+        connections.with_label_values(&["idle"]).set(idle);
+        connections.with_label_values(&["active"]).set(size - idle);
+        connections.with_label_values(&["max"]).set(max);
+
+        let now = time::OffsetDateTime::now_utc();
+        let active_sessions = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!: i64" FROM sessions WHERE expires_at > ?"#,
+            now
+        )
+        .fetch_one(pool)
+        .await
+        .context("count active sessions")?;
+        self.0.active_sessions.set(active_sessions);
+
+        let mut buffer = vec![];
+        TextEncoder::new()
+            .encode(&self.0.registry.gather(), &mut buffer)
+            .context("encode metrics")?;
+
+        Ok(String::from_utf8_lossy(&buffer).into_owned())
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum MetricsError {
+    #[error("{0}")]
+    Sqlx(#[from] contextual::Error<sqlx::Error>),
+
+    #[error("{0}")]
+    Prometheus(#[from] contextual::Error<prometheus::Error>),
+}
+
+/// Counts requests and observes their latency per route and status class (`2xx`, `4xx`, ...).
+pub async fn track(
+    State(metrics): State<Metrics>,
+    request: Request<Body>,
+    next: Next,
+) -> Response<Body> {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".into());
+
+    let start = Instant::now();
+    let response = next.run(request).await;
+    let elapsed = start.elapsed();
+
+    let status = format!("{}xx", response.status().as_u16() / 100);
+    let labels = [method.as_str(), route.as_str(), status.as_str()];
+
+    metrics.0.http_requests.with_label_values(&labels).inc();
+    metrics
+        .0
+        .http_request_duration
+        .with_label_values(&labels)
+        .observe(elapsed.as_secs_f64());
+
+    response
+}
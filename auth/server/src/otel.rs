@@ -0,0 +1,83 @@
+//! OpenTelemetry trace export over OTLP/HTTP and W3C trace-context propagation.
+//!
+//! When the [`layer`] is installed, the OTel trace id becomes the `x-trace-id`
+//! (both in the `request` span and in the response header),
+//! so that a support ticket quoting it maps directly to a trace.
+
+use axum::{body::Body, middleware::Next};
+use http::{HeaderMap, HeaderValue, Request, Response};
+use opentelemetry::{
+    global,
+    trace::{TraceContextExt, TraceId, TracerProvider},
+};
+use opentelemetry_http::{HeaderExtractor, HeaderInjector};
+use opentelemetry_otlp::{ExporterBuildError, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{Resource, propagation::TraceContextPropagator, trace::SdkTracerProvider};
+use tracing::Span;
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+
+use crate::{OtelConfig, X_TRACE_ID};
+
+pub type Layer =
+    OpenTelemetryLayer<tracing_subscriber::Registry, opentelemetry_sdk::trace::SdkTracer>;
+
+/// Batches spans and exports them to `config.endpoint`.
+/// Call [`SdkTracerProvider::shutdown`] before exiting to flush what is left.
+pub fn tracer_provider(config: &OtelConfig) -> Result<SdkTracerProvider, ExporterBuildError> {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let exporter = SpanExporter::builder().with_http();
+    let exporter = match &config.endpoint {
+        Some(endpoint) => exporter.with_endpoint(endpoint),
+        // falls back to `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT` / `OTEL_EXPORTER_OTLP_ENDPOINT`
+        None => exporter,
+    };
+
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter.build()?)
+        .with_resource(
+            Resource::builder()
+                .with_service_name(config.service_name.clone())
+                .build(),
+        )
+        .build())
+}
+
+pub fn layer(provider: &SdkTracerProvider) -> Layer {
+    tracing_opentelemetry::layer().with_tracer(provider.tracer("auth"))
+}
+
+/// Continues the trace from the incoming `traceparent` header (if any)
+/// and returns the resulting trace id, or `None` when the [`layer`] is not installed.
+pub fn set_parent(span: &Span, headers: &HeaderMap) -> Option<TraceId> {
+    let parent =
+        global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)));
+    span.set_parent(parent).ok()?;
+
+    let trace_id = span.context().span().span_context().trace_id();
+    (trace_id != TraceId::INVALID).then_some(trace_id)
+}
+
+/// Replaces the generated `x-trace-id` with the OTel trace id
+/// and returns the `traceparent` of the request span to the caller.
+pub async fn trace_context(mut request: Request<Body>, next: Next) -> Response<Body> {
+    let context = Span::current().context();
+    let trace_id = context.span().span_context().trace_id();
+
+    if trace_id == TraceId::INVALID {
+        return next.run(request).await;
+    }
+
+    let trace_id =
+        HeaderValue::try_from(trace_id.to_string()).unwrap(/* hex is a valid header value */);
+    request.headers_mut().insert(X_TRACE_ID, trace_id.clone());
+
+    let mut response = next.run(request).await;
+
+    response.headers_mut().insert(X_TRACE_ID, trace_id);
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut HeaderInjector(response.headers_mut()))
+    });
+
+    response
+}
@@ -0,0 +1,422 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use axum::{
+    Json,
+    body::Body,
+    extract::{FromRequest, State},
+    middleware::Next,
+    response::IntoResponse,
+};
+use axum_middleware::{
+    BoxFuture, Decision, Quota, RateLimitStore, RateLimitStoreError, gcra, unix_nanos,
+};
+use base64::{Engine, prelude::BASE64_STANDARD_NO_PAD};
+use client_ip::ClientIp;
+use contextual::Context;
+use error_kind::ErrorKind;
+use error_response::ErrorResponse;
+use http::{HeaderMap, Request, Response, StatusCode};
+use serde::Deserialize;
+
+use crate::{
+    HELP, RateLimitKey, RateLimitRule, RateLimiterConfig,
+    core::{AccessToken, Basic, Credentials, Payload, Peppers, SessionId},
+};
+
+/// `/login` and `/signup` bodies are tiny. Anything bigger is not buffered
+/// just to look for a `username`, so that the limiter can't be used to exhaust memory.
+const MAX_BUFFERED_BODY: usize = 64 * 1024;
+
+/// How often keys whose bucket has filled up again are evicted from the store.
+pub const EVICTION_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Optimistic concurrency retries of [`SqliteStore::check`] before giving up on a contended key.
+const MAX_ATTEMPTS: usize = 8;
+
+/// Every rule that matches a request is checked, in order, until one rejects it.
+/// The `RateLimit-*` headers describe the most restrictive of them.
+pub struct RateLimits {
+    rules: Vec<RateLimitRule>,
+    store: Arc<dyn RateLimitStore>,
+    pool: sqlx::Pool<sqlx::Sqlite>,
+    peppers: Peppers,
+    on_reject: Option<Box<dyn Fn() + Send + Sync>>,
+}
+
+impl RateLimits {
+    /// `global` applies to every route per client IP, ahead of `rules`.
+    pub fn new(
+        global: RateLimiterConfig,
+        rules: Vec<RateLimitRule>,
+        store: Arc<dyn RateLimitStore>,
+        pool: sqlx::Pool<sqlx::Sqlite>,
+        peppers: Peppers,
+    ) -> Self {
+        let global = RateLimitRule {
+            route: "*".into(),
+            rate: global,
+            key: RateLimitKey::Ip,
+        };
+
+        Self {
+            rules: std::iter::once(global).chain(rules).collect(),
+            store,
+            pool,
+            peppers,
+            on_reject: None,
+        }
+    }
+
+    /// Called every time a request is rejected, e.g. to count rejections in metrics.
+    #[cfg_attr(not(feature = "metrics"), allow(dead_code))]
+    pub fn on_reject(mut self, hook: impl Fn() + Send + Sync + 'static) -> Self {
+        self.on_reject = Some(Box::new(hook));
+        self
+    }
+}
+
+impl RateLimitRule {
+    pub fn matches(&self, path: &str) -> bool {
+        match self.route.strip_suffix('*') {
+            Some(prefix) => path.starts_with(prefix),
+            None => path == self.route,
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum RateLimitError {
+    #[error("too many requests :: {rule}")]
+    TooManyRequests { rule: String, decision: Decision },
+
+    #[error("request body larger than {MAX_BUFFERED_BODY} bytes")]
+    PayloadTooLarge,
+}
+
+pub async fn rate_limit(
+    State(rate_limits): State<Arc<RateLimits>>,
+    request: Request<Body>,
+    next: Next,
+) -> Response<Body> {
+    match check(&rate_limits, request).await {
+        Ok((request, decision)) => {
+            let mut response = next.run(request).await;
+            if let Some(decision) = decision {
+                response.headers_mut().extend(decision.headers());
+            }
+            response
+        }
+        Err(err) => {
+            if let (RateLimitError::TooManyRequests { .. }, Some(on_reject)) =
+                (&err, &rate_limits.on_reject)
+            {
+                on_reject();
+            }
+            err.into_response()
+        }
+    }
+}
+
+async fn check(
+    rate_limits: &RateLimits,
+    request: Request<Body>,
+) -> Result<(Request<Body>, Option<Decision>), RateLimitError> {
+    let path = request.uri().path().to_string();
+    let mut request = request;
+    let mut most_restrictive: Option<Decision> = None;
+
+    for rule in &rate_limits.rules {
+        if !rule.matches(&path) {
+            continue;
+        }
+
+        let key;
+        (request, key) = self::key(rule.key, request, rate_limits).await?;
+
+        // e.g. a `user_id` rule on an anonymous request
+        let Some(key) = key else {
+            continue;
+        };
+
+        let quota = Quota {
+            limit: rule.rate.limit,
+            interval: rule.rate.interval,
+        };
+
+        let decision = match rate_limits
+            .store
+            .check(format!("{rule} :: {key}"), quota)
+            .await
+        {
+            Ok(decision) => decision,
+            Err(_err) => {
+                // fail open, an unavailable store shouldn't take the whole service down with it
+                #[cfg(feature = "tracing")]
+                tracing::error!(%rule, "rate limit store :: {:?}", _err);
+
+                continue;
+            }
+        };
+
+        if !decision.allowed {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(%rule, "rate limited");
+
+            return Err(RateLimitError::TooManyRequests {
+                rule: rule.to_string(),
+                decision,
+            });
+        }
+
+        if most_restrictive.is_none_or(|current| decision.remaining < current.remaining) {
+            most_restrictive = Some(decision);
+        }
+    }
+
+    Ok((request, most_restrictive))
+}
+
+/// Hands the request back because finding the `username` may consume the body.
+async fn key(
+    key: RateLimitKey,
+    request: Request<Body>,
+    rate_limits: &RateLimits,
+) -> Result<(Request<Body>, Option<String>), RateLimitError> {
+    let headers = request.headers();
+
+    let key = match key {
+        // unknown only when served without `ConnectInfo`, and better unlimited than all in one bucket
+        RateLimitKey::Ip => request
+            .extensions()
+            .get::<ClientIp>()
+            .map(|ClientIp(ip_addr)| ip_addr.to_string()),
+        RateLimitKey::Username => return username(request).await,
+        RateLimitKey::UserId => user_id(headers, &rate_limits.pool, &rate_limits.peppers)
+            .await
+            .map(|id| id.to_string()),
+        // peppered like the stored hash, since the `sqlite` store keeps the key in the database
+        RateLimitKey::Token => match (
+            AccessToken::try_from_headers(headers),
+            rate_limits.peppers.versions(),
+        ) {
+            (Ok(Some(token)), Ok(peppers)) => {
+                Some(BASE64_STANDARD_NO_PAD.encode(token.hash_hmac_sha256(&peppers[0].secret)))
+            }
+            _ => None,
+        },
+    };
+
+    Ok((request, key))
+}
+
+/// From `Authorization: Basic`, else from the `username` field of a json or form body.
+/// Normalized like on login, so that `ｊｏｅ` (fullwidth) shares the limit of `joe`.
+async fn username(
+    request: Request<Body>,
+) -> Result<(Request<Body>, Option<String>), RateLimitError> {
+    #[derive(Deserialize)]
+    struct Username {
+        username: String,
+    }
+
+    if let Ok(Some(Basic { username, .. })) = Basic::try_from_headers(request.headers()) {
+        return Ok((request, Some(validation::normalize_username(&username))));
+    }
+
+    let (parts, body) = request.into_parts();
+    let bytes = axum::body::to_bytes(body, MAX_BUFFERED_BODY)
+        .await
+        .map_err(|_| RateLimitError::PayloadTooLarge)?;
+
+    let username = Payload::<Username>::from_request(
+        Request::from_parts(parts.clone(), Body::from(bytes.clone())),
+        &(),
+    )
+    .await
+    .ok()
+    .map(|Payload(Username { username })| validation::normalize_username(&username));
+
+    Ok((Request::from_parts(parts, Body::from(bytes)), username))
+}
+
+/// Only for session and access token credentials.
+/// `Basic` credentials would cost a bcrypt verification per request and are limited per `username` instead.
+async fn user_id(
+    headers: &HeaderMap,
+    pool: &sqlx::Pool<sqlx::Sqlite>,
+    peppers: &Peppers,
+) -> Option<i64> {
+    if let Ok(Some(access_token)) = AccessToken::try_from_headers(headers) {
+        let peppers = peppers.versions().ok()?;
+        return access_token
+            .info(pool, &peppers)
+            .await
+            .ok()
+            .flatten()
+            .map(|info| info.user_id);
+    }
+
+    if let Ok(Some(session_id)) = SessionId::try_from_headers(headers) {
+        let peppers = peppers.versions().ok()?;
+        return session_id
+            .info(pool, &peppers)
+            .await
+            .ok()
+            .flatten()
+            .map(|info| info.user_id);
+    }
+
+    None
+}
+
+/// Shares limits between the processes using the same database.
+pub struct SqliteStore {
+    pool: sqlx::Pool<sqlx::Sqlite>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SqliteStoreError {
+    #[error("{0}")]
+    Sqlx(#[from] contextual::Error<sqlx::Error>),
+
+    #[error("rate limit key contended for {MAX_ATTEMPTS} attempts")]
+    Contended,
+}
+
+impl SqliteStore {
+    pub fn new(pool: sqlx::Pool<sqlx::Sqlite>) -> Self {
+        Self { pool }
+    }
+
+    /// Compare-and-swap on `tat`, so that concurrent requests
+    /// (possibly from other processes) can't both spend the last slot.
+    async fn try_check(&self, key: &str, quota: Quota) -> Result<Decision, SqliteStoreError> {
+        for _ in 0..MAX_ATTEMPTS {
+            let now = unix_nanos(SystemTime::now());
+
+            let tat = sqlx::query_scalar!("SELECT tat FROM rate_limits WHERE key = ?", key)
+                .fetch_optional(&self.pool)
+                .await
+                .context("select rate limit tat")?;
+
+            let (decision, new_tat) = gcra(tat.map(|tat| tat as u64), now, quota);
+            let Some(new_tat) = new_tat.map(|new_tat| new_tat as i64) else {
+                return Ok(decision);
+            };
+
+            let swapped = match tat {
+                Some(tat) => sqlx::query!(
+                    "UPDATE rate_limits SET tat = ? WHERE key = ? AND tat = ?",
+                    new_tat,
+                    key,
+                    tat
+                )
+                .execute(&self.pool)
+                .await
+                .context("update rate limit tat")?,
+                None => sqlx::query!(
+                    "INSERT INTO rate_limits (key, tat) VALUES (?, ?) ON CONFLICT DO NOTHING",
+                    key,
+                    new_tat
+                )
+                .execute(&self.pool)
+                .await
+                .context("insert rate limit tat")?,
+            };
+
+            if swapped.rows_affected() == 1 {
+                return Ok(decision);
+            }
+        }
+
+        Err(SqliteStoreError::Contended)
+    }
+
+    async fn try_evict(&self) -> Result<u64, SqliteStoreError> {
+        let now = unix_nanos(SystemTime::now()) as i64;
+
+        let evicted = sqlx::query!("DELETE FROM rate_limits WHERE tat <= ?", now)
+            .execute(&self.pool)
+            .await
+            .context("evict rate limits")?;
+
+        Ok(evicted.rows_affected())
+    }
+}
+
+impl RateLimitStore for SqliteStore {
+    fn check(
+        &self,
+        key: String,
+        quota: Quota,
+    ) -> BoxFuture<'_, Result<Decision, RateLimitStoreError>> {
+        Box::pin(async move { Ok(self.try_check(&key, quota).await?) })
+    }
+
+    fn evict(&self) -> BoxFuture<'_, Result<u64, RateLimitStoreError>> {
+        Box::pin(async move { Ok(self.try_evict().await?) })
+    }
+}
+
+/// Periodically evicts idle keys until `shutdown` is triggered.
+pub async fn evict_idle(store: Arc<dyn RateLimitStore>, shutdown: crate::Shutdown) {
+    loop {
+        tokio::select! {
+            () = shutdown.triggered() => return,
+            () = tokio::time::sleep(EVICTION_INTERVAL) => {}
+        }
+
+        let _result = store.evict().await;
+
+        #[cfg(feature = "tracing")]
+        match _result {
+            Ok(evicted) => tracing::debug!(evicted, "rate limit keys evicted"),
+            Err(err) => tracing::error!("rate limit store :: {:?}", err),
+        }
+    }
+}
+
+impl ErrorKind for RateLimitError {
+    fn kind(&self) -> String {
+        match self {
+            RateLimitError::TooManyRequests { .. } => "rate-limit.exceeded".into(),
+            RateLimitError::PayloadTooLarge => "rate-limit.payload-too-large".into(),
+        }
+    }
+}
+
+impl IntoResponse for RateLimitError {
+    fn into_response(self) -> axum::response::Response {
+        #[cfg(feature = "tracing")]
+        tracing::info!("{:?}", self);
+
+        let (status, headers) = match &self {
+            RateLimitError::TooManyRequests { decision, .. } => {
+                (StatusCode::TOO_MANY_REQUESTS, decision.headers())
+            }
+            RateLimitError::PayloadTooLarge => (StatusCode::PAYLOAD_TOO_LARGE, HeaderMap::new()),
+        };
+
+        let message = match &self {
+            RateLimitError::TooManyRequests { decision, .. } => format!(
+                "{self} :: retry after {}s",
+                decision.reset.as_secs_f64().ceil() as u64
+            ),
+            RateLimitError::PayloadTooLarge => self.to_string(),
+        };
+
+        (
+            status,
+            headers,
+            Json(
+                ErrorResponse::new(message)
+                    .with_kind(self.kind())
+                    .with_help(HELP.into()),
+            ),
+        )
+            .into_response()
+    }
+}
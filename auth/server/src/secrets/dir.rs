@@ -0,0 +1,72 @@
+use std::{fs, io, path::PathBuf};
+
+use zeroize::Zeroizing;
+
+use super::{SecretStore, SecretStoreError, validate_name, write_atomic};
+
+/// Secrets are plaintext files in `dir`, named after the secret.
+#[derive(Debug, Clone)]
+pub struct DirStore {
+    dir: PathBuf,
+}
+
+impl DirStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn path(&self, name: &str) -> Result<PathBuf, SecretStoreError> {
+        validate_name(name)?;
+        Ok(self.dir.join(name))
+    }
+}
+
+impl SecretStore for DirStore {
+    fn read(&self, name: &str) -> Result<Option<Zeroizing<Vec<u8>>>, SecretStoreError> {
+        match fs::read(self.path(name)?) {
+            Ok(secret) => Ok(Some(Zeroizing::new(secret))),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn write(&self, name: &str, secret: &[u8]) -> Result<(), SecretStoreError> {
+        let path = self.path(name)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        Ok(write_atomic(&path, secret)?)
+    }
+
+    fn remove(&self, name: &str) -> Result<(), SecretStoreError> {
+        match fs::remove_file(self.path(name)?) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn list(&self, dir: &str) -> Result<Vec<String>, SecretStoreError> {
+        let entries = match fs::read_dir(self.path(dir)?) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut names = vec![];
+        for entry in entries {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+
+            // leftovers of an interrupted `write_atomic`
+            if !entry.file_type()?.is_file() || name.ends_with(".tmp") {
+                continue;
+            }
+
+            names.push(name);
+        }
+
+        Ok(names)
+    }
+}
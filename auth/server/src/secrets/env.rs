@@ -0,0 +1,64 @@
+use base64::{Engine, prelude::BASE64_STANDARD};
+use zeroize::Zeroizing;
+
+use super::{SecretStore, SecretStoreError, validate_name};
+
+/// Secrets are base64 encoded environment variables named `<prefix><NAME>`,
+/// e.g. `AUTH_SECRET_HMAC` for `hmac`.
+///
+/// Read-only, so keys can't be rotated through the server
+/// and there are no previous versions to fall back on.
+#[derive(Debug, Clone)]
+pub struct EnvStore {
+    prefix: String,
+}
+
+impl EnvStore {
+    pub fn new(prefix: String) -> Self {
+        Self { prefix }
+    }
+
+    /// Uppercased, with anything that isn't alphanumeric replaced by `_`.
+    pub fn var(&self, name: &str) -> String {
+        let name = name
+            .chars()
+            .map(|c| match c.is_ascii_alphanumeric() {
+                true => c.to_ascii_uppercase(),
+                false => '_',
+            })
+            .collect::<String>();
+
+        format!("{}{name}", self.prefix)
+    }
+}
+
+impl SecretStore for EnvStore {
+    fn read(&self, name: &str) -> Result<Option<Zeroizing<Vec<u8>>>, SecretStoreError> {
+        validate_name(name)?;
+
+        let Some(value) = std::env::var_os(self.var(name)) else {
+            return Ok(None);
+        };
+
+        let value = Zeroizing::new(value.into_string().map_err(|_| {
+            SecretStoreError::InvalidData(format!("{} is not valid unicode", self.var(name)))
+        })?);
+
+        BASE64_STANDARD
+            .decode(value.trim())
+            .map(|secret| Some(Zeroizing::new(secret)))
+            .map_err(|err| SecretStoreError::InvalidData(format!("{} :: {err}", self.var(name))))
+    }
+
+    fn write(&self, _name: &str, _secret: &[u8]) -> Result<(), SecretStoreError> {
+        Err(SecretStoreError::ReadOnly)
+    }
+
+    fn remove(&self, _name: &str) -> Result<(), SecretStoreError> {
+        Err(SecretStoreError::ReadOnly)
+    }
+
+    fn list(&self, _dir: &str) -> Result<Vec<String>, SecretStoreError> {
+        Ok(vec![])
+    }
+}
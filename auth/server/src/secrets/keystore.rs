@@ -0,0 +1,222 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use base64::{Engine, prelude::BASE64_STANDARD};
+use chacha20poly1305::{
+    KeyInit, XChaCha20Poly1305, XNonce,
+    aead::{Aead, Payload},
+};
+use rand::RngCore;
+use zeroize::Zeroizing;
+
+use super::{SecretStore, SecretStoreError, validate_name, write_atomic};
+
+/// Identifies the file format, and is authenticated along with the secrets.
+const MAGIC: &[u8; 8] = b"monaks\x00\x01";
+const NONCE_LEN: usize = 24;
+
+type Entries = BTreeMap<String, Zeroizing<Vec<u8>>>;
+
+/// All secrets in a single file, encrypted with XChaCha20-Poly1305 under a [`MasterKey`]:
+/// `MAGIC || nonce || ciphertext`, with a fresh random nonce on every write.
+///
+/// The file is read on every access, so that changes made by other processes are picked up,
+/// and rewritten atomically as a whole on every change.
+#[derive(Clone)]
+pub struct KeystoreStore {
+    path: PathBuf,
+    master_key: Arc<MasterKey>,
+
+    /// Serializes the read-modify-write cycles of this process.
+    write_lock: Arc<Mutex<()>>,
+}
+
+/// 32 bytes, base64 encoded wherever they come from.
+pub struct MasterKey(Zeroizing<[u8; 32]>);
+
+impl MasterKey {
+    pub fn from_base64(encoded: &str) -> Result<Self, SecretStoreError> {
+        let decoded = Zeroizing::new(
+            BASE64_STANDARD
+                .decode(encoded.trim())
+                .map_err(|err| SecretStoreError::InvalidData(format!("master key :: {err}")))?,
+        );
+
+        let key = <[u8; 32]>::try_from(decoded.as_slice()).map_err(|_| {
+            SecretStoreError::InvalidData(format!(
+                "master key :: expected 32 bytes, got {}",
+                decoded.len()
+            ))
+        })?;
+
+        Ok(Self(Zeroizing::new(key)))
+    }
+
+    pub fn from_env(var: &str) -> Result<Self, SecretStoreError> {
+        let encoded = Zeroizing::new(std::env::var(var).map_err(|err| {
+            SecretStoreError::InvalidData(format!("master key :: {var} :: {err}"))
+        })?);
+
+        Self::from_base64(&encoded)
+    }
+
+    /// Reads the key from an inherited file descriptor (e.g. `3<master.key`),
+    /// which keeps it out of the environment and of the command line.
+    pub fn from_fd(fd: i32) -> Result<Self, SecretStoreError> {
+        let encoded = Zeroizing::new(fs::read_to_string(format!("/dev/fd/{fd}"))?);
+        Self::from_base64(&encoded)
+    }
+}
+
+impl KeystoreStore {
+    pub fn new(path: PathBuf, master_key: MasterKey) -> Self {
+        Self {
+            path,
+            master_key: Arc::new(master_key),
+            write_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    fn load(&self) -> Result<Entries, SecretStoreError> {
+        let content = match fs::read(&self.path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Entries::new()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let Some((nonce, ciphertext)) = content
+            .strip_prefix(MAGIC.as_slice())
+            .filter(|rest| rest.len() >= NONCE_LEN)
+            .map(|rest| rest.split_at(NONCE_LEN))
+        else {
+            return Err(SecretStoreError::InvalidData(format!(
+                "{} is not a keystore",
+                self.path.display()
+            )));
+        };
+
+        let plaintext = Zeroizing::new(
+            self.cipher()
+                .decrypt(
+                    XNonce::from_slice(nonce),
+                    Payload {
+                        msg: ciphertext,
+                        aad: MAGIC,
+                    },
+                )
+                .map_err(|_| SecretStoreError::Decrypt)?,
+        );
+
+        decode(&plaintext)
+    }
+
+    fn save(&self, entries: &Entries) -> Result<(), SecretStoreError> {
+        let plaintext = encode(entries);
+
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::rng().fill_bytes(&mut nonce);
+
+        let ciphertext = self
+            .cipher()
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext.as_bytes(),
+                    aad: MAGIC,
+                },
+            )
+            .map_err(|_| SecretStoreError::InvalidData("unable to encrypt the keystore".into()))?;
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        Ok(write_atomic(
+            &self.path,
+            &[MAGIC.as_slice(), &nonce, &ciphertext].concat(),
+        )?)
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Entries)) -> Result<(), SecretStoreError> {
+        let _guard = self.write_lock.lock().unwrap();
+
+        let mut entries = self.load()?;
+        f(&mut entries);
+        self.save(&entries)
+    }
+
+    fn cipher(&self) -> XChaCha20Poly1305 {
+        XChaCha20Poly1305::new(self.master_key.0.as_slice().into())
+    }
+}
+
+/// One `<name> <base64>` line per secret.
+fn encode(entries: &Entries) -> Zeroizing<String> {
+    let mut encoded = Zeroizing::new(String::new());
+    for (name, secret) in entries {
+        encoded.push_str(name);
+        encoded.push(' ');
+        BASE64_STANDARD.encode_string(secret, &mut encoded);
+        encoded.push('\n');
+    }
+    encoded
+}
+
+fn decode(plaintext: &[u8]) -> Result<Entries, SecretStoreError> {
+    let plaintext = std::str::from_utf8(plaintext)
+        .map_err(|err| SecretStoreError::InvalidData(err.to_string()))?;
+
+    let mut entries = Entries::new();
+    for line in plaintext.lines() {
+        let Some((name, secret)) = line.split_once(' ') else {
+            return Err(SecretStoreError::InvalidData(
+                "malformed keystore entry".into(),
+            ));
+        };
+
+        let secret = BASE64_STANDARD
+            .decode(secret)
+            .map_err(|err| SecretStoreError::InvalidData(format!("{name} :: {err}")))?;
+
+        entries.insert(name.to_string(), Zeroizing::new(secret));
+    }
+
+    Ok(entries)
+}
+
+impl SecretStore for KeystoreStore {
+    fn read(&self, name: &str) -> Result<Option<Zeroizing<Vec<u8>>>, SecretStoreError> {
+        validate_name(name)?;
+        Ok(self.load()?.remove(name))
+    }
+
+    fn write(&self, name: &str, secret: &[u8]) -> Result<(), SecretStoreError> {
+        validate_name(name)?;
+        self.update(|entries| {
+            entries.insert(name.to_string(), Zeroizing::new(secret.to_vec()));
+        })
+    }
+
+    fn remove(&self, name: &str) -> Result<(), SecretStoreError> {
+        validate_name(name)?;
+        self.update(|entries| {
+            entries.remove(name);
+        })
+    }
+
+    fn list(&self, dir: &str) -> Result<Vec<String>, SecretStoreError> {
+        validate_name(dir)?;
+        let prefix = format!("{dir}/");
+
+        Ok(self
+            .load()?
+            .into_keys()
+            .filter_map(|name| name.strip_prefix(&prefix).map(str::to_string))
+            .filter(|name| !name.contains('/'))
+            .collect())
+    }
+}
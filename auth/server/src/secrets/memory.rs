@@ -0,0 +1,57 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use zeroize::Zeroizing;
+
+use super::{SecretStore, SecretStoreError, validate_name};
+
+/// Secrets only live as long as the process, e.g. for tests.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+    secrets: Arc<Mutex<BTreeMap<String, Zeroizing<Vec<u8>>>>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SecretStore for MemoryStore {
+    fn read(&self, name: &str) -> Result<Option<Zeroizing<Vec<u8>>>, SecretStoreError> {
+        validate_name(name)?;
+        Ok(self.secrets.lock().unwrap().get(name).cloned())
+    }
+
+    fn write(&self, name: &str, secret: &[u8]) -> Result<(), SecretStoreError> {
+        validate_name(name)?;
+        self.secrets
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), Zeroizing::new(secret.to_vec()));
+        Ok(())
+    }
+
+    fn remove(&self, name: &str) -> Result<(), SecretStoreError> {
+        validate_name(name)?;
+        self.secrets.lock().unwrap().remove(name);
+        Ok(())
+    }
+
+    fn list(&self, dir: &str) -> Result<Vec<String>, SecretStoreError> {
+        validate_name(dir)?;
+        let prefix = format!("{dir}/");
+
+        Ok(self
+            .secrets
+            .lock()
+            .unwrap()
+            .keys()
+            .filter_map(|name| name.strip_prefix(&prefix))
+            .filter(|name| !name.contains('/'))
+            .map(str::to_string)
+            .collect())
+    }
+}
@@ -0,0 +1,327 @@
+mod dir;
+mod env;
+mod keystore;
+mod memory;
+
+pub use dir::DirStore;
+pub use env::EnvStore;
+pub use keystore::{KeystoreStore, MasterKey};
+pub use memory::MemoryStore;
+
+use std::{
+    io,
+    path::Path,
+    sync::{
+        Arc, RwLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use time::OffsetDateTime;
+use zeroize::Zeroizing;
+
+/// Where the raw bytes of secrets live. Names are `/`-separated, e.g. `hmac` or `hmac.previous/<kid>`.
+pub trait SecretStore: Send + Sync {
+    /// `None` when there's no secret named `name`.
+    fn read(&self, name: &str) -> Result<Option<Zeroizing<Vec<u8>>>, SecretStoreError>;
+
+    /// Creates or replaces the secret named `name`.
+    fn write(&self, name: &str, secret: &[u8]) -> Result<(), SecretStoreError>;
+
+    /// Does nothing when there's no secret named `name`.
+    fn remove(&self, name: &str) -> Result<(), SecretStoreError>;
+
+    /// Names of the secrets directly under `dir`, e.g. `hmac.previous`, without the `dir/` prefix.
+    fn list(&self, dir: &str) -> Result<Vec<String>, SecretStoreError>;
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SecretStoreError {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+
+    #[error("secret not found :: {0}")]
+    NotFound(String),
+
+    #[error("invalid secret name :: {0}")]
+    InvalidName(String),
+
+    #[error("invalid secret :: {0}")]
+    InvalidData(String),
+
+    #[error("secret store is read-only")]
+    ReadOnly,
+
+    #[error(
+        "unable to decrypt the keystore, either the master key is wrong or the keystore is corrupted"
+    )]
+    Decrypt,
+}
+
+/// Versioned keys on top of a [`SecretStore`].
+///
+/// `<key>` holds the current version of a key
+/// and `<key>.previous/<kid>` the ones it replaced, until their retirement date.
+/// That way, whatever was signed with a previous version stays valid for a grace period after rotation.
+#[derive(Clone)]
+pub struct Secrets {
+    store: Arc<dyn SecretStore>,
+
+    /// Bumped whenever a key is rotated or reset, see [`CachedVersions`].
+    generation: Arc<AtomicU64>,
+}
+
+/// The [`Secrets::versions`] of one key, read once and then again only after the key is rotated
+/// or reset (through any clone of the [`Secrets`]), or once `ttl` has elapsed, e.g. after another
+/// instance rotated it. For keys needed on every request, since some stores (e.g. [`KeystoreStore`])
+/// read and decrypt a whole file on every access.
+#[derive(Clone)]
+pub struct CachedVersions {
+    secrets: Secrets,
+    key: &'static str,
+    ttl: Duration,
+    cached: Arc<RwLock<Option<Cached>>>,
+}
+
+struct Cached {
+    generation: u64,
+    loaded_at: Instant,
+    versions: Arc<[Secret]>,
+}
+
+/// One version of a key.
+#[derive(Clone)]
+pub struct Secret {
+    /// Derived from the secret itself, so that keys written before versioning also have one.
+    pub kid: String,
+    pub secret: Zeroizing<Vec<u8>>,
+
+    /// `None` for the current version.
+    pub retires_at: Option<OffsetDateTime>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PreviousVersion {
+    /// unix timestamp
+    retires_at: i64,
+    secret: String,
+}
+
+impl Secrets {
+    const DEFAULT_N_BYTES: usize = 32;
+
+    pub fn new(store: Arc<dyn SecretStore>) -> Self {
+        Self {
+            store,
+            generation: Arc::default(),
+        }
+    }
+
+    pub fn cached(&self, key: &'static str, ttl: Duration) -> CachedVersions {
+        CachedVersions {
+            secrets: self.clone(),
+            key,
+            ttl,
+            cached: Arc::default(),
+        }
+    }
+
+    /// The current version of `key`.
+    pub fn get(&self, key: &str) -> Result<Zeroizing<Vec<u8>>, SecretStoreError> {
+        self.store
+            .read(key)?
+            .ok_or_else(|| SecretStoreError::NotFound(key.to_string()))
+    }
+
+    /// The current version of `key` along with its `kid`, to sign with.
+    pub fn current(&self, key: &str) -> Result<Secret, SecretStoreError> {
+        let secret = self.get(key)?;
+        Ok(Secret {
+            kid: kid(&secret),
+            secret,
+            retires_at: None,
+        })
+    }
+
+    /// The current and the unretired previous versions of `key`, newest first.
+    pub fn versions(&self, key: &str) -> Result<Vec<Secret>, SecretStoreError> {
+        let mut versions = vec![self.current(key)?];
+
+        let now = OffsetDateTime::now_utc();
+        let mut previous = self
+            .previous(key)?
+            .into_iter()
+            .filter(|version| {
+                version
+                    .retires_at
+                    .is_some_and(|retires_at| retires_at > now)
+            })
+            .collect::<Vec<_>>();
+        previous.sort_by_key(|version| std::cmp::Reverse(version.retires_at));
+
+        versions.extend(previous);
+        Ok(versions)
+    }
+
+    /// Replaces the current version of `key` with a new random one.
+    /// The replaced version stays valid for `grace_period`, and retired versions are removed.
+    pub fn rotate(&self, key: &str, grace_period: Duration) -> Result<Secret, SecretStoreError> {
+        let previous_dir = previous_dir(key);
+
+        if let Some(secret) = self.store.read(key)? {
+            let retires_at = OffsetDateTime::now_utc() + grace_period;
+            let previous = PreviousVersion {
+                retires_at: retires_at.unix_timestamp(),
+                secret: BASE64_URL_SAFE_NO_PAD.encode(&secret),
+            };
+            let content = Zeroizing::new(
+                toml::to_string(&previous)
+                    .map_err(|err| SecretStoreError::InvalidData(err.to_string()))?,
+            );
+            self.store.write(
+                &format!("{previous_dir}/{}", kid(&secret)),
+                content.as_bytes(),
+            )?;
+        }
+
+        let now = OffsetDateTime::now_utc();
+        for version in self.previous(key)? {
+            if version
+                .retires_at
+                .is_some_and(|retires_at| retires_at <= now)
+            {
+                self.store
+                    .remove(&format!("{previous_dir}/{}", version.kid))?;
+            }
+        }
+
+        self.reset(key)?;
+        self.current(key)
+    }
+
+    /// Overwrites the current version of `key`, invalidating whatever was signed with it.
+    /// Prefer [`Secrets::rotate`].
+    pub fn reset(&self, key: &str) -> Result<(), SecretStoreError> {
+        let buf = {
+            let mut rng = rand::rng();
+            let mut buf = vec![0u8; Secrets::DEFAULT_N_BYTES];
+            rng.fill_bytes(&mut buf);
+            Zeroizing::new(buf)
+        };
+
+        self.store.write(key, &buf)?;
+        self.generation.fetch_add(1, Ordering::Release);
+        Ok(())
+    }
+
+    fn previous(&self, key: &str) -> Result<Vec<Secret>, SecretStoreError> {
+        let previous_dir = previous_dir(key);
+
+        let mut versions = vec![];
+        for kid in self.store.list(&previous_dir)? {
+            let Some(content) = self.store.read(&format!("{previous_dir}/{kid}"))? else {
+                continue;
+            };
+
+            let content = Zeroizing::new(
+                String::from_utf8(content.to_vec())
+                    .map_err(|err| SecretStoreError::InvalidData(err.to_string()))?,
+            );
+            let previous = toml::from_str::<PreviousVersion>(&content)
+                .map_err(|err| SecretStoreError::InvalidData(err.to_string()))?;
+
+            versions.push(Secret {
+                kid,
+                secret: Zeroizing::new(
+                    BASE64_URL_SAFE_NO_PAD
+                        .decode(&previous.secret)
+                        .map_err(|err| SecretStoreError::InvalidData(err.to_string()))?,
+                ),
+                retires_at: Some(
+                    OffsetDateTime::from_unix_timestamp(previous.retires_at)
+                        .map_err(|err| SecretStoreError::InvalidData(err.to_string()))?,
+                ),
+            });
+        }
+
+        Ok(versions)
+    }
+}
+
+impl CachedVersions {
+    /// The current version first, see [`Secrets::versions`].
+    pub fn get(&self) -> Result<Arc<[Secret]>, SecretStoreError> {
+        let generation = self.secrets.generation.load(Ordering::Acquire);
+
+        if let Some(cached) = &*self.cached.read().unwrap()
+            && cached.generation == generation
+            && cached.loaded_at.elapsed() < self.ttl
+        {
+            return Ok(cached.versions.clone());
+        }
+
+        let versions: Arc<[Secret]> = self.secrets.versions(self.key)?.into();
+        *self.cached.write().unwrap() = Some(Cached {
+            generation,
+            loaded_at: Instant::now(),
+            versions: versions.clone(),
+        });
+        Ok(versions)
+    }
+}
+
+fn previous_dir(key: &str) -> String {
+    format!("{key}.previous")
+}
+
+fn kid(secret: &[u8]) -> String {
+    BASE64_URL_SAFE_NO_PAD.encode(&Sha256::digest(secret)[..8])
+}
+
+/// Rejects names that could escape the store, e.g. `../../etc/passwd` for [`DirStore`].
+fn validate_name(name: &str) -> Result<(), SecretStoreError> {
+    let valid = !name.is_empty()
+        && name.split('/').all(|segment| {
+            !segment.is_empty()
+                && !segment.starts_with('.')
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        });
+
+    match valid {
+        true => Ok(()),
+        false => Err(SecretStoreError::InvalidName(name.to_string())),
+    }
+}
+
+/// Through a temporary file and a rename, so that readers never see a partially written secret.
+/// The file is only readable and writable by its owner.
+fn write_atomic(path: &Path, content: &[u8]) -> Result<(), io::Error> {
+    use std::io::Write;
+
+    let tmp = path.with_extension("tmp");
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut file = options.open(&tmp)?;
+
+    // `mode` only applies to newly created files, not to the leftover of an interrupted write
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+
+    file.write_all(content)?;
+    file.sync_all()?;
+    drop(file);
+
+    std::fs::rename(tmp, path)
+}
@@ -27,15 +27,23 @@ pub fn span<B>(request: &Request<B>) -> Span {
 
     let span = tracing::error_span!(
         "request",
-        %trace_id,
+        trace_id = tracing::field::Empty,
         method = %request.method(),
         uri = %request.uri(),
         ip = tracing::field::Empty
     );
 
-    #[cfg(feature = "client-ip")]
-    match client_ip::client_ip(&request) {
-        Some(ip_addr) => span.record("ip", tracing::field::display(ip_addr)),
+    #[cfg(feature = "otel")]
+    match crate::otel::set_parent(&span, request.headers()) {
+        Some(otel_trace_id) => span.record("trace_id", tracing::field::display(otel_trace_id)),
+        None => span.record("trace_id", trace_id),
+    };
+
+    #[cfg(not(feature = "otel"))]
+    span.record("trace_id", trace_id);
+
+    match request.extensions().get::<client_ip::ClientIp>() {
+        Some(client_ip::ClientIp(ip_addr)) => span.record("ip", tracing::field::display(ip_addr)),
         None => span.record("ip", "<unknown-ip>"),
     };
 
@@ -0,0 +1,132 @@
+mod shared;
+
+use axum::body::to_bytes;
+use base64::{Engine, prelude::BASE64_STANDARD};
+use shared::TestClient;
+use test_proc_macros::{email, password, username};
+
+#[tokio::test]
+async fn tokens_are_prefixed_and_checksummed() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let username = username!("user1");
+    let email = email!("user1@test.com");
+    let password = password!("Aa!1aaaa");
+
+    let mut client = TestClient::default().await;
+
+    client
+        .send(request!(
+            POST "/signup";
+            "host" => "localhost"
+            "content-type" => "application/x-www-form-urlencoded";
+            format!("username={}&email={}&password={}", username, email, password)
+        ))
+        .await
+        .status(201);
+
+    client
+        .grant_permission(username, "post:/access-token/generate")
+        .await;
+
+    let response = client
+        .send(request!(
+            POST "/access-token/generate";
+            "authorization" => format!("Basic {}", BASE64_STANDARD.encode(format!("{username}:{password}")))
+            "content-type" => "application/x-www-form-urlencoded";
+            "name=my-token&ttl_sec=3600"
+        ))
+        .await
+        .status(201)
+        .into_response();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let access_token = String::from_utf8(body.to_vec()).unwrap();
+
+    assert!(access_token.starts_with("mona_at_"));
+
+    client
+        .send(request!(
+            GET "/access-token/verify";
+            "authorization" => format!("Token {access_token}");
+        ))
+        .await
+        .status(200);
+
+    // tokens handed out before they were prefixed
+    let legacy = &access_token["mona_at_".len()..access_token.len() - 6];
+    client
+        .send(request!(
+            GET "/access-token/verify";
+            "authorization" => format!("Token {legacy}");
+        ))
+        .await
+        .status(200);
+
+    // a typo is caught by the checksum without looking the token up
+    let mut typo = access_token.clone().into_bytes();
+    typo[10] = if typo[10] == b'A' { b'B' } else { b'A' };
+    let typo = String::from_utf8(typo).unwrap();
+    client
+        .send(request!(
+            GET "/access-token/verify";
+            "authorization" => format!("Token {typo}");
+        ))
+        .await
+        .status(400)
+        .json_body(|body: serde_json::Value| {
+            assert_eq!(
+                body["kind"],
+                "auth.access-token.authorization-header.malformed"
+            );
+        })
+        .await;
+
+    // a session id is not an access token
+    let response = client
+        .send(request!(
+            POST "/login";
+            "content-type" => "application/x-www-form-urlencoded";
+            format!("username={}&password={}", username, password)
+        ))
+        .await
+        .status(200)
+        .into_response();
+    let session_id = response
+        .headers()
+        .get("set-cookie")
+        .expect("session cookie not set")
+        .to_str()
+        .unwrap()
+        .split(';')
+        .next()
+        .unwrap()
+        .strip_prefix("session_id=")
+        .expect("session cookie named session_id")
+        .to_string();
+
+    assert!(session_id.starts_with("mona_sess_"));
+
+    client
+        .send(request!(
+            GET "/access-token/verify";
+            "authorization" => format!("Token {session_id}");
+        ))
+        .await
+        .status(400)
+        .json_body(|body: serde_json::Value| {
+            assert_eq!(
+                body["kind"],
+                "auth.access-token.authorization-header.malformed"
+            );
+        })
+        .await;
+
+    client
+        .send(request!(
+            GET "/private";
+            "cookie" => format!("session_id={session_id}");
+        ))
+        .await
+        .status(200);
+}
@@ -0,0 +1,135 @@
+use std::{path::Path, process::Command};
+
+use tempfile::tempdir;
+
+fn config_check(config: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_auth"));
+    command
+        .env_clear()
+        .args(["config", "check", "--config"])
+        .arg(config);
+    command
+}
+
+fn config_file(extra: &str) -> String {
+    let mut config = String::from(
+        r#"
+        port = 8080
+        secrets_dir = "./secrets"
+        "#,
+    );
+
+    #[cfg(feature = "rate-limit")]
+    config.push_str("rate_limiter = \"10/min\"\n");
+
+    #[cfg(feature = "serve-dir")]
+    config.push_str("serve_dir = \"./ui\"\n");
+
+    config.push_str(extra);
+
+    config.push_str(
+        r#"
+        [database]
+        url = "sqlite://data.db"
+
+        [cookie]
+        domain = "example.com"
+        "#,
+    );
+
+    #[cfg(feature = "smtp")]
+    config.push_str(
+        r#"
+        [smtp]
+        relay = "127.0.0.1"
+        username = "mailer"
+        password = "hunter2"
+        senders_dir = "./senders"
+        templates_dir = "../templates"
+        "#,
+    );
+
+    config
+}
+
+#[test]
+fn precedence() {
+    let dir = tempdir().expect("unable to create temp dir");
+    let path = dir.path().join("auth.toml");
+    std::fs::write(&path, config_file("")).expect("unable to write config file");
+
+    let output = config_check(&path)
+        .env("PORT", "9000")
+        .env("COOKIE_SAME_SITE", "none")
+        .args(["--cookie-same-site", "lax"])
+        .output()
+        .expect("unable to run `auth config check`");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+
+    // env over file
+    assert!(stdout.contains("port = 9000"), "{stdout}");
+    // cli over env
+    assert!(stdout.contains(r#"same_site = "lax""#), "{stdout}");
+    // file over defaults
+    assert!(stdout.contains(r#"domain = "example.com""#), "{stdout}");
+    // defaults
+    assert!(stdout.contains("secure = true"), "{stdout}");
+
+    #[cfg(feature = "smtp")]
+    {
+        assert!(!stdout.contains("hunter2"), "{stdout}");
+        assert!(stdout.contains(r#"password = "<redacted>""#), "{stdout}");
+    }
+}
+
+#[test]
+fn invalid_config_file() {
+    let dir = tempdir().expect("unable to create temp dir");
+    let path = dir.path().join("auth.toml");
+    std::fs::write(&path, config_file("databse_url = \"sqlite://data.db\"\n"))
+        .expect("unable to write config file");
+
+    let output = config_check(&path)
+        .output()
+        .expect("unable to run `auth config check`");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown field `databse_url`"));
+}
+
+#[test]
+fn keystore_master_key() {
+    let dir = tempdir().expect("unable to create temp dir");
+    let path = dir.path().join("auth.toml");
+    std::fs::write(&path, config_file("[secret_store]\nkind = \"keystore\"\n"))
+        .expect("unable to write config file");
+
+    let output = config_check(&path)
+        .output()
+        .expect("unable to run `auth config check`");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("AUTH_MASTER_KEY"));
+
+    let output = config_check(&path)
+        .env("AUTH_MASTER_KEY", "c2hvcnQ=")
+        .output()
+        .expect("unable to run `auth config check`");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("expected 32 bytes"));
+
+    let output = config_check(&path)
+        .env(
+            "AUTH_MASTER_KEY",
+            "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=",
+        )
+        .output()
+        .expect("unable to run `auth config check`");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+    assert!(!stdout.contains("AAAAAAAA"), "{stdout}");
+}
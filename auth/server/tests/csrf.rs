@@ -0,0 +1,163 @@
+mod shared;
+
+use base64::{Engine, prelude::BASE64_STANDARD};
+use shared::TestClient;
+use test_proc_macros::{email, password, username};
+
+async fn session_cookie(client: &mut TestClient, username: &str, password: &str) -> String {
+    let response = client
+        .send(request!(
+            POST "/login";
+            "content-type" => "application/x-www-form-urlencoded";
+            format!("username={}&password={}", username, password)
+        ))
+        .await
+        .status(200)
+        .into_response();
+
+    let set_cookie = response
+        .headers()
+        .get("set-cookie")
+        .expect("session cookie not set")
+        .to_str()
+        .expect("non utf-8 set-cookie header");
+
+    set_cookie
+        .split(';')
+        .next()
+        .expect("empty set-cookie header")
+        .to_string()
+}
+
+#[tokio::test]
+async fn session_cookie_requires_same_origin() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let username = username!("user1");
+    let email = email!("user1@test.com");
+    let password = password!("Aa!1aaaa");
+
+    let mut client = TestClient::default().await;
+
+    client
+        .send(request!(
+            POST "/signup";
+            "host" => "localhost"
+            "content-type" => "application/x-www-form-urlencoded";
+            format!("username={}&email={}&password={}", username, email, password)
+        ))
+        .await
+        .status(201);
+
+    let cookie = session_cookie(&mut client, username, password).await;
+
+    client
+        .send(request!(
+            POST "/access-token/generate";
+            "cookie" => &cookie
+            "sec-fetch-site" => "cross-site"
+            "content-type" => "application/x-www-form-urlencoded";
+            "name=my-token"
+        ))
+        .await
+        .status(403)
+        .json_body(|body: serde_json::Value| {
+            assert_eq!(body["kind"], "auth.csrf.cross-site");
+        })
+        .await;
+
+    client
+        .send(request!(
+            POST "/access-token/generate";
+            "cookie" => &cookie
+            "host" => "localhost"
+            "origin" => "https://evil.example"
+            "content-type" => "application/x-www-form-urlencoded";
+            "name=my-token"
+        ))
+        .await
+        .status(403)
+        .json_body(|body: serde_json::Value| {
+            assert_eq!(body["kind"], "auth.csrf.origin-mismatch");
+        })
+        .await;
+
+    // past the csrf check, the fresh user lacks the permission to generate tokens
+    client
+        .send(request!(
+            POST "/access-token/generate";
+            "cookie" => &cookie
+            "sec-fetch-site" => "same-origin"
+            "content-type" => "application/x-www-form-urlencoded";
+            "name=my-token"
+        ))
+        .await
+        .status(403)
+        .json_body(|body: serde_json::Value| {
+            assert_eq!(body["kind"], "auth.permissions");
+        })
+        .await;
+
+    client
+        .send(request!(
+            POST "/access-token/generate";
+            "cookie" => &cookie
+            "host" => "localhost:8080"
+            "origin" => "http://localhost:8080"
+            "content-type" => "application/x-www-form-urlencoded";
+            "name=my-token"
+        ))
+        .await
+        .status(403)
+        .json_body(|body: serde_json::Value| {
+            assert_eq!(body["kind"], "auth.permissions");
+        })
+        .await;
+
+    client
+        .send(request!(
+            POST "/logout";
+            "cookie" => &cookie
+            "sec-fetch-site" => "cross-site";
+        ))
+        .await
+        .status(403);
+}
+
+#[tokio::test]
+async fn basic_authorization_is_exempt() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let username = username!("user1");
+    let email = email!("user1@test.com");
+    let password = password!("Aa!1aaaa");
+
+    let mut client = TestClient::default().await;
+
+    client
+        .send(request!(
+            POST "/signup";
+            "host" => "localhost"
+            "content-type" => "application/x-www-form-urlencoded";
+            format!("username={}&email={}&password={}", username, email, password)
+        ))
+        .await
+        .status(201);
+
+    client
+        .send(request!(
+            POST "/access-token/generate";
+            "authorization" => format!("Basic {}", BASE64_STANDARD.encode(format!("{username}:{password}")))
+            "sec-fetch-site" => "cross-site"
+            "content-type" => "application/x-www-form-urlencoded";
+            "name=my-token"
+        ))
+        .await
+        .status(403)
+        .json_body(|body: serde_json::Value| {
+            assert_eq!(body["kind"], "auth.permissions");
+        })
+        .await;
+}
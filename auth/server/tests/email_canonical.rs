@@ -0,0 +1,336 @@
+mod shared;
+
+use std::time::Duration;
+
+use shared::TestClient;
+use test_proc_macros::{email, password, username};
+
+#[tokio::test]
+async fn domains_are_compared_case_insensitively() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::default().await;
+
+    assert_eq!(
+        shared::signup(
+            &mut client,
+            username!("user1"),
+            email!("Joe@Bücher.example"),
+            password!("Aa!1aaaa")
+        )
+        .await
+        .0,
+        201
+    );
+
+    assert_eq!(
+        shared::signup(
+            &mut client,
+            username!("user2"),
+            email!("Joe@xn--bcher-kva.EXAMPLE"),
+            password!("Aa!1aaaa")
+        )
+        .await
+        .0,
+        409
+    );
+    assert_eq!(
+        shared::availability(
+            &mut client,
+            "/check/email-availability",
+            email!("Joe@BÜCHER.example")
+        )
+        .await,
+        409
+    );
+
+    // the local part is left as is by default
+    assert_eq!(
+        shared::availability(
+            &mut client,
+            "/check/email-availability",
+            email!("joe@bücher.example")
+        )
+        .await,
+        200
+    );
+    assert_eq!(
+        shared::availability(
+            &mut client,
+            "/check/email-availability",
+            email!("Joe+news@bücher.example")
+        )
+        .await,
+        200
+    );
+}
+
+#[tokio::test]
+async fn local_parts_are_folded_by_policy() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::with_opts(|opts| {
+        opts.email.fold_local_part = true;
+        opts.email.strip_plus_tags = true;
+    })
+    .await;
+
+    assert_eq!(
+        shared::signup(
+            &mut client,
+            username!("user1"),
+            email!("Joe+signup@Gmail.com"),
+            password!("Aa!1aaaa")
+        )
+        .await
+        .0,
+        201
+    );
+
+    assert_eq!(
+        shared::availability(
+            &mut client,
+            "/check/email-availability",
+            email!("joe@gmail.com")
+        )
+        .await,
+        409
+    );
+    assert_eq!(
+        shared::signup(
+            &mut client,
+            username!("user2"),
+            email!("JOE+other@gmail.com"),
+            password!("Aa!1aaaa")
+        )
+        .await
+        .0,
+        409
+    );
+
+    // plus tags are only stripped for the providers known to support them
+    assert_eq!(
+        shared::availability(
+            &mut client,
+            "/check/email-availability",
+            email!("joe+signup@example.com")
+        )
+        .await,
+        200
+    );
+}
+
+#[tokio::test]
+async fn existing_emails_are_canonicalized_on_startup() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::default().await;
+    assert_eq!(
+        shared::signup(
+            &mut client,
+            username!("user1"),
+            email!("Joe@Example.com"),
+            password!("Aa!1aaaa")
+        )
+        .await
+        .0,
+        201
+    );
+
+    let pool = client.pool().await;
+    let canonical = || async {
+        sqlx::query_as::<_, (Option<String>, Option<String>)>(
+            "SELECT email_canonical, email_canonical_kind FROM users",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap()
+    };
+    assert_eq!(
+        canonical().await,
+        (Some("Joe@example.com".into()), Some("domain".into()))
+    );
+
+    let database_url = pool
+        .connect_options()
+        .get_filename()
+        .to_string_lossy()
+        .to_string();
+
+    // folding local parts, without touching the database
+    let mut restarted = TestClient::with_opts(|opts| {
+        opts.database.url = database_url.clone();
+        opts.email.fold_local_part = true;
+    })
+    .await;
+
+    for _ in 0..100 {
+        if canonical().await.1.as_deref() == Some("domain+case") {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(
+        canonical().await,
+        (Some("joe@example.com".into()), Some("domain+case".into()))
+    );
+    assert_eq!(
+        shared::availability(
+            &mut restarted,
+            "/check/email-availability",
+            email!("JOE@example.com")
+        )
+        .await,
+        409
+    );
+
+    // and back
+    let mut restarted = TestClient::with_opts(|opts| {
+        opts.database.url = database_url;
+    })
+    .await;
+    for _ in 0..100 {
+        if canonical().await.1.as_deref() == Some("domain") {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(
+        canonical().await,
+        (Some("Joe@example.com".into()), Some("domain".into()))
+    );
+    assert_eq!(
+        shared::availability(
+            &mut restarted,
+            "/check/email-availability",
+            email!("Joe@example.com")
+        )
+        .await,
+        409
+    );
+    assert_eq!(
+        shared::availability(
+            &mut restarted,
+            "/check/email-availability",
+            email!("joe@example.com")
+        )
+        .await,
+        200
+    );
+}
+
+#[tokio::test]
+async fn canonical_forms_are_unique() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::default().await;
+    assert_eq!(
+        shared::signup(
+            &mut client,
+            username!("user1"),
+            email!("Joe@Example.com"),
+            password!("Aa!1aaaa")
+        )
+        .await
+        .0,
+        201
+    );
+
+    // as if of another canonicalization, so that `exists` compares it as is and lets the signup through
+    sqlx::query("UPDATE users SET email_canonical_kind = 'stale'")
+        .execute(&client.pool().await)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        shared::signup(
+            &mut client,
+            username!("user2"),
+            email!("Joe@EXAMPLE.com"),
+            password!("Aa!1aaaa")
+        )
+        .await,
+        (409, Some("email.exists".into()))
+    );
+}
+
+#[tokio::test]
+async fn emails_sharing_a_canonical_form_are_left_as_they_are() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::default().await;
+    for (username, email) in [
+        (username!("user1"), email!("Joe@example.com")),
+        (username!("user2"), email!("joe@example.com")),
+    ] {
+        assert_eq!(
+            shared::signup(&mut client, username, email, password!("Aa!1aaaa"))
+                .await
+                .0,
+            201
+        );
+    }
+
+    let pool = client.pool().await;
+    let canonical = || async {
+        sqlx::query_as::<_, (String, Option<String>, Option<String>)>(
+            "SELECT email, email_canonical, email_canonical_kind FROM users ORDER BY id",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap()
+    };
+
+    let database_url = pool
+        .connect_options()
+        .get_filename()
+        .to_string_lossy()
+        .to_string();
+    let mut restarted = TestClient::with_opts(|opts| {
+        opts.database.url = database_url;
+        opts.email.fold_local_part = true;
+    })
+    .await;
+
+    for _ in 0..100 {
+        if canonical().await[0].2.as_deref() == Some("domain+case") {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(
+        canonical().await,
+        vec![
+            (
+                "Joe@example.com".into(),
+                Some("joe@example.com".into()),
+                Some("domain+case".into())
+            ),
+            ("joe@example.com".into(), None, None),
+        ]
+    );
+
+    assert_eq!(
+        shared::availability(
+            &mut restarted,
+            "/check/email-availability",
+            email!("JOE@example.com")
+        )
+        .await,
+        409
+    );
+    assert_eq!(
+        shared::availability(
+            &mut restarted,
+            "/check/email-availability",
+            email!("joe@example.com")
+        )
+        .await,
+        409
+    );
+}
@@ -0,0 +1,147 @@
+mod shared;
+
+use shared::TestClient;
+use test_proc_macros::{email, password, username};
+
+#[tokio::test]
+async fn disposable_emails_are_rejected() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::default().await;
+
+    assert_eq!(
+        shared::availability(
+            &mut client,
+            "/check/email-availability",
+            email!("joe@mailinator.com")
+        )
+        .await,
+        400
+    );
+    assert_eq!(
+        shared::signup(
+            &mut client,
+            username!("user1"),
+            email!("joe@Mailinator.com"),
+            password!("Aa!1aaaa")
+        )
+        .await,
+        (400, Some("email.policy.disposable".into()))
+    );
+    assert_eq!(
+        shared::signup(
+            &mut client,
+            username!("user1"),
+            email!("joe@test.com"),
+            password!("Aa!1aaaa")
+        )
+        .await,
+        (201, None)
+    );
+
+    let mut client = TestClient::with_opts(|opts| opts.email.allow_disposable = true).await;
+    assert_eq!(
+        shared::signup(
+            &mut client,
+            username!("user1"),
+            email!("joe@mailinator.com"),
+            password!("Aa!1aaaa")
+        )
+        .await,
+        (201, None)
+    );
+}
+
+#[tokio::test]
+async fn disposable_domains_can_be_replaced() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("disposable_domains.txt");
+    std::fs::write(&path, "# ours\nthrowaway.example\n").unwrap();
+
+    let mut client =
+        TestClient::with_opts(|opts| opts.email.disposable_domains_file = Some(path)).await;
+
+    assert_eq!(
+        shared::availability(
+            &mut client,
+            "/check/email-availability",
+            email!("joe@eu.throwaway.example")
+        )
+        .await,
+        400
+    );
+    assert_eq!(
+        shared::availability(
+            &mut client,
+            "/check/email-availability",
+            email!("joe@mailinator.com")
+        )
+        .await,
+        200
+    );
+}
+
+#[tokio::test]
+async fn allowed_and_denied_domains() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::with_opts(|opts| {
+        opts.email.allowed_domains = vec!["test.com".into()];
+        opts.email.denied_domains = vec!["contractors.test.com".into()];
+    })
+    .await;
+
+    assert_eq!(
+        shared::signup(
+            &mut client,
+            username!("user1"),
+            email!("joe@example.com"),
+            password!("Aa!1aaaa")
+        )
+        .await,
+        (400, Some("email.policy.not-allowed".into()))
+    );
+    assert_eq!(
+        shared::signup(
+            &mut client,
+            username!("user1"),
+            email!("joe@contractors.test.com"),
+            password!("Aa!1aaaa")
+        )
+        .await,
+        (400, Some("email.policy.denied".into()))
+    );
+    assert_eq!(
+        shared::availability(
+            &mut client,
+            "/check/email-availability",
+            email!("joe@eu.test.com")
+        )
+        .await,
+        200
+    );
+    assert_eq!(
+        shared::signup(
+            &mut client,
+            username!("user1"),
+            email!("joe@test.com"),
+            password!("Aa!1aaaa")
+        )
+        .await,
+        (201, None)
+    );
+    assert_eq!(
+        shared::availability(
+            &mut client,
+            "/check/email-availability",
+            email!("joe@test.com")
+        )
+        .await,
+        409
+    );
+}
@@ -0,0 +1,107 @@
+mod shared;
+
+use shared::TestClient;
+
+#[tokio::test]
+async fn security_headers() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::default().await;
+
+    let response = client
+        .send(request!(GET "/heartbeat";;))
+        .await
+        .status(200)
+        .into_response();
+
+    let headers = response.headers();
+    assert!(headers.contains_key("strict-transport-security"));
+    assert_eq!(headers["x-content-type-options"], "nosniff");
+    assert_eq!(headers["referrer-policy"], "no-referrer");
+    assert_eq!(headers["cache-control"], "no-store");
+}
+
+#[tokio::test]
+async fn cors_disabled_by_default() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::default().await;
+
+    let response = client
+        .send(request!(
+            GET "/heartbeat";
+            "origin" => "https://app.example.com";
+        ))
+        .await
+        .status(200)
+        .into_response();
+
+    assert!(
+        !response
+            .headers()
+            .contains_key("access-control-allow-origin")
+    );
+}
+
+#[tokio::test]
+async fn cors_preflight() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::with_opts(|opts| {
+        opts.cors = auth::CorsConfig {
+            allowed_origins: vec!["https://app.example.com".into()],
+            allowed_methods: vec!["GET".into(), "POST".into()],
+            allowed_headers: vec!["content-type".into()],
+            allow_credentials: true,
+        }
+    })
+    .await;
+
+    let response = client
+        .send(request!(
+            OPTIONS "/login";
+            "origin" => "https://app.example.com"
+            "access-control-request-method" => "POST";
+        ))
+        .await
+        .is_success()
+        .into_response();
+
+    let headers = response.headers();
+    assert_eq!(
+        headers["access-control-allow-origin"],
+        "https://app.example.com"
+    );
+    assert_eq!(headers["access-control-allow-credentials"], "true");
+
+    let response = client
+        .send(request!(
+            OPTIONS "/login";
+            "origin" => "https://evil.example"
+            "access-control-request-method" => "POST";
+        ))
+        .await
+        .into_response();
+
+    assert!(
+        !response
+            .headers()
+            .contains_key("access-control-allow-origin")
+    );
+}
+
+#[tokio::test]
+#[should_panic(expected = "unable to create router")]
+async fn cors_wildcard_with_credentials() {
+    TestClient::with_opts(|opts| {
+        opts.cors = auth::CorsConfig {
+            allowed_origins: vec!["*".into()],
+            allow_credentials: true,
+            ..Default::default()
+        }
+    })
+    .await;
+}
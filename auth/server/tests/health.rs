@@ -0,0 +1,96 @@
+mod shared;
+
+use serde_json::{Value, json};
+use shared::TestClient;
+
+// nothing listens on the test smtp relay, so readiness can only fail with the `smtp` feature
+#[cfg(not(feature = "smtp"))]
+const READY: u16 = 200;
+#[cfg(feature = "smtp")]
+const READY: u16 = 503;
+
+#[tokio::test]
+async fn live() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::default().await;
+    client
+        .send(request!(GET "/health/live";;))
+        .await
+        .status(200);
+
+    client.shutdown().trigger();
+    client
+        .send(request!(GET "/health/live";;))
+        .await
+        .status(200);
+}
+
+#[tokio::test]
+async fn ready() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::default().await;
+
+    client
+        .send(request!(GET "/health/ready";;))
+        .await
+        .status(READY)
+        .json_body(|body: Value| {
+            assert_eq!(body["ready"], READY == 200);
+            for check in ["shutdown", "database", "migrations", "hmac", "pepper"] {
+                assert_eq!(body["checks"][check], json!({ "status": "ok" }), "{body}");
+            }
+
+            #[cfg(feature = "smtp")]
+            assert_eq!(body["checks"]["smtp"]["status"], "failed", "{body}");
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn not_ready_without_hmac_key() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::with_opts(|opts| {
+        opts.secret_store.kind = auth::SecretStoreKind::Dir;
+        std::fs::remove_file(opts.secrets_dir.join("hmac")).expect("unable to remove hmac secret")
+    })
+    .await;
+
+    client
+        .send(request!(GET "/health/ready";;))
+        .await
+        .status(503)
+        .json_body(|body: Value| {
+            assert_eq!(body["ready"], false);
+            assert_eq!(body["checks"]["hmac"]["status"], "failed", "{body}");
+            assert_eq!(body["checks"]["database"]["status"], "ok", "{body}");
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn not_ready_while_draining() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::default().await;
+    client.shutdown().trigger();
+
+    client
+        .send(request!(GET "/health/ready";;))
+        .await
+        .status(503)
+        .json_body(|body: Value| {
+            assert_eq!(body["ready"], false);
+            assert_eq!(
+                body["checks"]["shutdown"],
+                json!({ "status": "failed", "reason": "draining" })
+            );
+        })
+        .await;
+}
@@ -0,0 +1,78 @@
+#![cfg(feature = "smtp")]
+
+mod shared;
+
+use base64::{Engine, prelude::BASE64_STANDARD};
+use email::Email;
+use shared::TestClient;
+use signature::Signed;
+use test_proc_macros::{email, password, username};
+
+fn verification_token(secret: &[u8], kid: Option<&str>) -> String {
+    let email: Email = email!("user1@test.com").parse().unwrap();
+    let token = Signed::new(email).with_purpose("verify-email");
+    let token = match kid {
+        Some(kid) => token.with_kid(kid),
+        None => token,
+    };
+    token.encode(secret).expect("unable to sign token")
+}
+
+#[tokio::test]
+async fn rotation_keeps_previous_key_during_grace_period() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let username = username!("user1");
+    let password = password!("Aa!1aaaa");
+    let basic = format!(
+        "Basic {}",
+        BASE64_STANDARD.encode(format!("{username}:{password}"))
+    );
+
+    let mut client = TestClient::default().await;
+
+    client
+        .send(request!(
+            POST "/signup";
+            "host" => "localhost"
+            "content-type" => "application/x-www-form-urlencoded";
+            format!("username={}&email={}&password={}", username, email!("user1@test.com"), password)
+        ))
+        .await
+        .status(201);
+    client.grant_permission(username, "post:/rotate-key").await;
+
+    let old_secret = client.secrets().get("hmac").unwrap();
+    let legacy_token = verification_token(&old_secret, None);
+    let unknown_kid_token = verification_token(&old_secret, Some("unknown"));
+
+    let rotate = |grace_period_secs: u64| {
+        request!(
+            POST "/rotate-key";
+            "authorization" => &basic
+            "content-type" => "application/json";
+            format!(r#"{{"key": "hmac", "grace_period_secs": {grace_period_secs}}}"#)
+        )
+    };
+
+    client.send(rotate(3600)).await.status(200);
+
+    let new_secret = client.secrets().get("hmac").unwrap();
+    assert_ne!(old_secret, new_secret);
+
+    let verify = |token: &str| request!(GET format!("/verify-email?token={token}");;);
+
+    // signed before rotation, without a `kid`, checked against every unretired version
+    client.send(verify(&legacy_token)).await.status(200);
+    client.send(verify(&unknown_kid_token)).await.status(400);
+
+    // without a grace period, the replaced key is retired right away
+    let replaced_token = verification_token(&new_secret, None);
+    client.send(verify(&replaced_token)).await.status(200);
+    client.send(rotate(0)).await.status(200);
+    client.send(verify(&replaced_token)).await.status(400);
+
+    // while the one replaced earlier is still in its grace period
+    client.send(verify(&legacy_token)).await.status(200);
+}
@@ -0,0 +1,82 @@
+#![cfg(feature = "metrics")]
+
+mod shared;
+
+use base64::{Engine, prelude::BASE64_STANDARD};
+use shared::TestClient;
+use test_proc_macros::{email, password, username};
+
+#[tokio::test]
+async fn metrics() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let username = username!("user1");
+    let email = email!("user1@test.com");
+    let password = password!("Aa!1aaaa");
+    let basic = format!(
+        "Basic {}",
+        BASE64_STANDARD.encode(format!("{username}:{password}"))
+    );
+
+    let mut client = TestClient::default().await;
+
+    client
+        .send(request!(
+            POST "/signup";
+            "host" => "localhost"
+            "content-type" => "application/x-www-form-urlencoded";
+            format!("username={}&email={}&password={}", username, email, password)
+        ))
+        .await
+        .status(201);
+
+    client
+        .send(request!(
+            POST "/login";
+            "content-type" => "application/x-www-form-urlencoded";
+            format!("username={}&password={}", username, "wrong")
+        ))
+        .await
+        .status(401);
+
+    client
+        .send(request!(
+            POST "/login";
+            "content-type" => "application/x-www-form-urlencoded";
+            format!("username={}&password={}", username, password)
+        ))
+        .await
+        .status(200);
+
+    client.send(request!(GET "/metrics";;)).await.status(401);
+
+    client
+        .send(request!(GET "/metrics"; "authorization" => &basic;))
+        .await
+        .status(403);
+
+    client.grant_permission(username, "get:/metrics").await;
+
+    let response = client
+        .send(request!(GET "/metrics"; "authorization" => &basic;))
+        .await
+        .status(200)
+        .into_response();
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("unable to read response body");
+    let body = String::from_utf8_lossy(&body);
+
+    for line in [
+        r#"auth_logins_total{outcome="failure"} 1"#,
+        r#"auth_logins_total{outcome="success"} 1"#,
+        r#"http_requests_total{method="POST",route="/signup",status="2xx"} 1"#,
+        r#"http_requests_total{method="GET",route="/metrics",status="4xx"} 2"#,
+        r#"auth_sqlite_pool_connections{state="max"}"#,
+        "auth_sessions_active 1",
+    ] {
+        assert!(body.contains(line), "`{line}` not found in\n{body}");
+    }
+}
@@ -0,0 +1,87 @@
+#![cfg(feature = "otel")]
+
+mod shared;
+
+use std::sync::{Arc, Mutex};
+
+use axum::{Router, body::Bytes, extract::State, routing::post};
+use shared::TestClient;
+use tokio::net::TcpListener;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+/// Stands in for an OTLP/HTTP collector and records the export requests it receives.
+async fn mock_collector() -> (String, Arc<Mutex<Vec<Bytes>>>) {
+    let exports = Arc::new(Mutex::new(vec![]));
+
+    let router = Router::new()
+        .route(
+            "/v1/traces",
+            post(
+                |State(exports): State<Arc<Mutex<Vec<Bytes>>>>, body: Bytes| async move {
+                    exports.lock().unwrap().push(body);
+                },
+            ),
+        )
+        .with_state(exports.clone());
+
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("unable to bind mock collector");
+    let addr = listener.local_addr().expect("mock collector local_addr");
+    tokio::spawn(async move { axum::serve(listener, router).await });
+
+    (format!("http://{addr}/v1/traces"), exports)
+}
+
+// multi_thread so that the mock collector keeps serving while `force_flush` blocks this thread
+#[tokio::test(flavor = "multi_thread")]
+async fn trace_context() {
+    let (endpoint, exports) = mock_collector().await;
+
+    let provider = auth::otel::tracer_provider(&auth::OtelConfig {
+        endpoint: Some(endpoint),
+        service_name: "auth-test".into(),
+    })
+    .expect("unable to build tracer provider");
+
+    let _guard = tracing_subscriber::registry()
+        .with(auth::otel::layer(&provider))
+        .set_default();
+
+    let mut client = TestClient::default().await;
+
+    let response = client
+        .send(request!(
+            GET "/heartbeat";
+            "traceparent" => "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        ))
+        .await
+        .status(200)
+        .into_response();
+
+    let headers = response.headers();
+    assert_eq!(headers["x-trace-id"], "4bf92f3577b34da6a3ce929d0e0e4736");
+
+    let traceparent = headers["traceparent"].to_str().unwrap();
+    assert!(traceparent.starts_with("00-4bf92f3577b34da6a3ce929d0e0e4736-"));
+    assert!(!traceparent.contains("00f067aa0ba902b7"), "{traceparent}");
+
+    let response = client
+        .send(request!(GET "/heartbeat";;))
+        .await
+        .status(200)
+        .into_response();
+
+    let headers = response.headers();
+    let trace_id = headers["x-trace-id"].to_str().unwrap();
+    assert_eq!(trace_id.len(), 32, "{trace_id}");
+    assert!(
+        headers["traceparent"]
+            .to_str()
+            .unwrap()
+            .starts_with(&format!("00-{trace_id}-"))
+    );
+
+    provider.force_flush().expect("unable to flush spans");
+    assert!(!exports.lock().unwrap().is_empty());
+}
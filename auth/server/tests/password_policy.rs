@@ -0,0 +1,70 @@
+mod shared;
+
+use shared::TestClient;
+use test_proc_macros::{email, username};
+
+#[tokio::test]
+async fn guessable_passwords_are_rejected() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::default().await;
+
+    for password in ["Password1!", "short", "Joe.Smith2024", "joe.smith@test.com"] {
+        assert_eq!(
+            shared::signup(
+                &mut client,
+                username!("joe_smith"),
+                email!("joe.smith@test.com"),
+                password
+            )
+            .await,
+            (400, Some("password.weak".into())),
+            "{password}"
+        );
+    }
+
+    assert_eq!(
+        shared::signup(
+            &mut client,
+            username!("joe_smith"),
+            email!("joe.smith@test.com"),
+            "correct horse battery staple extra"
+        )
+        .await,
+        (201, None)
+    );
+}
+
+#[tokio::test]
+async fn policy_is_configurable() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::with_opts(|opts| {
+        opts.password.require_digit = true;
+        opts.password.min_score = 4;
+    })
+    .await;
+
+    assert_eq!(
+        shared::signup(
+            &mut client,
+            username!("joe_smith"),
+            email!("joe.smith@test.com"),
+            "correct horse battery staple extra"
+        )
+        .await,
+        (400, Some("password.weak".into()))
+    );
+    assert_eq!(
+        shared::signup(
+            &mut client,
+            username!("joe_smith"),
+            email!("joe.smith@test.com"),
+            "correct horse battery staple 42"
+        )
+        .await,
+        (201, None)
+    );
+}
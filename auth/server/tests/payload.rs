@@ -0,0 +1,113 @@
+mod shared;
+
+use shared::TestClient;
+use test_proc_macros::{email, password, username};
+
+#[tokio::test]
+async fn json_onboarding_flow() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let username = username!("user1");
+    let email = email!("user1@test.com");
+    let password = password!("Aa!1aaaa");
+
+    let mut client = TestClient::default().await;
+
+    client
+        .send(request!(
+            POST "/signup";
+            "host" => "localhost"
+            "content-type" => "application/json";
+            serde_json::json!({ "username": username, "email": email, "password": password }).to_string()
+        ))
+        .await
+        .status(201);
+
+    client
+        .send(request!(
+            POST "/login";
+            "content-type" => "application/json; charset=utf-8";
+            serde_json::json!({ "username": username, "password": password }).to_string()
+        ))
+        .await
+        .status(200);
+}
+
+#[tokio::test]
+async fn unsupported_content_type() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::default().await;
+
+    client
+        .send(request!(
+            POST "/login";
+            "content-type" => "text/plain";
+            "username=user1&password=Aa!1aaaa"
+        ))
+        .await
+        .status(415)
+        .json_body(|body: serde_json::Value| {
+            assert_eq!(body["kind"], "payload.content-type.unsupported");
+        })
+        .await;
+
+    client.send(request!(POST "/login";;)).await.status(415);
+}
+
+#[tokio::test]
+async fn malformed_json() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::default().await;
+
+    client
+        .send(request!(
+            POST "/login";
+            "content-type" => "application/json";
+            r#"{ "username": "user1", "password": "#
+        ))
+        .await
+        .status(400)
+        .json_body(|body: serde_json::Value| {
+            assert_eq!(body["kind"], "payload.json.syntax");
+        })
+        .await;
+
+    client
+        .send(request!(
+            POST "/login";
+            "content-type" => "application/json";
+            r#"{ "username": "user1" }"#
+        ))
+        .await
+        .status(422)
+        .json_body(|body: serde_json::Value| {
+            assert_eq!(body["kind"], "payload.json.data");
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn malformed_form() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::default().await;
+
+    client
+        .send(request!(
+            POST "/login";
+            "content-type" => "application/x-www-form-urlencoded";
+            "username=user1"
+        ))
+        .await
+        .status(422)
+        .json_body(|body: serde_json::Value| {
+            assert_eq!(body["kind"], "payload.form");
+        })
+        .await;
+}
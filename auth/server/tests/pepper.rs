@@ -0,0 +1,182 @@
+mod shared;
+
+use std::time::Duration;
+
+use shared::TestClient;
+use test_proc_macros::{email, password, username};
+use time::OffsetDateTime;
+use token::Token;
+
+async fn signup(client: &mut TestClient) -> i64 {
+    assert_eq!(
+        shared::signup(
+            client,
+            username!("user1"),
+            email!("user1@test.com"),
+            password!("Aa!1aaaa")
+        )
+        .await,
+        (201, None)
+    );
+
+    sqlx::query_scalar("SELECT id FROM users WHERE username = ?")
+        .bind(username!("user1"))
+        .fetch_one(&client.pool().await)
+        .await
+        .expect("unable to get user id")
+}
+
+async fn session_id_hashes(client: &TestClient) -> Vec<Vec<u8>> {
+    sqlx::query_scalar("SELECT session_id_hash FROM sessions")
+        .fetch_all(&client.pool().await)
+        .await
+        .expect("unable to get session id hashes")
+}
+
+#[tokio::test]
+async fn session_ids_are_stored_peppered() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::default().await;
+    signup(&mut client).await;
+
+    let response = client
+        .send(request!(
+            POST "/login";
+            "content-type" => "application/x-www-form-urlencoded";
+            format!("username={}&password={}", username!("user1"), password!("Aa!1aaaa"))
+        ))
+        .await
+        .status(200)
+        .into_response();
+    let cookie = response
+        .headers()
+        .get("set-cookie")
+        .expect("session cookie not set")
+        .to_str()
+        .unwrap()
+        .split(';')
+        .next()
+        .unwrap()
+        .to_string();
+    let session_id =
+        Token::<32>::from_prefixed(cookie.strip_prefix("session_id=").unwrap(), "mona_sess_")
+            .unwrap();
+
+    let pepper = client.secrets().get("pepper").unwrap();
+    assert_eq!(
+        session_id_hashes(&client).await,
+        vec![session_id.hash_hmac_sha256(&pepper)]
+    );
+
+    // sessions started before the pepper was rotated are re-stored under the new one
+    client
+        .secrets()
+        .rotate("pepper", Duration::from_secs(3600))
+        .unwrap();
+    let pepper = client.secrets().get("pepper").unwrap();
+
+    client
+        .send(request!(GET "/private"; "cookie" => &cookie;))
+        .await
+        .status(200);
+    assert_eq!(
+        session_id_hashes(&client).await,
+        vec![session_id.hash_hmac_sha256(&pepper)]
+    );
+
+    client
+        .send(request!(POST "/logout"; "cookie" => &cookie "sec-fetch-site" => "same-origin";))
+        .await
+        .status(200);
+    assert!(session_id_hashes(&client).await.is_empty());
+}
+
+#[tokio::test]
+async fn unpeppered_hashes_are_migrated_on_use() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::default().await;
+    let user_id = signup(&mut client).await;
+    let pool = client.pool().await;
+
+    let created_at = OffsetDateTime::now_utc();
+    let expires_at = created_at + Duration::from_secs(3600);
+
+    let session_id = Token::<32>::random();
+    sqlx::query(
+        "INSERT INTO sessions (session_id_hash, user_id, created_at, expires_at) VALUES (?, ?, ?, ?)",
+    )
+    .bind(session_id.hash_sha256())
+    .bind(user_id)
+    .bind(created_at)
+    .bind(expires_at)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let access_token = Token::<32>::random();
+    sqlx::query(
+        "INSERT INTO access_tokens (name, access_token_hash, user_id, created_at, expires_at) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind("my-token")
+    .bind(access_token.hash_sha256())
+    .bind(user_id)
+    .bind(created_at)
+    .bind(expires_at)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let pepper = client.secrets().get("pepper").unwrap();
+
+    for _ in 0..2 {
+        client
+            .send(request!(
+                GET "/private";
+                "cookie" => format!("session_id={}", session_id.base64encoded());
+            ))
+            .await
+            .status(200);
+        client
+            .send(request!(
+                GET "/access-token/verify";
+                "authorization" => format!("Token {}", access_token.base64encoded());
+            ))
+            .await
+            .status(200);
+    }
+
+    assert_eq!(
+        session_id_hashes(&client).await,
+        vec![session_id.hash_hmac_sha256(&pepper)]
+    );
+    let access_token_hash: Vec<u8> =
+        sqlx::query_scalar("SELECT access_token_hash FROM access_tokens")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(access_token_hash, access_token.hash_hmac_sha256(&pepper));
+}
+
+#[tokio::test]
+async fn missing_pepper_fails_on_startup() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let dir = TestClient::try_with_opts(|opts| {
+        opts.secret_store.kind = auth::SecretStoreKind::Dir;
+        std::fs::remove_file(opts.secrets_dir.join("pepper")).expect("unable to remove pepper");
+    })
+    .await;
+    assert!(matches!(dir, Err(auth::ServerError::Secrets(_))));
+
+    let env = TestClient::try_with_opts(|opts| {
+        opts.secret_store.kind = auth::SecretStoreKind::Env;
+        opts.secret_store.env_prefix = Some("AUTH_TEST_MISSING_PEPPER_".into());
+    })
+    .await;
+    assert!(matches!(env, Err(auth::ServerError::Secrets(_))));
+}
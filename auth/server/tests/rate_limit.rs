@@ -0,0 +1,282 @@
+#![cfg(feature = "rate-limit")]
+
+mod shared;
+
+use base64::{Engine, prelude::BASE64_STANDARD_NO_PAD};
+use serde_json::Value;
+use shared::TestClient;
+use test_proc_macros::{password, username};
+use token::Token;
+
+fn rules(rules: &[&str]) -> Vec<auth::RateLimitRule> {
+    rules
+        .iter()
+        .map(|rule| rule.parse().expect("invalid rate limit rule"))
+        .collect()
+}
+
+#[tokio::test]
+async fn global_limit_headers() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::default().await;
+
+    let response = client
+        .send(shared::from_peer(request!(GET "/heartbeat";;), "192.0.2.1"))
+        .await
+        .status(200)
+        .into_response();
+    assert!(response.headers().contains_key("ratelimit-limit"));
+    assert!(response.headers().contains_key("ratelimit-remaining"));
+    assert!(response.headers().contains_key("ratelimit-reset"));
+    assert!(!response.headers().contains_key("retry-after"));
+}
+
+#[tokio::test]
+async fn unknown_ip_is_not_bucketed() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::with_opts(|opts| {
+        opts.rate_limits = rules(&["/heartbeat 1/min per ip"]);
+    })
+    .await;
+
+    // served without `ConnectInfo`, so the client ip is unknown
+    for _ in 0..3 {
+        let response = client
+            .send(request!(GET "/heartbeat";;))
+            .await
+            .status(200)
+            .into_response();
+        assert!(!response.headers().contains_key("ratelimit-limit"));
+    }
+}
+
+#[tokio::test]
+async fn sqlite_store_is_shared() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut database_url = String::new();
+
+    let mut client1 = TestClient::with_opts(|opts| {
+        opts.rate_limits = rules(&["/heartbeat 2/min per ip"]);
+        opts.rate_limit_store = auth::RateLimitStoreKind::Sqlite;
+        database_url = opts.database.url.clone();
+    })
+    .await;
+
+    let mut client2 = TestClient::with_opts(|opts| {
+        opts.rate_limits = rules(&["/heartbeat 2/min per ip"]);
+        opts.rate_limit_store = auth::RateLimitStoreKind::Sqlite;
+        opts.database.url = database_url;
+    })
+    .await;
+
+    let heartbeat = || shared::from_peer(request!(GET "/heartbeat";;), "192.0.2.1");
+
+    client1.send(heartbeat()).await.status(200);
+    client2.send(heartbeat()).await.status(200);
+    client1.send(heartbeat()).await.status(429);
+    client2.send(heartbeat()).await.status(429);
+}
+
+#[tokio::test]
+async fn per_ip() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::with_opts(|opts| {
+        opts.rate_limits = rules(&["/heartbeat 2/min per ip"]);
+    })
+    .await;
+
+    for remaining in ["1", "0"] {
+        let response = client
+            .send(shared::from_peer(request!(GET "/heartbeat";;), "192.0.2.1"))
+            .await
+            .status(200)
+            .into_response();
+        assert_eq!(response.headers()["ratelimit-limit"], "2");
+        assert_eq!(response.headers()["ratelimit-remaining"], remaining);
+    }
+
+    let response = client
+        .send(shared::from_peer(request!(GET "/heartbeat";;), "192.0.2.1"))
+        .await
+        .status(429)
+        .into_response();
+    let retry_after: u64 = response.headers()["retry-after"]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!((1..=60).contains(&retry_after), "{retry_after}");
+
+    shared::Asserter::from(response)
+        .json_body(|body: Value| assert_eq!(body["kind"], "rate-limit.exceeded"))
+        .await;
+
+    client
+        .send(shared::from_peer(request!(GET "/heartbeat";;), "192.0.2.2"))
+        .await
+        .status(200);
+
+    // other routes are only subject to the global limit
+    client
+        .send(shared::from_peer(request!(GET "/sysinfo";;), "192.0.2.1"))
+        .await
+        .status(401);
+}
+
+#[tokio::test]
+async fn per_username() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let password = password!("Aa!1aaaa");
+
+    let mut client = TestClient::with_opts(|opts| {
+        opts.rate_limits = rules(&["/login 1/min per username"]);
+    })
+    .await;
+
+    let login = |username: &str| {
+        request!(
+            POST "/login";
+            "content-type" => "application/x-www-form-urlencoded";
+            format!("username={}&password={}", username, password)
+        )
+    };
+
+    client.send(login(username!("user1"))).await.status(401);
+    client.send(login(username!("user1"))).await.status(429);
+    client.send(login(username!("user2"))).await.status(401);
+}
+
+#[tokio::test]
+async fn per_token() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let token1 = format!("Token {}A", "B".repeat(42));
+    let token2 = format!("Token {}A", "C".repeat(42));
+
+    let mut client = TestClient::with_opts(|opts| {
+        opts.rate_limits = rules(&["/private 1/min per token"]);
+    })
+    .await;
+
+    client
+        .send(request!(GET "/private"; "authorization" => &token1;))
+        .await
+        .status(401);
+    client
+        .send(request!(GET "/private"; "authorization" => &token1;))
+        .await
+        .status(429);
+    client
+        .send(request!(GET "/private"; "authorization" => &token2;))
+        .await
+        .status(401);
+
+    // the rule doesn't apply to requests without a token
+    client.send(request!(GET "/private";;)).await.status(401);
+    client.send(request!(GET "/private";;)).await.status(401);
+}
+
+#[tokio::test]
+async fn per_token_keys_are_peppered() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let token = Token::<32>::random();
+
+    let mut client = TestClient::with_opts(|opts| {
+        opts.rate_limits = rules(&["/private 1/min per token"]);
+        opts.rate_limit_store = auth::RateLimitStoreKind::Sqlite;
+    })
+    .await;
+
+    client
+        .send(request!(
+            GET "/private";
+            "authorization" => format!("Token {}", token.base64encoded());
+        ))
+        .await
+        .status(401);
+
+    let pepper = client.secrets().get("pepper").unwrap();
+    let keys: Vec<String> = sqlx::query_scalar("SELECT key FROM rate_limits")
+        .fetch_all(&client.pool().await)
+        .await
+        .unwrap();
+
+    // a dump of `rate_limits` can't be checked against tokens captured elsewhere
+    let unpeppered = BASE64_STANDARD_NO_PAD.encode(token.hash_sha256());
+    let peppered = BASE64_STANDARD_NO_PAD.encode(token.hash_hmac_sha256(&pepper));
+    assert!(
+        !keys.iter().any(|key| key.contains(&unpeppered)),
+        "{keys:?}"
+    );
+    assert!(keys.iter().any(|key| key.ends_with(&peppered)), "{keys:?}");
+}
+
+#[tokio::test]
+async fn spoofed_forwarding_headers_are_ignored() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::with_opts(|opts| {
+        opts.rate_limits = rules(&["/heartbeat 1/min per ip"]);
+    })
+    .await;
+
+    let heartbeat = |spoofed: &str| {
+        shared::from_peer(
+            request!(
+                GET "/heartbeat";
+                "forwarded" => format!("for={spoofed}")
+                "x-forwarded-for" => spoofed
+                "x-real-ip" => spoofed;
+            ),
+            "192.0.2.1",
+        )
+    };
+
+    client.send(heartbeat("198.51.100.1")).await.status(200);
+    client.send(heartbeat("198.51.100.2")).await.status(429);
+}
+
+#[tokio::test]
+async fn per_ip_behind_trusted_proxies() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::with_opts(|opts| {
+        opts.rate_limits = rules(&["/heartbeat 1/min per ip"]);
+        opts.client_ip = auth::ClientIpConfig {
+            header: auth::ClientIpHeader::XForwardedFor,
+            trusted_proxies: vec!["10.0.0.0/8".parse().unwrap()],
+        };
+    })
+    .await;
+
+    let heartbeat = |x_forwarded_for: &str| {
+        shared::from_peer(
+            request!(GET "/heartbeat"; "x-forwarded-for" => x_forwarded_for;),
+            "10.0.0.1",
+        )
+    };
+
+    client.send(heartbeat("198.51.100.1")).await.status(200);
+    client.send(heartbeat("198.51.100.2")).await.status(200);
+
+    // whatever the client prepends, the proxy appends the address it connected from
+    client
+        .send(heartbeat("203.0.113.9, 198.51.100.1"))
+        .await
+        .status(429);
+}
@@ -0,0 +1,152 @@
+mod shared;
+
+use std::{os::fd::AsRawFd, path::Path, sync::Arc, time::Duration};
+
+use auth::secrets::{MemoryStore, Secrets};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use serde_json::Value;
+use shared::TestClient;
+use tempfile::tempdir;
+
+async fn keystore_client(keystore: &Path, master_key: &[u8; 32]) -> TestClient {
+    let dir = tempdir().expect("unable to create temp dir");
+    let master_key_path = dir.path().join("master.key");
+    std::fs::write(&master_key_path, BASE64_STANDARD.encode(master_key))
+        .expect("unable to write master key");
+
+    // only needs to stay open while the server reads the key on startup
+    let master_key_file = std::fs::File::open(&master_key_path).expect("unable to open master key");
+
+    TestClient::with_opts(|opts| {
+        opts.secret_store = auth::SecretStoreConfig {
+            kind: auth::SecretStoreKind::Keystore,
+            keystore_path: Some(keystore.to_owned()),
+            master_key_fd: Some(master_key_file.as_raw_fd()),
+            ..Default::default()
+        };
+    })
+    .await
+}
+
+#[tokio::test]
+async fn keystore_encrypts_secrets_at_rest() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let dir = tempdir().expect("unable to create temp dir");
+    let keystore = dir.path().join("keystore");
+
+    let mut client = keystore_client(&keystore, &[1; 32]).await;
+    client
+        .secrets()
+        .reset("hmac")
+        .expect("unable to reset hmac key");
+    let hmac = client
+        .secrets()
+        .get("hmac")
+        .expect("unable to get hmac key");
+
+    let content = std::fs::read(&keystore).expect("unable to read keystore");
+    assert!(
+        !content
+            .windows(hmac.len())
+            .any(|window| window == hmac.as_slice())
+    );
+    assert!(!String::from_utf8_lossy(&content).contains(&BASE64_STANDARD.encode(&hmac)));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = std::fs::metadata(&keystore).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    client
+        .send(request!(GET "/health/ready";;))
+        .await
+        .json_body(|body: Value| {
+            assert_eq!(body["checks"]["hmac"]["status"], "ok", "{body}");
+        })
+        .await;
+
+    // another process with the same master key
+    let client = keystore_client(&keystore, &[1; 32]).await;
+    assert_eq!(client.secrets().get("hmac").unwrap(), hmac);
+}
+
+#[tokio::test]
+async fn keystore_with_wrong_master_key() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let dir = tempdir().expect("unable to create temp dir");
+    let keystore = dir.path().join("keystore");
+
+    let client = keystore_client(&keystore, &[1; 32]).await;
+    client
+        .secrets()
+        .reset("hmac")
+        .expect("unable to reset hmac key");
+
+    let mut client = keystore_client(&keystore, &[2; 32]).await;
+    assert!(matches!(
+        client.secrets().get("hmac"),
+        Err(auth::secrets::SecretStoreError::Decrypt)
+    ));
+
+    client
+        .send(request!(GET "/health/ready";;))
+        .await
+        .status(503)
+        .json_body(|body: Value| {
+            assert_eq!(body["checks"]["hmac"]["status"], "failed", "{body}");
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn secret_names_stay_within_the_store() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let client = TestClient::default().await;
+
+    for name in ["../hmac", "/etc/passwd", "hmac/../../x", ".hidden", ""] {
+        assert!(
+            matches!(
+                client.secrets().reset(name),
+                Err(auth::secrets::SecretStoreError::InvalidName(_))
+            ),
+            "{name}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn cached_versions_are_reloaded_on_rotation_or_once_stale() {
+    let store = Arc::new(MemoryStore::new());
+    let secrets = Secrets::new(store.clone());
+    secrets.reset("pepper").expect("unable to reset pepper");
+
+    // another instance sharing the store
+    let other = Secrets::new(store);
+
+    let cached = secrets.cached("pepper", Duration::from_secs(3600));
+    let stale = secrets.cached("pepper", Duration::ZERO);
+    let kid = cached.get().expect("unable to get pepper")[0].kid.clone();
+    stale.get().expect("unable to get pepper");
+
+    let rotated = other
+        .rotate("pepper", Duration::from_secs(3600))
+        .expect("unable to rotate pepper");
+    assert_eq!(cached.get().unwrap()[0].kid, kid);
+    assert_eq!(stale.get().unwrap()[0].kid, rotated.kid);
+
+    let rotated = secrets
+        .rotate("pepper", Duration::from_secs(3600))
+        .expect("unable to rotate pepper");
+    let versions = cached.get().unwrap();
+    assert_eq!(versions[0].kid, rotated.kid);
+    assert_eq!(versions.len(), 3);
+}
@@ -1,8 +1,5 @@
 use auth::ServerOpts;
-use axum::{
-    Router,
-    body::{Body, to_bytes},
-};
+use axum::body::{Body, to_bytes};
 use http::{Request, Response};
 use sqlx::{Pool, Sqlite, sqlite::SqliteConnectOptions};
 use tempfile::{TempDir, tempdir};
@@ -11,14 +8,27 @@ use tower::Service;
 pub mod macros;
 
 pub struct TestClient {
-    router: Router,
+    server: auth::Server,
 
     // hold TempDir because the temporary directory will be deleted on Drop
     _temp_dir: TempDir,
 }
 
 impl TestClient {
+    #[allow(dead_code)] // not every test binary runs with the default options
     pub async fn default() -> Self {
+        Self::with_opts(|_| {}).await
+    }
+
+    pub async fn with_opts(configure: impl FnOnce(&mut ServerOpts)) -> Self {
+        Self::try_with_opts(configure)
+            .await
+            .expect("unable to create router")
+    }
+
+    pub async fn try_with_opts(
+        configure: impl FnOnce(&mut ServerOpts),
+    ) -> Result<Self, auth::ServerError> {
         let temp_dir = tempdir().expect("unable to create temp dir");
 
         let database_config = auth::DatabaseConfig {
@@ -29,9 +39,7 @@ impl TestClient {
         };
         Self::prepare_database(&database_config).await;
 
-        // let secrets = Secret
-
-        let router = auth::router(ServerOpts {
+        let mut opts = ServerOpts {
             database: database_config,
 
             secrets_dir: {
@@ -40,12 +48,36 @@ impl TestClient {
                 dir
             },
 
+            // with a random `hmac` key and `pepper`, see `TestClient::secrets` to get at them
+            secret_store: auth::SecretStoreConfig {
+                kind: auth::SecretStoreKind::Memory,
+                ..Default::default()
+            },
+
+            csrf: auth::CsrfConfig::default(),
+            cors: auth::CorsConfig::default(),
+            cookie: auth::CookieConfig::default(),
+            shutdown: auth::ShutdownConfig::default(),
+            email: auth::EmailConfig::default(),
+            username: auth::UsernameConfig::default(),
+            password: auth::PasswordConfig::default(),
+            client_ip: auth::ClientIpConfig::default(),
+
+            #[cfg(feature = "otel")]
+            otel: auth::OtelConfig::default(),
+
             #[cfg(feature = "rate-limit")]
             rate_limiter: auth::RateLimiterConfig {
                 limit: usize::MAX,
                 interval: std::time::Duration::from_secs(0),
             },
 
+            #[cfg(feature = "rate-limit")]
+            rate_limits: vec![],
+
+            #[cfg(feature = "rate-limit")]
+            rate_limit_store: auth::RateLimitStoreKind::default(),
+
             #[cfg(feature = "serve-dir")]
             serve_dir: temp_dir.path().to_owned(),
 
@@ -63,18 +95,73 @@ impl TestClient {
                     templates_dir: "../templates".into(),
                 }
             },
+        };
+        configure(&mut opts);
+
+        let server = auth::router(opts).await?;
+
+        Ok(Self {
+            server,
+            _temp_dir: temp_dir,
         })
+    }
+
+    /// Serves on an ephemeral port, for tests that need a real listener.
+    /// Keep the returned `TempDir` alive for as long as the server runs.
+    #[allow(dead_code)] // not every test binary needs a real listener
+    pub async fn serve(self) -> (auth::Serving, TempDir) {
+        let serving = auth::serve(self.server, 0).await.expect("unable to serve");
+        (serving, self._temp_dir)
+    }
+
+    /// Grants `permission` to `username` straight in the database,
+    /// since the seed permissions are not loaded in tests.
+    #[allow(dead_code)] // not every test binary needs permissions
+    pub async fn grant_permission(&self, username: &str, permission: &str) {
+        let pool = self.pool().await;
+
+        sqlx::query("INSERT INTO permissions (permission) VALUES (?) ON CONFLICT DO NOTHING")
+            .bind(permission)
+            .execute(&pool)
+            .await
+            .expect("unable to insert permission");
+
+        sqlx::query(
+            r#"
+            INSERT INTO user_permissions (user_id, permission_id)
+            SELECT users.id, permissions.id FROM users, permissions
+            WHERE users.username = ? AND permissions.permission = ?
+            "#,
+        )
+        .bind(username)
+        .bind(permission)
+        .execute(&pool)
         .await
-        .expect("unable to create router");
+        .expect("unable to grant permission");
+    }
 
-        Self {
-            router,
-            _temp_dir: temp_dir,
-        }
+    /// Another connection to the test database, to look at or tamper with what the server stored.
+    #[allow(dead_code)] // not every test binary looks at the database
+    pub async fn pool(&self) -> Pool<Sqlite> {
+        Pool::<Sqlite>::connect_with(
+            SqliteConnectOptions::new().filename(self._temp_dir.path().join("test.db")),
+        )
+        .await
+        .expect("unable to connect to test db")
+    }
+
+    #[allow(dead_code)] // not every test binary needs the secrets
+    pub fn secrets(&self) -> &auth::secrets::Secrets {
+        &self.server.secrets
+    }
+
+    #[allow(dead_code)] // not every test binary shuts the server down
+    pub fn shutdown(&self) -> &auth::Shutdown {
+        &self.server.shutdown
     }
 
     pub async fn send(&mut self, request: Request<Body>) -> Asserter {
-        let response = self.router
+        let response = self.server.router
             .call(request)
             .await
             .unwrap(/* Infallible */);
@@ -98,6 +185,7 @@ impl TestClient {
     fn prepare_secrets(dir: &std::path::Path) {
         std::fs::create_dir_all(dir).expect("unable to create secrets dir");
         std::fs::write(dir.join("hmac"), vec![0; 1]).expect("unable to create hmac secret");
+        std::fs::write(dir.join("pepper"), vec![1; 32]).expect("unable to create pepper secret");
     }
 
     #[cfg(feature = "smtp")]
@@ -111,6 +199,79 @@ impl TestClient {
     }
 }
 
+/// For `application/x-www-form-urlencoded` bodies and query strings.
+#[allow(dead_code)] // not every test binary sends arbitrary input
+pub fn urlencode(s: &str) -> String {
+    s.bytes()
+        .map(|byte| match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'@' | b'.' | b'-' | b'_' => {
+                (byte as char).to_string()
+            }
+            byte => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+/// The status and, when rejected, the error `kind` of a signup.
+#[allow(dead_code)] // not every test binary looks at why a signup is rejected
+pub async fn signup(
+    client: &mut TestClient,
+    username: &str,
+    email: &str,
+    password: &str,
+) -> (u16, Option<String>) {
+    let response = client
+        .send(crate::request!(
+            POST "/signup";
+            "host" => "localhost"
+            "content-type" => "application/x-www-form-urlencoded";
+            format!(
+                "username={}&email={}&password={}",
+                urlencode(username),
+                urlencode(email),
+                urlencode(password)
+            )
+        ))
+        .await
+        .into_response();
+
+    let status = response.status().as_u16();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let kind = serde_json::from_slice::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|body| body["kind"].as_str().map(String::from));
+    (status, kind)
+}
+
+/// The status of `GET /check/<field>-availability?<field>=<value>`, e.g. for `/check/email-availability`.
+#[allow(dead_code)] // not every test binary checks availability
+pub async fn availability(client: &mut TestClient, path: &str, value: &str) -> u16 {
+    let field = path
+        .strip_prefix("/check/")
+        .and_then(|path| path.strip_suffix("-availability"))
+        .expect("not an availability check");
+
+    client
+        .send(crate::request!(
+            GET format!("{path}?{field}={}", urlencode(value));;
+        ))
+        .await
+        .into_response()
+        .status()
+        .as_u16()
+}
+
+/// As if `request` was served with `ConnectInfo` and came from `peer`,
+/// since [`TestClient::send`] calls the router directly.
+#[allow(dead_code)] // not every test binary looks at the client ip
+pub fn from_peer(mut request: Request<Body>, peer: &str) -> Request<Body> {
+    let peer = std::net::SocketAddr::new(peer.parse().expect("invalid peer ip"), 4711);
+    request
+        .extensions_mut()
+        .insert(axum::extract::ConnectInfo(peer));
+    request
+}
+
 pub struct Asserter {
     response: Response<Body>,
 }
@@ -198,6 +359,7 @@ impl From<Asserter> for Response<Body> {
 static TRACING_INIT: std::sync::Once = std::sync::Once::new();
 
 #[cfg(feature = "tracing")]
+#[allow(dead_code)] // tests that install their own subscriber (e.g. `otel`) don't use it
 pub fn tracing_init() {
     TRACING_INIT.call_once(|| {
         use tracing_subscriber::layer::SubscriberExt;
@@ -0,0 +1,251 @@
+mod shared;
+
+use std::{net::SocketAddr, time::Duration};
+
+use shared::TestClient;
+use test_proc_macros::{email, password, username};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+/// A signup over a real connection, split so that the request can be held in flight
+/// by sending the head (and the first bytes of the body) before the rest of the body.
+fn signup_request() -> (String, String) {
+    let body = format!(
+        "username={}&email={}&password={}",
+        username!("user1"),
+        shared::urlencode(email!("user1@test.com")),
+        shared::urlencode(password!("Aa!1aaaa"))
+    );
+    let (first, rest) = body.split_at(8);
+
+    let head = format!(
+        "POST /signup HTTP/1.1\r\n\
+         host: localhost\r\n\
+         content-type: application/x-www-form-urlencoded\r\n\
+         content-length: {}\r\n\
+         connection: close\r\n\r\n{first}",
+        body.len()
+    );
+    (head, rest.to_string())
+}
+
+async fn read_response(stream: &mut TcpStream) -> String {
+    let mut response = String::new();
+    stream
+        .read_to_string(&mut response)
+        .await
+        .expect("unable to read response");
+    response
+}
+
+#[tokio::test]
+async fn graceful_shutdown() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::default().await;
+    client.send(request!(GET "/heartbeat";;)).await.status(200);
+
+    let (serving, _temp_dir) = client.serve().await;
+    let addr = SocketAddr::from(([127, 0, 0, 1], serving.local_addr.port()));
+
+    let mut stream = TcpStream::connect(addr)
+        .await
+        .expect("unable to connect to server");
+    stream
+        .write_all(b"GET /heartbeat HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n")
+        .await
+        .expect("unable to send request");
+
+    let mut response = String::new();
+    stream
+        .read_to_string(&mut response)
+        .await
+        .expect("unable to read response");
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+
+    serving.shutdown.trigger();
+
+    tokio::time::timeout(Duration::from_secs(5), serving.wait())
+        .await
+        .expect("server did not shut down in time")
+        .expect("server shut down with an error");
+
+    assert!(TcpStream::connect(addr).await.is_err());
+}
+
+#[tokio::test]
+async fn in_flight_requests_are_drained() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let (serving, _temp_dir) = TestClient::default().await.serve().await;
+    let addr = SocketAddr::from(([127, 0, 0, 1], serving.local_addr.port()));
+
+    let (head, rest) = signup_request();
+    let mut stream = TcpStream::connect(addr)
+        .await
+        .expect("unable to connect to server");
+    stream
+        .write_all(head.as_bytes())
+        .await
+        .expect("unable to send request head");
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    serving.shutdown.trigger();
+    let stopped = tokio::spawn(serving.wait());
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!stopped.is_finished(), "stopped with a request in flight");
+    assert!(TcpStream::connect(addr).await.is_err());
+
+    stream
+        .write_all(rest.as_bytes())
+        .await
+        .expect("unable to send request body");
+    let response = read_response(&mut stream).await;
+    assert!(response.starts_with("HTTP/1.1 201"), "{response}");
+
+    tokio::time::timeout(Duration::from_secs(5), stopped)
+        .await
+        .expect("server did not shut down in time")
+        .unwrap()
+        .expect("server shut down with an error");
+}
+
+#[tokio::test]
+async fn in_flight_requests_are_cut_off_at_the_drain_timeout() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let client = TestClient::with_opts(|opts| opts.shutdown.drain_timeout_secs = 1).await;
+    let (serving, _temp_dir) = client.serve().await;
+    let addr = SocketAddr::from(([127, 0, 0, 1], serving.local_addr.port()));
+
+    // the rest of the body never comes
+    let (head, _rest) = signup_request();
+    let mut stream = TcpStream::connect(addr)
+        .await
+        .expect("unable to connect to server");
+    stream
+        .write_all(head.as_bytes())
+        .await
+        .expect("unable to send request head");
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let triggered = tokio::time::Instant::now();
+    serving.shutdown.trigger();
+
+    let result = tokio::time::timeout(Duration::from_secs(5), serving.wait())
+        .await
+        .expect("server did not shut down in time");
+    assert!(
+        matches!(result, Err(auth::ServerError::DrainTimeout { .. })),
+        "{result:?}"
+    );
+    assert!(triggered.elapsed() >= Duration::from_secs(1));
+}
+
+/// Holds the connection of the verification email sent on signup open,
+/// so that the task sending it stays pending until the connection is dropped.
+#[cfg(feature = "smtp")]
+async fn stalled_smtp_signup(
+    drain_timeout_secs: u64,
+) -> (
+    auth::Serving,
+    tempfile::TempDir,
+    TcpStream,
+    tokio::task::JoinHandle<String>,
+) {
+    let smtp = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("unable to bind smtp listener");
+    let smtp_port = smtp.local_addr().unwrap().port();
+
+    let client = TestClient::with_opts(|opts| {
+        opts.smtp.port = Some(smtp_port);
+        opts.smtp.templates_dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../../templates").into();
+        opts.shutdown.drain_timeout_secs = drain_timeout_secs;
+    })
+    .await;
+    let (serving, temp_dir) = client.serve().await;
+    let addr = SocketAddr::from(([127, 0, 0, 1], serving.local_addr.port()));
+
+    let signup = tokio::spawn(async move {
+        let (head, rest) = signup_request();
+        let mut stream = TcpStream::connect(addr)
+            .await
+            .expect("unable to connect to server");
+        stream
+            .write_all(format!("{head}{rest}").as_bytes())
+            .await
+            .expect("unable to send request");
+        read_response(&mut stream).await
+    });
+
+    let (smtp_connection, _) = tokio::time::timeout(Duration::from_secs(5), smtp.accept())
+        .await
+        .expect("verification email was not sent")
+        .expect("unable to accept smtp connection");
+
+    (serving, temp_dir, smtp_connection, signup)
+}
+
+#[cfg(feature = "smtp")]
+#[tokio::test]
+async fn background_tasks_are_drained() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let (serving, _temp_dir, smtp_connection, signup) = stalled_smtp_signup(30).await;
+
+    serving.shutdown.trigger();
+    let stopped = tokio::spawn(serving.wait());
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(
+        !stopped.is_finished(),
+        "stopped with the email task pending"
+    );
+
+    // the email task fails and is done
+    drop(smtp_connection);
+
+    tokio::time::timeout(Duration::from_secs(5), stopped)
+        .await
+        .expect("server did not shut down in time")
+        .unwrap()
+        .expect("server shut down with an error");
+
+    let response = signup.await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 201"), "{response}");
+}
+
+#[cfg(feature = "smtp")]
+#[tokio::test]
+async fn background_tasks_are_cut_off_at_the_drain_timeout() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let (serving, _temp_dir, _smtp_connection, _signup) = stalled_smtp_signup(1).await;
+
+    let triggered = tokio::time::Instant::now();
+    serving.shutdown.trigger();
+
+    let result = tokio::time::timeout(Duration::from_secs(5), serving.wait())
+        .await
+        .expect("server did not shut down in time");
+    assert!(
+        matches!(
+            result,
+            Err(auth::ServerError::DrainTimeout {
+                pending_tasks: 1,
+                ..
+            })
+        ),
+        "{result:?}"
+    );
+    assert!(triggered.elapsed() >= Duration::from_secs(1));
+}
@@ -0,0 +1,227 @@
+mod shared;
+
+use std::time::Duration;
+
+use shared::TestClient;
+use test_proc_macros::password;
+
+async fn login(client: &mut TestClient, username: &str) -> u16 {
+    client
+        .send(request!(
+            POST "/login";
+            "content-type" => "application/x-www-form-urlencoded";
+            format!("username={}&password={}", shared::urlencode(username), password!("Aa!1aaaa"))
+        ))
+        .await
+        .into_response()
+        .status()
+        .as_u16()
+}
+
+#[tokio::test]
+async fn ascii_usernames_by_default() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::default().await;
+
+    assert_eq!(
+        shared::signup(
+            &mut client,
+            "Admin",
+            "admin@test.com",
+            password!("Aa!1aaaa")
+        )
+        .await,
+        (400, Some("username.reserved".into()))
+    );
+    assert_eq!(
+        shared::signup(&mut client, "josé", "jose@test.com", password!("Aa!1aaaa")).await,
+        (400, Some("username.invalid".into()))
+    );
+
+    assert_eq!(
+        shared::signup(&mut client, "Joe", "joe1@test.com", password!("Aa!1aaaa")).await,
+        (201, None)
+    );
+    // compared as is
+    assert_eq!(
+        shared::availability(&mut client, "/check/username-availability", "joe").await,
+        200
+    );
+    assert_eq!(
+        shared::signup(&mut client, "joe", "joe2@test.com", password!("Aa!1aaaa")).await,
+        (201, None)
+    );
+}
+
+#[tokio::test]
+async fn unicode_usernames() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::with_opts(|opts| opts.username.allow_unicode = true).await;
+
+    // decomposed, stored composed
+    assert_eq!(
+        shared::signup(
+            &mut client,
+            "Jose\u{0301}",
+            "jose@test.com",
+            password!("Aa!1aaaa")
+        )
+        .await,
+        (201, None)
+    );
+    assert_eq!(login(&mut client, "José").await, 200);
+    assert_eq!(login(&mut client, "Ｊｏｓé").await, 200);
+
+    assert_eq!(
+        shared::availability(&mut client, "/check/username-availability", "josé").await,
+        409
+    );
+    assert_eq!(
+        shared::signup(&mut client, "JOSÉ", "jose2@test.com", password!("Aa!1aaaa")).await,
+        (409, Some("username.exists".into()))
+    );
+
+    // р and а are Cyrillic
+    assert_eq!(
+        shared::signup(
+            &mut client,
+            "раypal",
+            "paypal@test.com",
+            password!("Aa!1aaaa")
+        )
+        .await,
+        (400, Some("username.invalid".into()))
+    );
+
+    assert_eq!(
+        shared::signup(
+            &mut client,
+            "scope",
+            "scope@test.com",
+            password!("Aa!1aaaa")
+        )
+        .await,
+        (201, None)
+    );
+    // all Cyrillic
+    assert_eq!(
+        shared::availability(&mut client, "/check/username-availability", "ѕсоре").await,
+        409
+    );
+
+    assert_eq!(
+        shared::signup(
+            &mut client,
+            "山田",
+            "yamada@test.com",
+            password!("Aa!1aaaa")
+        )
+        .await,
+        (201, None)
+    );
+}
+
+#[tokio::test]
+async fn existing_usernames_are_canonicalized_on_startup() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::default().await;
+    assert_eq!(
+        shared::signup(&mut client, "Joe", "joe@test.com", password!("Aa!1aaaa")).await,
+        (201, None)
+    );
+
+    let pool = client.pool().await;
+    let canonical = || async {
+        sqlx::query_as::<_, (Option<String>, Option<String>)>(
+            "SELECT username_canonical, username_canonical_kind FROM users",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap()
+    };
+    assert_eq!(
+        canonical().await,
+        (Some("Joe".into()), Some("as-is".into()))
+    );
+
+    let database_url = pool
+        .connect_options()
+        .get_filename()
+        .to_string_lossy()
+        .to_string();
+
+    // turning on unicode usernames, without touching the database
+    let mut restarted = TestClient::with_opts(|opts| {
+        opts.database.url = database_url.clone();
+        opts.username.allow_unicode = true;
+    })
+    .await;
+
+    // compared as is until recomputed
+    assert_eq!(
+        shared::signup(
+            &mut restarted,
+            "Joe",
+            "joe2@test.com",
+            password!("Aa!1aaaa")
+        )
+        .await,
+        (409, Some("username.exists".into()))
+    );
+
+    for _ in 0..100 {
+        if canonical().await.1.as_deref() == Some("skeleton") {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(
+        canonical().await,
+        (Some("joe".into()), Some("skeleton".into()))
+    );
+
+    assert_eq!(
+        shared::availability(&mut restarted, "/check/username-availability", "jOE").await,
+        409
+    );
+    assert_eq!(
+        shared::signup(
+            &mut restarted,
+            "joe",
+            "joe3@test.com",
+            password!("Aa!1aaaa")
+        )
+        .await,
+        (409, Some("username.exists".into()))
+    );
+
+    // and back
+    let mut restarted = TestClient::with_opts(|opts| {
+        opts.database.url = database_url;
+    })
+    .await;
+    for _ in 0..100 {
+        if canonical().await.1.as_deref() == Some("as-is") {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(
+        canonical().await,
+        (Some("Joe".into()), Some("as-is".into()))
+    );
+    assert_eq!(
+        shared::availability(&mut restarted, "/check/username-availability", "Joe").await,
+        409
+    );
+    assert_eq!(
+        shared::availability(&mut restarted, "/check/username-availability", "joe").await,
+        200
+    );
+}
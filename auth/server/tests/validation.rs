@@ -0,0 +1,123 @@
+mod shared;
+
+use shared::TestClient;
+
+async fn signup(client: &mut TestClient, body: serde_json::Value) -> (u16, serde_json::Value) {
+    let response = client
+        .send(request!(
+            POST "/signup";
+            "host" => "localhost"
+            "content-type" => "application/json";
+            body.to_string()
+        ))
+        .await
+        .into_response();
+
+    let status = response.status().as_u16();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (
+        status,
+        serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null),
+    )
+}
+
+fn fields(body: &serde_json::Value) -> Vec<(String, String)> {
+    body["fields"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|field| {
+            (
+                field["field"].as_str().unwrap().to_string(),
+                field["kind"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn every_invalid_field_is_listed() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::default().await;
+
+    let (status, body) = signup(
+        &mut client,
+        serde_json::json!({ "username": "j", "email": "joe", "password": "password" }),
+    )
+    .await;
+
+    assert_eq!(status, 400);
+    assert_eq!(body["kind"], "username.invalid");
+    assert_eq!(
+        fields(&body),
+        vec![
+            ("username".into(), "username.invalid".into()),
+            ("email".into(), "email.invalid".into()),
+            ("password".into(), "password.weak".into()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn fields_are_checked_against_the_configured_policies() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::with_opts(|opts| {
+        opts.password.min_score = 0;
+    })
+    .await;
+
+    let (status, body) = signup(
+        &mut client,
+        serde_json::json!({ "username": "admin", "email": "joe@smith.com", "password": "password" }),
+    )
+    .await;
+
+    assert_eq!(status, 400);
+    assert_eq!(
+        fields(&body),
+        vec![("username".into(), "username.reserved".into())]
+    );
+}
+
+#[tokio::test]
+async fn malformed_payloads_are_rejected_before_validation() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::default().await;
+
+    let (status, body) = signup(&mut client, serde_json::json!({ "username": "joe" })).await;
+
+    assert_eq!(status, 422);
+    assert_eq!(body["kind"], "payload.json.data");
+    assert!(body.get("fields").is_none());
+}
+
+#[tokio::test]
+async fn email_policy_violations_are_listed_with_the_other_fields() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::default().await;
+
+    let (status, body) = signup(
+        &mut client,
+        serde_json::json!({ "username": "j", "email": "joe@mailinator.com", "password": "h?P7o]37" }),
+    )
+    .await;
+
+    assert_eq!(status, 400);
+    assert_eq!(
+        fields(&body),
+        vec![
+            ("username".into(), "username.invalid".into()),
+            ("email".into(), "email.policy.disposable".into()),
+        ]
+    );
+}
@@ -0,0 +1,194 @@
+#![cfg(feature = "smtp")]
+
+mod shared;
+
+use std::time::Duration;
+
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use email::Email;
+use serde_json::Value;
+use shared::TestClient;
+use signature::{Encrypted, EncryptionKey, Signed};
+use test_proc_macros::{email, password, username};
+use time::OffsetDateTime;
+
+fn verify(token: &str) -> http::Request<axum::body::Body> {
+    request!(GET format!("/verify-email?token={token}");;)
+}
+
+#[tokio::test]
+async fn claims_are_validated() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::default().await;
+    client
+        .send(request!(
+            POST "/signup";
+            "host" => "localhost"
+            "content-type" => "application/x-www-form-urlencoded";
+            format!("username={}&email={}&password={}", username!("user1"), email!("user1@test.com"), password!("Aa!1aaaa"))
+        ))
+        .await
+        .status(201);
+
+    let key = client.secrets().current("hmac").unwrap();
+    let sign = |token: Signed<Email>| token.with_kid(key.kid.clone()).encode(&key.secret).unwrap();
+    let token = || Signed::new(email!("user1@test.com").parse::<Email>().unwrap());
+
+    // e.g. a token issued for something else, signed with the same key
+    for token in [token(), token().with_purpose("reset-password")] {
+        client
+            .send(verify(&sign(token)))
+            .await
+            .status(400)
+            .json_body(|body: Value| assert_eq!(body["kind"], "token.invalid", "{body}"))
+            .await;
+    }
+
+    let not_yet_valid = token()
+        .with_purpose("verify-email")
+        .with_nbf(OffsetDateTime::now_utc() + Duration::from_secs(600));
+    client
+        .send(verify(&sign(not_yet_valid)))
+        .await
+        .status(400)
+        .json_body(|body: Value| assert_eq!(body["kind"], "token.temporal.invalid", "{body}"))
+        .await;
+
+    // within the tolerated clock skew
+    let skewed = token()
+        .with_purpose("verify-email")
+        .with_nbf(OffsetDateTime::now_utc() + Duration::from_secs(5));
+    client.send(verify(&sign(skewed))).await.status(200);
+}
+
+#[tokio::test]
+async fn tokens_are_encrypted() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::default().await;
+    client
+        .send(request!(
+            POST "/signup";
+            "host" => "localhost"
+            "content-type" => "application/x-www-form-urlencoded";
+            format!("username={}&email={}&password={}", username!("user1"), email!("user1@test.com"), password!("Aa!1aaaa"))
+        ))
+        .await
+        .status(201);
+
+    let key = client.secrets().current("hmac").unwrap();
+    let email = email!("user1@test.com").parse::<Email>().unwrap();
+
+    let encrypted = Encrypted::new(email.clone())
+        .with_purpose("verify-email")
+        .with_kid(key.kid.clone())
+        .encrypt(&EncryptionKey::derive(&key.secret))
+        .unwrap();
+    assert!(!encrypted.contains(&BASE64_URL_SAFE_NO_PAD.encode(AsRef::<[u8]>::as_ref(&email))));
+    client.send(verify(&encrypted)).await.status(200);
+
+    // the purpose is validated as for signed tokens
+    let encrypted = Encrypted::new(email)
+        .with_kid(key.kid.clone())
+        .encrypt(&EncryptionKey::derive(&key.secret))
+        .unwrap();
+    client
+        .send(verify(&encrypted))
+        .await
+        .status(400)
+        .json_body(|body: Value| assert_eq!(body["kind"], "token.invalid", "{body}"))
+        .await;
+}
+
+#[tokio::test]
+async fn tokens_are_single_use() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::default().await;
+    client
+        .send(request!(
+            POST "/signup";
+            "host" => "localhost"
+            "content-type" => "application/x-www-form-urlencoded";
+            format!("username={}&email={}&password={}", username!("user1"), email!("user1@test.com"), password!("Aa!1aaaa"))
+        ))
+        .await
+        .status(201);
+
+    let key = client.secrets().current("hmac").unwrap();
+    let encrypt = |jti: &str| {
+        Encrypted::new(email!("user1@test.com").parse::<Email>().unwrap())
+            .with_purpose("verify-email")
+            .with_jti(jti)
+            .encrypt(&EncryptionKey::derive(&key.secret))
+            .unwrap()
+    };
+
+    let token = encrypt("some-jti");
+    client.send(verify(&token)).await.status(200);
+    client
+        .send(verify(&token))
+        .await
+        .status(409)
+        .json_body(|body: Value| assert_eq!(body["kind"], "token.reused", "{body}"))
+        .await;
+
+    // another token for the same email is another use
+    client
+        .send(verify(&encrypt("another-jti")))
+        .await
+        .status(200);
+}
+
+#[tokio::test]
+async fn tokens_are_only_used_up_once_the_email_is_verified() {
+    #[cfg(feature = "tracing")]
+    shared::tracing_init();
+
+    let mut client = TestClient::default().await;
+    client
+        .send(request!(
+            POST "/signup";
+            "host" => "localhost"
+            "content-type" => "application/x-www-form-urlencoded";
+            format!("username={}&email={}&password={}", username!("user1"), email!("user1@test.com"), password!("Aa!1aaaa"))
+        ))
+        .await
+        .status(201);
+
+    let key = client.secrets().current("hmac").unwrap();
+    let token = Encrypted::new(email!("user1@test.com").parse::<Email>().unwrap())
+        .with_purpose("verify-email")
+        .with_jti("some-jti")
+        .encrypt(&EncryptionKey::derive(&key.secret))
+        .unwrap();
+
+    let pool = client.pool().await;
+    sqlx::query(
+        "CREATE TRIGGER fail_verification BEFORE UPDATE OF email_verified ON users
+         BEGIN SELECT RAISE(ABORT, 'unavailable'); END",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    client.send(verify(&token)).await.status(500);
+
+    sqlx::query("DROP TRIGGER fail_verification")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // the failed attempt didn't use the link up
+    client.send(verify(&token)).await.status(200);
+
+    let email_verified: bool = sqlx::query_scalar("SELECT email_verified FROM users")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert!(email_verified);
+}
@@ -8,7 +8,15 @@ mod latency;
 #[cfg(feature = "latency")]
 pub use latency::latency_ms;
 
+#[cfg(feature = "security-headers")]
+mod security_headers;
+#[cfg(feature = "security-headers")]
+pub use security_headers::security_headers;
+
 #[cfg(feature = "rate-limit")]
 mod rate_limit;
 #[cfg(feature = "rate-limit")]
-pub use rate_limit::{RateLimiter, rate_limiter};
+pub use rate_limit::{
+    BoxFuture, Decision, MemoryStore, Quota, RATELIMIT_LIMIT, RATELIMIT_REMAINING, RATELIMIT_RESET,
+    RateLimitStore, RateLimitStoreError, RateLimiter, gcra, rate_limiter, unix_nanos,
+};
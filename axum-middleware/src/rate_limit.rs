@@ -1,81 +1,269 @@
 use std::{
-    collections::VecDeque,
+    future::Future,
     net::IpAddr,
+    pin::Pin,
     sync::Arc,
-    time::{Duration, Instant},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use axum::{
     body::Body,
     extract::State,
-    http::{Request, Response, StatusCode},
+    http::{
+        HeaderMap, HeaderName, HeaderValue, Request, Response, StatusCode, header::RETRY_AFTER,
+    },
     middleware::Next,
     response::IntoResponse,
 };
-use client_ip::client_ip;
+use client_ip::ClientIpConfig;
 use dashmap::DashMap;
 
+pub const RATELIMIT_LIMIT: HeaderName = HeaderName::from_static("ratelimit-limit");
+pub const RATELIMIT_REMAINING: HeaderName = HeaderName::from_static("ratelimit-remaining");
+pub const RATELIMIT_RESET: HeaderName = HeaderName::from_static("ratelimit-reset");
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+pub type RateLimitStoreError = Box<dyn std::error::Error + Send + Sync>;
+
+/// `limit` requests per `interval`, all of which may be spent in a single burst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    pub limit: usize,
+    pub interval: Duration,
+}
+
+/// The outcome of [`RateLimitStore::check`], along with what the client needs to back off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decision {
+    pub allowed: bool,
+    pub limit: usize,
+    pub remaining: usize,
+
+    /// Until a slot frees up. Zero while `remaining` is non-zero.
+    pub reset: Duration,
+}
+
+/// Where the per-key state of the [GCRA](https://en.wikipedia.org/wiki/Generic_cell_rate_algorithm) lives.
+/// That state is a single timestamp per key (see [`gcra`]),
+/// so a store shared between processes makes them share their limits.
+pub trait RateLimitStore: Send + Sync {
+    /// Records the request against `key` unless that would exceed the `quota`.
+    fn check(
+        &self,
+        key: String,
+        quota: Quota,
+    ) -> BoxFuture<'_, Result<Decision, RateLimitStoreError>>;
+
+    /// Forgets the keys that have been idle long enough for their bucket to be full again,
+    /// which is indistinguishable from never having seen them. Returns how many were removed.
+    fn evict(&self) -> BoxFuture<'_, Result<u64, RateLimitStoreError>>;
+}
+
+/// GCRA: `tat` (theoretical arrival time) is when the bucket would be full again.
+/// Each request pushes it `interval / limit` into the future
+/// and is allowed as long as that doesn't push it past `now + interval`.
+///
+/// Times are nanoseconds since the unix epoch. Returns the decision
+/// and, when the request is allowed, the `tat` to store for the key.
+pub fn gcra(tat: Option<u64>, now: u64, quota: Quota) -> (Decision, Option<u64>) {
+    if quota.limit == 0 {
+        let decision = Decision {
+            allowed: false,
+            limit: 0,
+            remaining: 0,
+            reset: quota.interval,
+        };
+        return (decision, None);
+    }
+
+    let interval = quota.interval.as_nanos().min(u64::MAX as u128) as u64;
+    let emission_interval = interval / quota.limit as u64;
+
+    // e.g. `usize::MAX` per second, which is as good as no limit
+    if emission_interval == 0 {
+        let decision = Decision {
+            allowed: true,
+            limit: quota.limit,
+            remaining: quota.limit,
+            reset: Duration::ZERO,
+        };
+        return (decision, None);
+    }
+
+    let tat = tat.unwrap_or(now).max(now);
+    let new_tat = tat.saturating_add(emission_interval);
+    let allow_at = new_tat.saturating_sub(interval);
+
+    if now < allow_at {
+        let decision = Decision {
+            allowed: false,
+            limit: quota.limit,
+            remaining: 0,
+            reset: Duration::from_nanos(allow_at - now),
+        };
+        return (decision, None);
+    }
+
+    let next_allow_at = new_tat
+        .saturating_add(emission_interval)
+        .saturating_sub(interval);
+
+    let decision = Decision {
+        allowed: true,
+        limit: quota.limit,
+        remaining: ((interval - (new_tat - now)) / emission_interval) as usize,
+        reset: Duration::from_nanos(next_allow_at.saturating_sub(now)),
+    };
+    (decision, Some(new_tat))
+}
+
+pub fn unix_nanos(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .min(u64::MAX as u128) as u64
+}
+
+/// Keeps the state in this process. Limits are per process.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    tats: DashMap<String, u64>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl RateLimitStore for MemoryStore {
+    fn check(
+        &self,
+        key: String,
+        quota: Quota,
+    ) -> BoxFuture<'_, Result<Decision, RateLimitStoreError>> {
+        let now = unix_nanos(SystemTime::now());
+        let mut tat = self.tats.entry(key).or_insert(now);
+
+        let (decision, new_tat) = gcra(Some(*tat), now, quota);
+        if let Some(new_tat) = new_tat {
+            *tat = new_tat;
+        }
+
+        Box::pin(std::future::ready(Ok(decision)))
+    }
+
+    fn evict(&self) -> BoxFuture<'_, Result<u64, RateLimitStoreError>> {
+        let now = unix_nanos(SystemTime::now());
+        let before = self.tats.len();
+        self.tats.retain(|_, tat| *tat > now);
+        Box::pin(std::future::ready(Ok((before - self.tats.len()) as u64)))
+    }
+}
+
+impl Decision {
+    /// `RateLimit-Limit`, `RateLimit-Remaining`, `RateLimit-Reset` and,
+    /// when the request is rejected, `Retry-After`. Durations are in whole seconds, rounded up.
+    pub fn headers(&self) -> HeaderMap {
+        let reset = HeaderValue::from(self.reset.as_secs_f64().ceil() as u64);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(RATELIMIT_LIMIT, HeaderValue::from(self.limit));
+        headers.insert(RATELIMIT_REMAINING, HeaderValue::from(self.remaining));
+        headers.insert(RATELIMIT_RESET, reset.clone());
+
+        if !self.allowed {
+            headers.insert(RETRY_AFTER, reset);
+        }
+
+        headers
+    }
+}
+
+/// A single [`Quota`] per client IP.
 pub struct RateLimiter {
-    requests: DashMap<IpAddr, VecDeque<Instant>>,
-    limit: usize,
-    interval: Duration,
+    store: Arc<dyn RateLimitStore>,
+    quota: Quota,
+    client_ip: ClientIpConfig,
+    on_reject: Option<Box<dyn Fn() + Send + Sync>>,
 }
 
 impl RateLimiter {
     pub fn new(limit: usize, interval: Duration) -> Self {
         Self {
-            requests: DashMap::default(),
-            limit,
-            interval,
+            store: Arc::new(MemoryStore::new()),
+            quota: Quota { limit, interval },
+            client_ip: ClientIpConfig::default(),
+            on_reject: None,
         }
     }
 
+    pub fn with_store(mut self, store: Arc<dyn RateLimitStore>) -> Self {
+        self.store = store;
+        self
+    }
+
+    /// How the client IP is found behind proxies. The connected peer by default.
+    pub fn with_client_ip(mut self, client_ip: ClientIpConfig) -> Self {
+        self.client_ip = client_ip;
+        self
+    }
+
+    /// Called every time a request is rejected, e.g. to count rejections in metrics.
+    pub fn on_reject(mut self, hook: impl Fn() + Send + Sync + 'static) -> Self {
+        self.on_reject = Some(Box::new(hook));
+        self
+    }
+
     #[allow(dead_code)]
     pub fn nolimit() -> Self {
-        Self {
-            requests: DashMap::default(),
-            limit: usize::MAX,
-            interval: Duration::from_secs(0),
-        }
+        Self::new(usize::MAX, Duration::from_secs(0))
     }
 
-    pub fn is_too_many(&self, ip_addr: IpAddr) -> bool {
-        let now = Instant::now();
-        let mut request_timeline = self.requests.entry(ip_addr).or_default();
-
-        // clean up old entries
-        while let Some(time) = request_timeline.front() {
-            if now.duration_since(*time) > self.interval {
-                request_timeline.pop_front();
-            } else {
-                break;
-            }
+    pub async fn is_too_many(&self, ip_addr: IpAddr) -> bool {
+        match self.store.check(ip_addr.to_string(), self.quota).await {
+            Ok(decision) => !decision.allowed,
+            Err(_) => false,
         }
-
-        if request_timeline.len() >= self.limit {
-            return true;
-        }
-
-        request_timeline.push_back(now);
-        false
     }
 }
 
+/// Requests whose client IP is unknown are let through rather than sharing a single bucket.
+/// Serve with `into_make_service_with_connect_info::<SocketAddr>` so that it is always known.
 pub async fn rate_limiter(
     State(rate_limiter): State<Arc<RateLimiter>>,
     request: Request<Body>,
     next: Next,
 ) -> Response<Body> {
-    let client_ip = client_ip(&request).unwrap_or_else(|| {
+    let Some(client_ip) = rate_limiter.client_ip.client_ip(&request) else {
         tracing::warn!("unable to get client_ip while rate limiting");
-        IpAddr::from([0, 0, 0, 0])
-    });
+        return next.run(request).await;
+    };
 
-    if rate_limiter.is_too_many(client_ip) {
+    let decision = match rate_limiter
+        .store
+        .check(client_ip.to_string(), rate_limiter.quota)
+        .await
+    {
+        Ok(decision) => decision,
+        Err(err) => {
+            // fail open, an unavailable store shouldn't take the whole service down with it
+            tracing::error!("rate limit store :: {:?}", err);
+            return next.run(request).await;
+        }
+    };
+
+    if !decision.allowed {
         tracing::warn!("rate limited {}", client_ip);
 
-        return StatusCode::TOO_MANY_REQUESTS.into_response();
+        if let Some(on_reject) = &rate_limiter.on_reject {
+            on_reject();
+        }
+
+        return (StatusCode::TOO_MANY_REQUESTS, decision.headers()).into_response();
     }
 
-    next.run(request).await
+    let mut response = next.run(request).await;
+    response.headers_mut().extend(decision.headers());
+    response
 }
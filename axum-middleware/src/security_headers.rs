@@ -0,0 +1,30 @@
+use axum::{
+    body::Body,
+    http::{
+        HeaderValue, Request, Response,
+        header::{REFERRER_POLICY, STRICT_TRANSPORT_SECURITY, X_CONTENT_TYPE_OPTIONS},
+    },
+    middleware::Next,
+};
+
+/// sets response headers that are safe defaults for every response.
+/// headers already set by the handler are left untouched.
+pub async fn security_headers(request: Request<Body>, next: Next) -> Response<Body> {
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+
+    // browsers ignore HSTS received over plain http, so it is harmless during local development
+    headers
+        .entry(STRICT_TRANSPORT_SECURITY)
+        .or_insert(HeaderValue::from_static(
+            "max-age=63072000; includeSubDomains",
+        ));
+    headers
+        .entry(X_CONTENT_TYPE_OPTIONS)
+        .or_insert(HeaderValue::from_static("nosniff"));
+    headers
+        .entry(REFERRER_POLICY)
+        .or_insert(HeaderValue::from_static("no-referrer"));
+
+    response
+}
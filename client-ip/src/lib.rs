@@ -1,31 +1,151 @@
 use std::{net::IpAddr, str::FromStr};
 
-use forwarded_header_value::{ForwardedHeaderValue, Identifier};
-use http::{Request, header::FORWARDED};
-
-pub fn client_ip<B>(request: &Request<B>) -> Option<IpAddr> {
-    let client_ip = request
-        .headers()
-        .get(FORWARDED)
-        .and_then(|val| val.to_str().ok())
-        .and_then(|val| ForwardedHeaderValue::from_str(val).ok())
-        .map(|forwarded| forwarded.into_remotest())
-        .and_then(|stanza| stanza.forwarded_for)
-        .and_then(|identifier| match identifier {
-            Identifier::SocketAddr(socket_addr) => Some(socket_addr.ip()),
-            Identifier::IpAddr(ip_addr) => Some(ip_addr),
-            _ => None,
-        });
-
-    #[cfg(feature = "axum")]
-    let client_ip = client_ip.or_else(|| {
-        use axum::extract::ConnectInfo;
-        use std::net::SocketAddr;
-        request
+use forwarded_header_value::{ForwardedStanza, Identifier};
+use http::{HeaderMap, Request, header::FORWARDED};
+use ipnet::IpNet;
+
+/// Which header the client IP is taken from, and which peers are believed when they set it.
+///
+/// The header is read from the nearest hop (the connected peer) outwards, and only as long as
+/// the hop that wrote each entry is one of the `trusted_proxies`. The first address that isn't
+/// trusted is the client, so a client can prepend whatever it likes to the header
+/// without it being looked at. Without any `trusted_proxies`, the client is the connected peer.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(default, deny_unknown_fields)
+)]
+pub struct ClientIpConfig {
+    pub header: ClientIpHeader,
+
+    /// e.g. `10.0.0.0/8` for a load balancer in the private network, or `127.0.0.1/32` for a local reverse proxy.
+    pub trusted_proxies: Vec<IpNet>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum ClientIpHeader {
+    /// `Forwarded: for=192.0.2.1, for=198.51.100.7` (RFC 7239)
+    Forwarded,
+
+    /// `X-Forwarded-For: 192.0.2.1, 198.51.100.7`
+    XForwardedFor,
+
+    /// `X-Real-IP: 192.0.2.1`, which holds a single address.
+    XRealIp,
+
+    /// The connected peer, whatever the headers say.
+    #[default]
+    None,
+}
+
+/// The client IP of a request, put in its extensions by [`resolve`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+impl ClientIpConfig {
+    /// `None` when the connected peer is unknown, i.e. when not served with `ConnectInfo<SocketAddr>`.
+    pub fn client_ip<B>(&self, request: &Request<B>) -> Option<IpAddr> {
+        #[cfg(feature = "axum")]
+        let peer = request
             .extensions()
-            .get::<ConnectInfo<SocketAddr>>()
-            .map(|connect_info| connect_info.0.ip())
-    });
+            .get::<axum::extract::ConnectInfo<std::net::SocketAddr>>()
+            .map(|connect_info| connect_info.0.ip());
+
+        #[cfg(not(feature = "axum"))]
+        let peer = None;
+
+        peer.map(|peer| self.client_ip_from(peer, request.headers()))
+    }
+
+    /// Walks the header from `peer` outwards and stops at the first address that is not a trusted proxy.
+    /// An entry that isn't an address (e.g. `unknown`) ends the walk at the proxy that wrote it.
+    pub fn client_ip_from(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        let mut client = peer.to_canonical();
+
+        // nearest first
+        let hops: Vec<Option<IpAddr>> = match self.header {
+            ClientIpHeader::None => return client,
+            ClientIpHeader::Forwarded => entries(headers, FORWARDED.as_str())
+                .map(|entry| {
+                    ForwardedStanza::from_str(entry)
+                        .ok()
+                        .and_then(|stanza| stanza.forwarded_for_ip())
+                })
+                .collect(),
+            ClientIpHeader::XForwardedFor => entries(headers, "x-forwarded-for").map(ip).collect(),
+            ClientIpHeader::XRealIp => headers
+                .get_all("x-real-ip")
+                .iter()
+                .next_back()
+                .map(|value| value.to_str().ok().and_then(ip))
+                .into_iter()
+                .collect(),
+        };
+
+        for hop in hops.into_iter().rev() {
+            if !self.is_trusted(client) {
+                break;
+            }
+            match hop {
+                Some(ip) => client = ip.to_canonical(),
+                None => break,
+            }
+        }
+
+        client
+    }
+
+    pub fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|net| net.contains(&ip))
+    }
+}
+
+/// Comma separated entries of every `name` header, in the order they were added.
+fn entries<'a>(headers: &'a HeaderMap, name: &str) -> impl Iterator<Item = &'a str> {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+}
+
+/// `192.0.2.1`, `192.0.2.1:4711`, `[2001:db8::1]` or `[2001:db8::1]:4711`.
+fn ip(entry: &str) -> Option<IpAddr> {
+    Identifier::from_str(entry)
+        .ok()
+        .and_then(|identifier| identifier.ip())
+}
+
+/// Middleware that resolves the client IP once, for everything after it to read as [`ClientIp`],
+/// e.g. `from_fn_with_state(Arc::new(config), client_ip::resolve)`.
+#[cfg(feature = "axum")]
+pub async fn resolve(
+    axum::extract::State(config): axum::extract::State<std::sync::Arc<ClientIpConfig>>,
+    mut request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    if let Some(ip) = config.client_ip(&request) {
+        request.extensions_mut().insert(ClientIp(ip));
+    }
+    next.run(request).await
+}
+
+#[cfg(feature = "axum")]
+impl<S: Send + Sync> axum::extract::OptionalFromRequestParts<S> for ClientIp {
+    type Rejection = std::convert::Infallible;
 
-    client_ip
+    async fn from_request_parts(
+        parts: &mut http::request::Parts,
+        _state: &S,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        Ok(parts.extensions.get::<ClientIp>().copied())
+    }
 }
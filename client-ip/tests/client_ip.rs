@@ -0,0 +1,118 @@
+use std::net::IpAddr;
+
+use client_ip::{ClientIpConfig, ClientIpHeader};
+use http::HeaderMap;
+
+fn config(header: ClientIpHeader, trusted_proxies: &[&str]) -> ClientIpConfig {
+    ClientIpConfig {
+        header,
+        trusted_proxies: trusted_proxies
+            .iter()
+            .map(|net| net.parse().unwrap())
+            .collect(),
+    }
+}
+
+fn headers(headers: &[(&'static str, &str)]) -> HeaderMap {
+    headers
+        .iter()
+        .map(|(name, value)| (http::HeaderName::from_static(name), value.parse().unwrap()))
+        .collect()
+}
+
+fn ip(ip: &str) -> IpAddr {
+    ip.parse().unwrap()
+}
+
+#[test]
+fn headers_are_ignored_by_default() {
+    let config = ClientIpConfig::default();
+    let headers = headers(&[
+        ("forwarded", "for=192.0.2.1"),
+        ("x-forwarded-for", "192.0.2.1"),
+        ("x-real-ip", "192.0.2.1"),
+    ]);
+
+    assert_eq!(
+        config.client_ip_from(ip("203.0.113.9"), &headers),
+        ip("203.0.113.9")
+    );
+}
+
+#[test]
+fn headers_from_untrusted_peers_are_ignored() {
+    let config = config(ClientIpHeader::XForwardedFor, &["10.0.0.0/8"]);
+    let headers = headers(&[("x-forwarded-for", "192.0.2.1")]);
+
+    assert_eq!(
+        config.client_ip_from(ip("203.0.113.9"), &headers),
+        ip("203.0.113.9")
+    );
+}
+
+#[test]
+fn the_walk_stops_at_the_first_untrusted_hop() {
+    let config = config(ClientIpHeader::XForwardedFor, &["10.0.0.0/8"]);
+
+    // `192.0.2.66` was made up by the client, `198.51.100.7` connected to the first proxy
+    let headers = headers(&[("x-forwarded-for", "192.0.2.66, 198.51.100.7, 10.0.0.2")]);
+
+    assert_eq!(
+        config.client_ip_from(ip("10.0.0.1"), &headers),
+        ip("198.51.100.7")
+    );
+}
+
+#[test]
+fn forwarded() {
+    let config = config(ClientIpHeader::Forwarded, &["10.0.0.0/8", "2001:db8::/32"]);
+    let headers = headers(&[
+        ("forwarded", "for=192.0.2.66"),
+        (
+            "forwarded",
+            "for=\"198.51.100.7:4711\";proto=https, for=\"[2001:db8::1]\"",
+        ),
+    ]);
+
+    assert_eq!(
+        config.client_ip_from(ip("10.0.0.1"), &headers),
+        ip("198.51.100.7")
+    );
+}
+
+#[test]
+fn x_real_ip() {
+    let config = config(ClientIpHeader::XRealIp, &["127.0.0.1/32"]);
+    let headers = headers(&[("x-real-ip", "198.51.100.7")]);
+
+    assert_eq!(
+        config.client_ip_from(ip("127.0.0.1"), &headers),
+        ip("198.51.100.7")
+    );
+    assert_eq!(
+        config.client_ip_from(ip("::ffff:127.0.0.1"), &headers),
+        ip("198.51.100.7")
+    );
+}
+
+#[test]
+fn unknown_hops_end_the_walk() {
+    let config = config(ClientIpHeader::Forwarded, &["10.0.0.0/8"]);
+    let headers = headers(&[("forwarded", "for=192.0.2.66, for=unknown, for=10.0.0.2")]);
+
+    assert_eq!(
+        config.client_ip_from(ip("10.0.0.1"), &headers),
+        ip("10.0.0.2")
+    );
+}
+
+#[test]
+fn only_trusted_hops() {
+    let config = config(ClientIpHeader::XForwardedFor, &["10.0.0.0/8"]);
+    let headers = headers(&[("x-forwarded-for", "10.0.0.3, 10.0.0.2")]);
+
+    assert_eq!(
+        config.client_ip_from(ip("10.0.0.1"), &headers),
+        ip("10.0.0.3")
+    );
+}
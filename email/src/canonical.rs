@@ -0,0 +1,68 @@
+use crate::Email;
+
+/// Domains known to deliver `user+tag@domain` to `user@domain`.
+pub const PLUS_TAG_DOMAINS: &[&str] = &[
+    "fastmail.com",
+    "gmail.com",
+    "googlemail.com",
+    "hotmail.com",
+    "icloud.com",
+    "live.com",
+    "me.com",
+    "outlook.com",
+    "pm.me",
+    "proton.me",
+    "protonmail.com",
+];
+
+/// How [`Email::canonical`] folds the addresses that reach the same mailbox into one.
+///
+/// The domain is always lowercased and converted to its IDNA (punycode) form, since domains are case-insensitive.
+/// The local part is left as is by default: RFC 5321 lets the receiving server decide what it means.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Canonicalization {
+    /// `Joe@example.com` is `joe@example.com`, as it is with virtually every provider.
+    pub fold_local_part: bool,
+
+    /// `joe+news@gmail.com` is `joe@gmail.com`, for the [`PLUS_TAG_DOMAINS`].
+    pub strip_plus_tags: bool,
+}
+
+impl Canonicalization {
+    /// Names what [`Email::canonical`] folds besides the domain (`domain`, `domain+case`, `domain+plus`
+    /// or `domain+case+plus`), e.g. to store along with canonical forms and tell which ones another
+    /// canonicalization has to recompute.
+    pub fn kind(&self) -> &'static str {
+        match (self.fold_local_part, self.strip_plus_tags) {
+            (false, false) => "domain",
+            (true, false) => "domain+case",
+            (false, true) => "domain+plus",
+            (true, true) => "domain+case+plus",
+        }
+    }
+}
+
+impl Email {
+    /// The form two addresses are compared by, e.g. to tell whether one is already linked to an account.
+    /// Not meant to be sent to, since it may have lost the plus tag.
+    pub fn canonical(&self, canonicalization: Canonicalization) -> String {
+        let domain = self.canonical_domain();
+
+        let mut user = self.0.user();
+        if canonicalization.strip_plus_tags && PLUS_TAG_DOMAINS.contains(&domain.as_str()) {
+            user = user.split_once('+').map_or(user, |(user, _tag)| user);
+        }
+
+        match canonicalization.fold_local_part {
+            true => format!("{}@{domain}", user.to_lowercase()),
+            false => format!("{user}@{domain}"),
+        }
+    }
+
+    /// Lowercased and in its IDNA (punycode) form.
+    pub fn canonical_domain(&self) -> String {
+        // valid domains that are not valid IDNs, e.g. `[127.0.0.1]`, are only lowercased
+        idna::domain_to_ascii(self.0.domain())
+            .unwrap_or_else(|_| self.0.domain().to_ascii_lowercase())
+    }
+}
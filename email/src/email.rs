@@ -1,7 +1,7 @@
 use std::{fmt::Display, str::FromStr, string::FromUtf8Error};
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
-pub struct Email(lettre::Address);
+pub struct Email(pub(crate) lettre::Address);
 
 const MSG: &str = "email must conform to the HTML5 Specification https://html.spec.whatwg.org/multipage/input.html#valid-e-mail-address";
 
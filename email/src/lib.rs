@@ -1,3 +1,7 @@
+mod canonical;
 mod email;
+mod policy;
 
+pub use canonical::{Canonicalization, PLUS_TAG_DOMAINS};
 pub use email::{Email, ParseError};
+pub use policy::{DISPOSABLE_DOMAINS, EmailPolicy, PolicyViolation};
@@ -0,0 +1,120 @@
+use std::collections::HashSet;
+
+use crate::Email;
+
+/// See `disposable_domains.txt` at the root of this crate.
+pub const DISPOSABLE_DOMAINS: &str = include_str!("../disposable_domains.txt");
+
+/// Which domains email addresses may belong to, e.g. only `ourcompany.com` for an internal deployment.
+///
+/// Domains match themselves and their subdomains. Denied domains are checked first,
+/// then allowed ones (when there are any, every other domain is rejected), then disposable ones.
+/// Only the address itself is looked at, there are no DNS or MX lookups.
+#[derive(Debug, Clone)]
+pub struct EmailPolicy {
+    allowed: HashSet<String>,
+    denied: HashSet<String>,
+    disposable: HashSet<String>,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum PolicyViolation {
+    #[error("email domain `{0}` is denied")]
+    Denied(String),
+
+    #[error("email domain `{0}` is not allowed")]
+    NotAllowed(String),
+
+    #[error("email domain `{0}` belongs to a disposable email provider")]
+    Disposable(String),
+}
+
+impl EmailPolicy {
+    /// Rejects the bundled [`DISPOSABLE_DOMAINS`], and allows everything else.
+    pub fn new() -> Self {
+        Self::permissive().with_disposable_domains(DISPOSABLE_DOMAINS)
+    }
+
+    /// Allows everything.
+    pub fn permissive() -> Self {
+        Self {
+            allowed: HashSet::new(),
+            denied: HashSet::new(),
+            disposable: HashSet::new(),
+        }
+    }
+
+    /// Replaces the disposable domains, one per line. Blank lines and lines starting with `#` are ignored.
+    pub fn with_disposable_domains(mut self, list: &str) -> Self {
+        self.disposable = list
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(canonical_domain)
+            .collect();
+        self
+    }
+
+    pub fn with_allowed_domains<I, S>(mut self, domains: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.allowed = domains
+            .into_iter()
+            .map(|domain| canonical_domain(domain.as_ref()))
+            .collect();
+        self
+    }
+
+    pub fn with_denied_domains<I, S>(mut self, domains: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.denied = domains
+            .into_iter()
+            .map(|domain| canonical_domain(domain.as_ref()))
+            .collect();
+        self
+    }
+
+    pub fn check(&self, email: &Email) -> Result<(), PolicyViolation> {
+        let domain = email.canonical_domain();
+
+        if matches(&self.denied, &domain) {
+            return Err(PolicyViolation::Denied(domain));
+        }
+
+        if !self.allowed.is_empty() {
+            return match matches(&self.allowed, &domain) {
+                true => Ok(()),
+                false => Err(PolicyViolation::NotAllowed(domain)),
+            };
+        }
+
+        match matches(&self.disposable, &domain) {
+            true => Err(PolicyViolation::Disposable(domain)),
+            false => Ok(()),
+        }
+    }
+}
+
+impl Default for EmailPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `domain` or any of its parent domains is in `domains`.
+fn matches(domains: &HashSet<String>, domain: &str) -> bool {
+    std::iter::successors(Some(domain), |domain| {
+        domain.split_once('.').map(|(_, parent)| parent)
+    })
+    .any(|domain| domains.contains(domain))
+}
+
+fn canonical_domain(domain: &str) -> String {
+    let domain = domain.trim().trim_start_matches('@');
+    idna::domain_to_ascii(domain).unwrap_or_else(|_| domain.to_ascii_lowercase())
+}
@@ -0,0 +1,86 @@
+use email::{Canonicalization, Email};
+
+fn canonical(email: &str, canonicalization: Canonicalization) -> String {
+    email.parse::<Email>().unwrap().canonical(canonicalization)
+}
+
+#[test]
+fn domain_is_always_folded() {
+    let canonicalization = Canonicalization::default();
+
+    assert_eq!(
+        canonical("Joe@Example.COM", canonicalization),
+        "Joe@example.com"
+    );
+    assert_eq!(
+        canonical("joe@Bücher.example", canonicalization),
+        "joe@xn--bcher-kva.example"
+    );
+    assert_eq!(
+        canonical("joe@xn--bcher-kva.example", canonicalization),
+        canonical("joe@bücher.example", canonicalization)
+    );
+}
+
+#[test]
+fn local_part_is_folded_on_request() {
+    let canonicalization = Canonicalization {
+        fold_local_part: true,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        canonical("Joe@Example.com", canonicalization),
+        "joe@example.com"
+    );
+    assert_eq!(
+        canonical("ÉLODIE@example.com", canonicalization),
+        "élodie@example.com"
+    );
+}
+
+#[test]
+fn plus_tags_are_stripped_for_known_providers() {
+    let canonicalization = Canonicalization {
+        strip_plus_tags: true,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        canonical("joe+news@Gmail.com", canonicalization),
+        "joe@gmail.com"
+    );
+    assert_eq!(
+        canonical("joe+news+more@outlook.com", canonicalization),
+        "joe@outlook.com"
+    );
+
+    // other domains may give `+` another meaning
+    assert_eq!(
+        canonical("joe+news@example.com", canonicalization),
+        "joe+news@example.com"
+    );
+
+    assert_eq!(
+        canonical("joe+news@gmail.com", Canonicalization::default()),
+        "joe+news@gmail.com"
+    );
+}
+
+#[test]
+fn kinds_differ_by_what_is_folded() {
+    let kinds = [(false, false), (true, false), (false, true), (true, true)].map(
+        |(fold_local_part, strip_plus_tags)| {
+            Canonicalization {
+                fold_local_part,
+                strip_plus_tags,
+            }
+            .kind()
+        },
+    );
+
+    assert_eq!(Canonicalization::default().kind(), "domain");
+    for (i, kind) in kinds.iter().enumerate() {
+        assert!(!kinds[..i].contains(kind), "{kind}");
+    }
+}
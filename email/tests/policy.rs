@@ -0,0 +1,77 @@
+use email::{Email, EmailPolicy, PolicyViolation};
+
+fn check(policy: &EmailPolicy, email: &str) -> Result<(), PolicyViolation> {
+    policy.check(&email.parse::<Email>().unwrap())
+}
+
+#[test]
+fn disposable_domains_are_rejected_by_default() {
+    let policy = EmailPolicy::default();
+
+    assert_eq!(check(&policy, "joe@example.com"), Ok(()));
+    assert_eq!(
+        check(&policy, "joe@Mailinator.com"),
+        Err(PolicyViolation::Disposable("mailinator.com".into()))
+    );
+    assert_eq!(
+        check(&policy, "joe@eu.mailinator.com"),
+        Err(PolicyViolation::Disposable("eu.mailinator.com".into()))
+    );
+
+    // a domain merely ending like a disposable one
+    assert_eq!(check(&policy, "joe@notmailinator.company"), Ok(()));
+
+    assert_eq!(
+        check(&EmailPolicy::permissive(), "joe@mailinator.com"),
+        Ok(())
+    );
+}
+
+#[test]
+fn disposable_domains_can_be_replaced() {
+    let policy = EmailPolicy::new()
+        .with_disposable_domains("# comment\n\n  throwaway.example  \nBücher.example\n");
+
+    assert!(check(&policy, "joe@throwaway.example").is_err());
+    assert!(check(&policy, "joe@xn--bcher-kva.example").is_err());
+    assert_eq!(check(&policy, "joe@mailinator.com"), Ok(()));
+}
+
+#[test]
+fn only_allowed_domains() {
+    let policy = EmailPolicy::new().with_allowed_domains(["ourcompany.com", "@Partner.example"]);
+
+    assert_eq!(check(&policy, "joe@ourcompany.com"), Ok(()));
+    assert_eq!(check(&policy, "joe@eu.OurCompany.com"), Ok(()));
+    assert_eq!(check(&policy, "joe@partner.example"), Ok(()));
+    assert_eq!(
+        check(&policy, "joe@ourcompany.com.evil.example"),
+        Err(PolicyViolation::NotAllowed(
+            "ourcompany.com.evil.example".into()
+        ))
+    );
+    assert_eq!(
+        check(&policy, "joe@example.com"),
+        Err(PolicyViolation::NotAllowed("example.com".into()))
+    );
+}
+
+#[test]
+fn denied_domains_take_precedence() {
+    let policy = EmailPolicy::new()
+        .with_allowed_domains(["ourcompany.com"])
+        .with_denied_domains(["contractors.ourcompany.com", "example.com"]);
+
+    assert_eq!(check(&policy, "joe@ourcompany.com"), Ok(()));
+    assert_eq!(
+        check(&policy, "joe@contractors.ourcompany.com"),
+        Err(PolicyViolation::Denied("contractors.ourcompany.com".into()))
+    );
+
+    let policy = EmailPolicy::new().with_denied_domains(["example.com"]);
+    assert_eq!(
+        check(&policy, "joe@example.com"),
+        Err(PolicyViolation::Denied("example.com".into()))
+    );
+    assert_eq!(check(&policy, "joe@example.org"), Ok(()));
+}
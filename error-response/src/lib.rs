@@ -18,6 +18,28 @@ pub struct ErrorResponse {
         schema(example = "Please check the response headers for `x-trace-id`")
     )]
     help: Option<String>,
+
+    /// Every invalid field of the request, when there is more than one thing to fix.
+    #[cfg(feature = "fields")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fields: Option<Vec<FieldError>>,
+}
+
+#[cfg(feature = "fields")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(serde::Serialize)]
+pub struct FieldError {
+    #[cfg_attr(feature = "openapi", schema(example = "username"))]
+    pub field: String,
+
+    #[cfg_attr(feature = "openapi", schema(example = "username.invalid"))]
+    pub kind: String,
+
+    #[cfg_attr(
+        feature = "openapi",
+        schema(example = "username must be between 2-30 in length")
+    )]
+    pub message: String,
 }
 
 impl ErrorResponse {
@@ -33,6 +55,9 @@ impl ErrorResponse {
 
             #[cfg(feature = "help")]
             help: None,
+
+            #[cfg(feature = "fields")]
+            fields: None,
         }
     }
 
@@ -72,6 +97,17 @@ impl ErrorResponse {
         self.help = Some(help);
         self
     }
+
+    #[cfg(feature = "fields")]
+    pub fn fields(&self) -> Option<&[FieldError]> {
+        self.fields.as_deref()
+    }
+
+    #[cfg(feature = "fields")]
+    pub fn with_fields(mut self, fields: Vec<FieldError>) -> Self {
+        self.fields = Some(fields);
+        self
+    }
 }
 
 #[cfg(feature = "datetime")]
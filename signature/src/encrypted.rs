@@ -0,0 +1,305 @@
+use std::{borrow::Borrow, convert::TryFrom, time::Duration};
+
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use chacha20poly1305::{
+    KeyInit, XChaCha20Poly1305, XNonce,
+    aead::{Aead, Payload},
+};
+use contextual::Context;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use time::OffsetDateTime;
+use zeroize::Zeroizing;
+
+use crate::{
+    ConsumeError, DecodeError, EncodeError, Header, Signed, TemporalValidityError, TokenLedger,
+    Validation,
+};
+
+const NONCE_LEN: usize = 24;
+
+/// Like [`Signed`], with the same header and expiry semantics,
+/// but the header and the token are encrypted (XChaCha20-Poly1305) rather than only signed,
+/// so that e.g. a verification link doesn't reveal the email address it is for.
+///
+/// Encoded as `protected.ciphertext`, where `protected` only holds the `kid` and is authenticated along with the rest.
+#[derive(Debug, Clone)]
+pub struct Encrypted<T>(Signed<T>);
+
+/// 32 bytes, the key of XChaCha20-Poly1305.
+pub struct EncryptionKey(Zeroizing<[u8; 32]>);
+
+/// The part of the token that is readable without the key, to pick it.
+#[derive(Serialize, Deserialize)]
+struct Protected {
+    enc: Enc,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kid: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+enum Enc {
+    XC20P,
+}
+
+impl EncryptionKey {
+    pub fn new(key: [u8; 32]) -> Self {
+        Self(Zeroizing::new(key))
+    }
+
+    /// Derives a key dedicated to encryption from a secret that is also used for something else (e.g. HMAC),
+    /// so that the same bytes are never used as the key of two algorithms.
+    pub fn derive(secret: &[u8]) -> Self {
+        let mut mac =
+            <Hmac<Sha256> as Mac>::new_from_slice(secret).expect("HMAC takes keys of any length");
+        mac.update(b"mona/encrypted-token/v1");
+        Self(Zeroizing::new(mac.finalize().into_bytes().into()))
+    }
+
+    fn cipher(&self) -> XChaCha20Poly1305 {
+        XChaCha20Poly1305::new(self.0.as_slice().into())
+    }
+}
+
+impl<T> From<Signed<T>> for Encrypted<T> {
+    fn from(signed: Signed<T>) -> Self {
+        Self(signed)
+    }
+}
+
+impl<T> From<Encrypted<T>> for Signed<T> {
+    fn from(encrypted: Encrypted<T>) -> Self {
+        encrypted.0
+    }
+}
+
+impl<T> Encrypted<T> {
+    /// Creates a new `Encrypted` token with the default settings of [`Signed::new`].
+    pub fn new(token: T) -> Self {
+        Self(Signed::new(token))
+    }
+
+    pub fn with_ttl(self, ttl: Duration) -> Self {
+        Self(self.0.with_ttl(ttl))
+    }
+
+    /// Records which key the token is encrypted with, so that [`Encrypted::decrypt_with`] can pick it.
+    pub fn with_kid(self, kid: impl Into<String>) -> Self {
+        Self(self.0.with_kid(kid))
+    }
+
+    pub fn with_iss(self, iss: impl Into<String>) -> Self {
+        Self(self.0.with_iss(iss))
+    }
+
+    pub fn with_aud(self, aud: impl Into<String>) -> Self {
+        Self(self.0.with_aud(aud))
+    }
+
+    pub fn with_sub(self, sub: impl Into<String>) -> Self {
+        Self(self.0.with_sub(sub))
+    }
+
+    pub fn with_nbf(self, nbf: OffsetDateTime) -> Self {
+        Self(self.0.with_nbf(nbf))
+    }
+
+    pub fn with_jti(self, jti: impl Into<String>) -> Self {
+        Self(self.0.with_jti(jti))
+    }
+
+    pub fn with_purpose(self, purpose: impl Into<String>) -> Self {
+        Self(self.0.with_purpose(purpose))
+    }
+
+    pub fn kid(&self) -> Option<&str> {
+        self.0.kid()
+    }
+
+    pub fn iss(&self) -> Option<&str> {
+        self.0.iss()
+    }
+
+    pub fn aud(&self) -> Option<&str> {
+        self.0.aud()
+    }
+
+    pub fn sub(&self) -> Option<&str> {
+        self.0.sub()
+    }
+
+    pub fn jti(&self) -> Option<&str> {
+        self.0.jti()
+    }
+
+    pub fn purpose(&self) -> Option<&str> {
+        self.0.purpose()
+    }
+
+    pub fn iat(&self) -> OffsetDateTime {
+        self.0.iat()
+    }
+
+    pub fn exp(&self) -> OffsetDateTime {
+        self.0.exp()
+    }
+
+    pub fn nbf(&self) -> Option<OffsetDateTime> {
+        self.0.nbf()
+    }
+
+    /// The token, if it is valid at this moment, with no clock skew allowed.
+    pub fn token(self) -> Result<T, TemporalValidityError> {
+        self.0.token()
+    }
+
+    /// Like [`Encrypted::token`], allowing for [`Validation::leeway`] of clock skew.
+    pub fn token_with(self, validation: &Validation) -> Result<T, TemporalValidityError> {
+        self.0.token_with(validation)
+    }
+
+    /// See [`Signed::consume`].
+    pub async fn consume(
+        self,
+        ledger: &dyn TokenLedger,
+        validation: &Validation,
+    ) -> Result<T, ConsumeError> {
+        self.0.consume(ledger, validation).await
+    }
+
+    /// Encrypts the `Encrypted` token into a url-safe base64 encoded string with no padding,
+    /// with a fresh random nonce every time.
+    pub fn encrypt(&self, key: &EncryptionKey) -> Result<String, EncodeError>
+    where
+        T: AsRef<[u8]>,
+    {
+        let protected = Protected {
+            enc: Enc::XC20P,
+            kid: self.0.header.kid.clone(),
+        };
+        let protected_json = serde_json::to_string(&protected).context("protected")?;
+        let protected_base64encoded = BASE64_URL_SAFE_NO_PAD.encode(protected_json);
+
+        let plaintext = {
+            let header_json = serde_json::to_string(&self.0.header).context("header")?;
+            Zeroizing::new(format!(
+                "{}.{}",
+                BASE64_URL_SAFE_NO_PAD.encode(header_json),
+                BASE64_URL_SAFE_NO_PAD.encode(self.0.token.as_ref())
+            ))
+        };
+
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::rng().fill_bytes(&mut nonce);
+
+        let ciphertext = key
+            .cipher()
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext.as_bytes(),
+                    aad: protected_base64encoded.as_bytes(),
+                },
+            )
+            .map_err(|_| EncodeError::Encrypt)?;
+
+        Ok(format!(
+            "{protected_base64encoded}.{}",
+            BASE64_URL_SAFE_NO_PAD.encode([nonce.as_slice(), &ciphertext].concat())
+        ))
+    }
+
+    /// Decrypts an `Encrypted` token from a url-safe base64 encoded string with no padding.
+    pub fn decrypt(
+        s: &str,
+        key: &EncryptionKey,
+    ) -> Result<Encrypted<T>, DecodeError<<T as TryFrom<Vec<u8>>>::Error>>
+    where
+        T: TryFrom<Vec<u8>>,
+        <T as TryFrom<Vec<u8>>>::Error: std::error::Error,
+    {
+        Self::decrypt_with(s, |_| vec![key], &Validation::default())
+    }
+
+    /// Like [`Encrypted::decrypt`], with the key looked up by the `kid` of the token
+    /// as in [`Signed::verify_with`], and the claims checked against `validation`.
+    /// Time is checked by [`Encrypted::token_with`].
+    pub fn decrypt_with<K: Borrow<EncryptionKey>>(
+        s: &str,
+        keys: impl FnOnce(Option<&str>) -> Vec<K>,
+        validation: &Validation,
+    ) -> Result<Encrypted<T>, DecodeError<<T as TryFrom<Vec<u8>>>::Error>>
+    where
+        T: TryFrom<Vec<u8>>,
+        <T as TryFrom<Vec<u8>>>::Error: std::error::Error,
+    {
+        let parts = s.split('.').collect::<Vec<&str>>();
+        let [protected_part, ciphertext_part] = parts.as_slice() else {
+            return Err(DecodeError::InvalidFormat);
+        };
+
+        let protected = {
+            let bytes = BASE64_URL_SAFE_NO_PAD
+                .decode(protected_part)
+                .context("protected")?;
+            serde_json::from_slice::<Protected>(&bytes).context("protected")?
+        };
+
+        let (nonce, ciphertext) = {
+            let bytes = BASE64_URL_SAFE_NO_PAD
+                .decode(ciphertext_part)
+                .context("ciphertext")?;
+            if bytes.len() < NONCE_LEN {
+                return Err(DecodeError::InvalidFormat);
+            }
+            let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+            (nonce.to_vec(), ciphertext.to_vec())
+        };
+
+        let keys = keys(protected.kid.as_deref());
+        if keys.is_empty() {
+            return Err(DecodeError::UnknownKid(protected.kid));
+        }
+
+        let plaintext = keys
+            .iter()
+            .find_map(|key| {
+                key.borrow()
+                    .cipher()
+                    .decrypt(
+                        XNonce::from_slice(&nonce),
+                        Payload {
+                            msg: &ciphertext,
+                            aad: protected_part.as_bytes(),
+                        },
+                    )
+                    .ok()
+            })
+            .map(Zeroizing::new)
+            .ok_or(DecodeError::Decrypt)?;
+
+        let plaintext =
+            std::str::from_utf8(&plaintext).map_err(|_| DecodeError::NonUTF8("plaintext"))?;
+        let Some((header_part, token_part)) = plaintext.split_once('.') else {
+            return Err(DecodeError::InvalidFormat);
+        };
+
+        let header = {
+            let bytes = BASE64_URL_SAFE_NO_PAD
+                .decode(header_part)
+                .context("header")?;
+            serde_json::from_slice::<Header>(&bytes).context("header")?
+        };
+
+        validation.check_claims(&header.claims())?;
+
+        let token = {
+            let bytes = BASE64_URL_SAFE_NO_PAD.decode(token_part).context("token")?;
+            T::try_from(bytes).map_err(DecodeError::TokenFromBytes)?
+        };
+
+        Ok(Self(Signed { header, token }))
+    }
+}
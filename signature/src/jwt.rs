@@ -0,0 +1,332 @@
+//! JSON Web Tokens ([RFC 7519](https://www.rfc-editor.org/rfc/rfc7519)) in the JWS compact serialization
+//! ([RFC 7515](https://www.rfc-editor.org/rfc/rfc7515)), for tokens that other services must be able to read.
+//!
+//! Unlike [`Signed`](crate::Signed), whose payload is opaque bytes, the payload here is a JSON object:
+//! the registered claims along with whatever `T` serializes to.
+
+use std::{borrow::Borrow, time::Duration};
+
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use contextual::Context;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use sha2::digest::InvalidLength;
+use time::OffsetDateTime;
+
+use crate::{
+    Alg, DecodeError, EncodeError, SigningKey, TemporalValidityError, Validation, VerifyingKey,
+    validation::RegisteredClaims, verify_signature,
+};
+
+/// The JOSE header.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Header {
+    pub alg: Alg,
+
+    /// `JWT` for the tokens of [`encode`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub typ: Option<String>,
+
+    /// id of the key used to sign the token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+}
+
+/// The registered claims of a JWT, along with the `custom` ones.
+/// Dates are NumericDates, i.e. seconds since the unix epoch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims<T> {
+    /// issuer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    iss: Option<String>,
+    /// subject
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sub: Option<String>,
+    /// audience
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    aud: Option<Audience>,
+    /// expiry time
+    #[serde(
+        default,
+        with = "time::serde::timestamp::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    exp: Option<OffsetDateTime>,
+    /// not before time
+    #[serde(
+        default,
+        with = "time::serde::timestamp::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    nbf: Option<OffsetDateTime>,
+    /// issued at time
+    #[serde(
+        default,
+        with = "time::serde::timestamp::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    iat: Option<OffsetDateTime>,
+    /// unique id of the token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    jti: Option<String>,
+    /// what the token may be used for, e.g. `verify-email`.
+    /// Not a registered claim, but checked the same way as for [`Signed`](crate::Signed) tokens.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    purpose: Option<String>,
+
+    #[serde(flatten)]
+    custom: T,
+}
+
+/// A single audience is a string, several are an array of strings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Audience {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("{0}")]
+    Decode(#[from] DecodeError<serde_json::Error>),
+
+    #[error("{0}")]
+    TemporalValidity(#[from] TemporalValidityError),
+}
+
+impl<T> Claims<T> {
+    const DEFAULT_TTL: Duration = Duration::from_secs(3600);
+
+    /// Claims issued now, that expire after the default ttl.
+    /// Settings can be modified using the various builder-style methods.
+    pub fn new(custom: T) -> Self {
+        let iat = OffsetDateTime::now_utc()
+            .replace_nanosecond(0)
+            .expect("0 is a valid nanosecond");
+
+        Self {
+            iss: None,
+            sub: None,
+            aud: None,
+            exp: Some(iat + Self::DEFAULT_TTL),
+            nbf: None,
+            iat: Some(iat),
+            jti: None,
+            purpose: None,
+            custom,
+        }
+    }
+
+    /// From `iat`, or from now when there's none.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        let iat = self.iat.unwrap_or_else(OffsetDateTime::now_utc);
+        self.exp = Some(iat + ttl);
+        self
+    }
+
+    pub fn with_iss(mut self, iss: impl Into<String>) -> Self {
+        self.iss = Some(iss.into());
+        self
+    }
+
+    pub fn with_sub(mut self, sub: impl Into<String>) -> Self {
+        self.sub = Some(sub.into());
+        self
+    }
+
+    /// Adds an audience, so that the token is meant for all the ones given.
+    pub fn with_aud(mut self, aud: impl Into<String>) -> Self {
+        let aud = aud.into();
+        self.aud = Some(match self.aud.take() {
+            None => Audience::One(aud),
+            Some(Audience::One(first)) => Audience::Many(vec![first, aud]),
+            Some(Audience::Many(mut auds)) => {
+                auds.push(aud);
+                Audience::Many(auds)
+            }
+        });
+        self
+    }
+
+    pub fn with_nbf(mut self, nbf: OffsetDateTime) -> Self {
+        self.nbf = Some(nbf);
+        self
+    }
+
+    pub fn with_jti(mut self, jti: impl Into<String>) -> Self {
+        self.jti = Some(jti.into());
+        self
+    }
+
+    /// Binds the token to a single use, see [`Validation::purpose`].
+    pub fn with_purpose(mut self, purpose: impl Into<String>) -> Self {
+        self.purpose = Some(purpose.into());
+        self
+    }
+
+    pub fn iss(&self) -> Option<&str> {
+        self.iss.as_deref()
+    }
+
+    pub fn sub(&self) -> Option<&str> {
+        self.sub.as_deref()
+    }
+
+    pub fn aud(&self) -> Vec<&str> {
+        match &self.aud {
+            None => vec![],
+            Some(Audience::One(aud)) => vec![aud.as_str()],
+            Some(Audience::Many(auds)) => auds.iter().map(String::as_str).collect(),
+        }
+    }
+
+    pub fn exp(&self) -> Option<OffsetDateTime> {
+        self.exp
+    }
+
+    pub fn nbf(&self) -> Option<OffsetDateTime> {
+        self.nbf
+    }
+
+    pub fn iat(&self) -> Option<OffsetDateTime> {
+        self.iat
+    }
+
+    pub fn jti(&self) -> Option<&str> {
+        self.jti.as_deref()
+    }
+
+    pub fn purpose(&self) -> Option<&str> {
+        self.purpose.as_deref()
+    }
+
+    pub fn custom(&self) -> &T {
+        &self.custom
+    }
+
+    pub fn into_custom(self) -> T {
+        self.custom
+    }
+
+    fn registered(&self) -> RegisteredClaims<'_> {
+        RegisteredClaims {
+            iss: self.iss(),
+            aud: self.aud(),
+            sub: self.sub(),
+            purpose: self.purpose(),
+            jti: self.jti(),
+            iat: self.iat,
+            exp: self.exp,
+            nbf: self.nbf,
+        }
+    }
+}
+
+/// Signs `claims` into a JWT with `typ` set to `JWT` and the `alg` of `key`.
+/// `kid` is recorded in the header so that [`decode_with`] can pick the key.
+pub fn encode<T: Serialize>(
+    claims: &Claims<T>,
+    key: &SigningKey,
+    kid: Option<&str>,
+) -> Result<String, EncodeError> {
+    let header = Header {
+        alg: key.alg(),
+        typ: Some("JWT".into()),
+        kid: kid.map(str::to_string),
+    };
+
+    let payload = serde_json::to_vec(claims).context("claims")?;
+    sign(&header, &payload, key)
+}
+
+/// Verifies a JWT signed with the [`SigningKey`] that `key` belongs to,
+/// then checks its claims against `validation`.
+/// Unlike [`Signed`](crate::Signed) tokens, `exp` is optional in a JWT, so a token without one is rejected.
+pub fn decode<T: DeserializeOwned>(
+    s: &str,
+    key: &VerifyingKey,
+    validation: &Validation,
+) -> Result<Claims<T>, Error> {
+    decode_with(s, |_| vec![key], validation)
+}
+
+/// Like [`decode`], with the candidate keys looked up by the `kid` in the header,
+/// e.g. every unretired key when it is `None`.
+pub fn decode_with<T: DeserializeOwned, K: Borrow<VerifyingKey>>(
+    s: &str,
+    keys: impl FnOnce(Option<&str>) -> Vec<K>,
+    validation: &Validation,
+) -> Result<Claims<T>, Error> {
+    let (_, payload) = verify(s, keys)?;
+
+    let claims =
+        serde_json::from_slice::<Claims<T>>(&payload).map_err(DecodeError::TokenFromBytes)?;
+
+    validation
+        .check_claims(&claims.registered())
+        .map_err(DecodeError::from)?;
+    validation.check_time(&claims.registered(), OffsetDateTime::now_utc())?;
+
+    Ok(claims)
+}
+
+/// Signs any `payload` as a JWS, with the `alg` of `header` replaced by that of `key`.
+pub fn sign(header: &Header, payload: &[u8], key: &SigningKey) -> Result<String, EncodeError> {
+    let header = Header {
+        alg: key.alg(),
+        ..header.clone()
+    };
+
+    let header_json = serde_json::to_string(&header).context("header")?;
+    let header_base64encoded = BASE64_URL_SAFE_NO_PAD.encode(header_json);
+    let payload_base64encoded = BASE64_URL_SAFE_NO_PAD.encode(payload);
+    let signature_base64encoded = {
+        let signing_input = format!("{header_base64encoded}.{payload_base64encoded}");
+        let signature_bytes = key
+            .sign(signing_input.as_bytes())
+            .map_err(|_: InvalidLength| EncodeError::InvalidKeyLength)?;
+        BASE64_URL_SAFE_NO_PAD.encode(signature_bytes)
+    };
+
+    Ok(format!(
+        "{header_base64encoded}.{payload_base64encoded}.{signature_base64encoded}"
+    ))
+}
+
+/// Verifies the signature of a JWS, and returns its header and payload, which are only trusted from then on.
+/// Only the keys of the `alg` in the header are tried, see [`Signed::verify_with`](crate::Signed::verify_with).
+pub fn verify<K: Borrow<VerifyingKey>>(
+    s: &str,
+    keys: impl FnOnce(Option<&str>) -> Vec<K>,
+) -> Result<(Header, Vec<u8>), DecodeError<serde_json::Error>> {
+    let parts = s.split('.').collect::<Vec<&str>>();
+
+    let [header_part, payload_part, signature_part] = parts.as_slice() else {
+        return Err(DecodeError::InvalidFormat);
+    };
+
+    let header = {
+        let bytes = BASE64_URL_SAFE_NO_PAD
+            .decode(header_part)
+            .context("header")?;
+        serde_json::from_slice::<Header>(&bytes).context("header")?
+    };
+
+    let signature = BASE64_URL_SAFE_NO_PAD
+        .decode(signature_part)
+        .context("signature")?;
+
+    verify_signature(
+        header.alg,
+        &format!("{header_part}.{payload_part}"),
+        &signature,
+        keys(header.kid.as_deref()),
+        header.kid.as_deref(),
+    )?;
+
+    let payload = BASE64_URL_SAFE_NO_PAD
+        .decode(payload_part)
+        .context("payload")?;
+
+    Ok((header, payload))
+}
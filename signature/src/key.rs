@@ -0,0 +1,311 @@
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use contextual::Context;
+use ed25519_dalek::{
+    Signer,
+    pkcs8::{DecodePrivateKey, DecodePublicKey},
+};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, digest::InvalidLength};
+use zeroize::Zeroizing;
+
+/// How a token is signed, named as in JOSE ([RFC 7518](https://www.rfc-editor.org/rfc/rfc7518), [RFC 8037](https://www.rfc-editor.org/rfc/rfc8037)).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Alg {
+    /// HMAC-SHA256 with a shared secret, so whoever can verify a token can also forge one.
+    /// Tokens signed before `alg` was recorded are HS256.
+    #[default]
+    HS256,
+
+    /// Ed25519. Verifying only takes the public key.
+    EdDSA,
+
+    /// ECDSA over P-256 with SHA-256, for verifiers without Ed25519 support.
+    ES256,
+}
+
+/// What [`Signed::sign`](crate::Signed::sign) signs with.
+pub enum SigningKey {
+    Hs256(Zeroizing<Vec<u8>>),
+    Ed25519(ed25519_dalek::SigningKey),
+    Es256(p256::ecdsa::SigningKey),
+}
+
+/// What [`Signed::verify`](crate::Signed::verify) verifies with.
+/// Only ever matches tokens of its own [`Alg`], whatever their header claims.
+#[derive(Clone)]
+pub enum VerifyingKey {
+    Hs256(Zeroizing<Vec<u8>>),
+    Ed25519(ed25519_dalek::VerifyingKey),
+    Es256(p256::ecdsa::VerifyingKey),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum KeyError {
+    #[error("{0}")]
+    Pkcs8(#[from] ed25519_dalek::pkcs8::Error),
+
+    #[error("{0}")]
+    Spki(#[from] ed25519_dalek::pkcs8::spki::Error),
+
+    #[error("{0}")]
+    Ed25519(#[from] ed25519_dalek::SignatureError),
+
+    #[error("{0}")]
+    P256(#[from] p256::elliptic_curve::Error),
+
+    #[error("{0}")]
+    Serde(#[from] contextual::Error<serde_json::Error>),
+
+    #[error("{0}")]
+    Base64(#[from] contextual::Error<base64::DecodeError>),
+
+    #[error("unsupported JWK :: kty={kty:?} crv={crv:?}")]
+    UnsupportedJwk { kty: String, crv: Option<String> },
+
+    #[error("JWK is missing `{0}`")]
+    MissingJwkParameter(&'static str),
+
+    #[error("expected a {expected} bytes key, got {actual}")]
+    InvalidLength { expected: usize, actual: usize },
+}
+
+/// The parameters of a JWK ([RFC 7517](https://www.rfc-editor.org/rfc/rfc7517)) used by any kind of key.
+#[derive(Deserialize)]
+struct Jwk {
+    kty: String,
+    crv: Option<String>,
+
+    /// `OKP` public key, or `EC` public point x coordinate
+    x: Option<String>,
+
+    /// `EC` public point y coordinate
+    y: Option<String>,
+
+    /// `OKP` or `EC` private key
+    d: Option<String>,
+
+    /// `oct` secret
+    k: Option<String>,
+}
+
+impl SigningKey {
+    pub fn hs256(secret: &[u8]) -> Self {
+        Self::Hs256(Zeroizing::new(secret.to_vec()))
+    }
+
+    pub fn ed25519(secret_key: &[u8; 32]) -> Self {
+        Self::Ed25519(ed25519_dalek::SigningKey::from_bytes(secret_key))
+    }
+
+    pub fn alg(&self) -> Alg {
+        match self {
+            SigningKey::Hs256(_) => Alg::HS256,
+            SigningKey::Ed25519(_) => Alg::EdDSA,
+            SigningKey::Es256(_) => Alg::ES256,
+        }
+    }
+
+    /// For HS256, that's the secret itself.
+    pub fn verifying_key(&self) -> VerifyingKey {
+        match self {
+            SigningKey::Hs256(secret) => VerifyingKey::Hs256(secret.clone()),
+            SigningKey::Ed25519(key) => VerifyingKey::Ed25519(key.verifying_key()),
+            SigningKey::Es256(key) => VerifyingKey::Es256(*key.verifying_key()),
+        }
+    }
+
+    /// An Ed25519 or P-256 PKCS#8 `PRIVATE KEY`,
+    /// e.g. from `openssl genpkey -algorithm ed25519` or `openssl genpkey -algorithm ec -pkeyopt ec_paramgen_curve:P-256`.
+    pub fn from_pem(pem: &str) -> Result<Self, KeyError> {
+        match ed25519_dalek::SigningKey::from_pkcs8_pem(pem) {
+            Ok(key) => Ok(Self::Ed25519(key)),
+            Err(_) => Ok(Self::Es256(p256::ecdsa::SigningKey::from_pkcs8_pem(pem)?)),
+        }
+    }
+
+    /// An `OKP`/`Ed25519` or `EC`/`P-256` private key (with `d`), or an `oct` secret.
+    pub fn from_jwk(jwk: &str) -> Result<Self, KeyError> {
+        let jwk = serde_json::from_str::<Jwk>(jwk).context("jwk")?;
+
+        match (jwk.kty.as_str(), jwk.crv.as_deref()) {
+            ("OKP", Some("Ed25519")) => {
+                let d = jwk_parameter(jwk.d, "d")?;
+                Ok(Self::ed25519(&key_bytes(&d)?))
+            }
+            ("EC", Some("P-256")) => {
+                let d = jwk_parameter(jwk.d, "d")?;
+                Ok(Self::Es256(p256::ecdsa::SigningKey::from_slice(&d)?))
+            }
+            ("oct", _) => Ok(Self::Hs256(jwk_parameter(jwk.k, "k")?)),
+            _ => Err(KeyError::UnsupportedJwk {
+                kty: jwk.kty,
+                crv: jwk.crv,
+            }),
+        }
+    }
+
+    pub(crate) fn sign(&self, signing_input: &[u8]) -> Result<Vec<u8>, InvalidLength> {
+        match self {
+            SigningKey::Hs256(secret) => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(secret)?;
+                mac.update(signing_input);
+                Ok(mac.finalize().into_bytes().to_vec())
+            }
+            SigningKey::Ed25519(key) => Ok(key.sign(signing_input).to_bytes().to_vec()),
+            SigningKey::Es256(key) => {
+                // `r || s`, as JWS wants it, rather than DER
+                let signature: p256::ecdsa::Signature = key.sign(signing_input);
+                Ok(signature.to_bytes().to_vec())
+            }
+        }
+    }
+}
+
+impl VerifyingKey {
+    pub fn hs256(secret: &[u8]) -> Self {
+        Self::Hs256(Zeroizing::new(secret.to_vec()))
+    }
+
+    pub fn ed25519(public_key: &[u8; 32]) -> Result<Self, KeyError> {
+        Ok(Self::Ed25519(ed25519_dalek::VerifyingKey::from_bytes(
+            public_key,
+        )?))
+    }
+
+    pub fn alg(&self) -> Alg {
+        match self {
+            VerifyingKey::Hs256(_) => Alg::HS256,
+            VerifyingKey::Ed25519(_) => Alg::EdDSA,
+            VerifyingKey::Es256(_) => Alg::ES256,
+        }
+    }
+
+    /// An Ed25519 or P-256 SPKI `PUBLIC KEY`, e.g. from `openssl pkey -pubout`.
+    pub fn from_pem(pem: &str) -> Result<Self, KeyError> {
+        match ed25519_dalek::VerifyingKey::from_public_key_pem(pem) {
+            Ok(key) => Ok(Self::Ed25519(key)),
+            Err(_) => Ok(Self::Es256(p256::ecdsa::VerifyingKey::from_public_key_pem(
+                pem,
+            )?)),
+        }
+    }
+
+    /// An `OKP`/`Ed25519` or `EC`/`P-256` key (`d` is ignored if present), or an `oct` secret.
+    pub fn from_jwk(jwk: &str) -> Result<Self, KeyError> {
+        let jwk = serde_json::from_str::<Jwk>(jwk).context("jwk")?;
+
+        match (jwk.kty.as_str(), jwk.crv.as_deref()) {
+            ("OKP", Some("Ed25519")) => {
+                let x = jwk_parameter(jwk.x, "x")?;
+                Self::ed25519(&key_bytes(&x)?)
+            }
+            ("EC", Some("P-256")) => {
+                let x = key_bytes::<32>(&jwk_parameter(jwk.x, "x")?)?;
+                let y = key_bytes::<32>(&jwk_parameter(jwk.y, "y")?)?;
+                let point =
+                    p256::EncodedPoint::from_affine_coordinates(&x.into(), &y.into(), false);
+                Ok(Self::Es256(p256::ecdsa::VerifyingKey::from_encoded_point(
+                    &point,
+                )?))
+            }
+            ("oct", _) => Ok(Self::Hs256(jwk_parameter(jwk.k, "k")?)),
+            _ => Err(KeyError::UnsupportedJwk {
+                kty: jwk.kty,
+                crv: jwk.crv,
+            }),
+        }
+    }
+
+    /// The public JWK of an Ed25519 or P-256 key, to hand out to whoever verifies tokens.
+    /// `None` for HS256, whose secret must not be handed out.
+    pub fn to_jwk(&self) -> Option<String> {
+        match self {
+            VerifyingKey::Hs256(_) => None,
+            VerifyingKey::Ed25519(key) => Some(
+                serde_json::json!({
+                    "kty": "OKP",
+                    "crv": "Ed25519",
+                    "x": BASE64_URL_SAFE_NO_PAD.encode(key.as_bytes()),
+                })
+                .to_string(),
+            ),
+            VerifyingKey::Es256(key) => {
+                let point = key.to_encoded_point(false);
+                Some(
+                    serde_json::json!({
+                        "kty": "EC",
+                        "crv": "P-256",
+                        "x": BASE64_URL_SAFE_NO_PAD.encode(point.x()?),
+                        "y": BASE64_URL_SAFE_NO_PAD.encode(point.y()?),
+                    })
+                    .to_string(),
+                )
+            }
+        }
+    }
+
+    /// Constant time for HS256, and strict (no malleable signatures) for Ed25519.
+    pub(crate) fn verify(
+        &self,
+        signing_input: &[u8],
+        signature: &[u8],
+    ) -> Result<bool, InvalidLength> {
+        match self {
+            VerifyingKey::Hs256(secret) => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(secret)?;
+                mac.update(signing_input);
+                Ok(mac.verify_slice(signature).is_ok())
+            }
+            VerifyingKey::Ed25519(key) => {
+                let Ok(signature) = ed25519_dalek::Signature::from_slice(signature) else {
+                    return Ok(false);
+                };
+                Ok(key.verify_strict(signing_input, &signature).is_ok())
+            }
+            VerifyingKey::Es256(key) => {
+                use p256::ecdsa::signature::Verifier;
+
+                let Ok(signature) = p256::ecdsa::Signature::from_slice(signature) else {
+                    return Ok(false);
+                };
+                Ok(key.verify(signing_input, &signature).is_ok())
+            }
+        }
+    }
+}
+
+fn jwk_parameter(
+    parameter: Option<String>,
+    name: &'static str,
+) -> Result<Zeroizing<Vec<u8>>, KeyError> {
+    let parameter = Zeroizing::new(parameter.ok_or(KeyError::MissingJwkParameter(name))?);
+    Ok(Zeroizing::new(
+        BASE64_URL_SAFE_NO_PAD
+            .decode(parameter.as_bytes())
+            .context(name)?,
+    ))
+}
+
+fn key_bytes<const N: usize>(bytes: &[u8]) -> Result<[u8; N], KeyError> {
+    <[u8; N]>::try_from(bytes).map_err(|_| KeyError::InvalidLength {
+        expected: N,
+        actual: bytes.len(),
+    })
+}
+
+impl std::fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SigningKey").field(&self.alg()).finish()
+    }
+}
+
+impl std::fmt::Debug for VerifyingKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerifyingKey::Hs256(_) => f.debug_tuple("VerifyingKey").field(&Alg::HS256).finish(),
+            VerifyingKey::Ed25519(key) => f.debug_tuple("VerifyingKey").field(key).finish(),
+            VerifyingKey::Es256(key) => f.debug_tuple("VerifyingKey").field(key).finish(),
+        }
+    }
+}
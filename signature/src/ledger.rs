@@ -0,0 +1,120 @@
+use std::{collections::HashMap, future::Future, pin::Pin, sync::Mutex};
+
+use time::OffsetDateTime;
+
+use crate::{Signed, TemporalValidityError, Validation};
+
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+pub type TokenLedgerError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Where the `jti` of single-use tokens (e.g. email verification or password reset links) are recorded once used,
+/// so that a token can't be replayed until it expires.
+/// A ledger shared between processes makes them reject the tokens used with any of them.
+pub trait TokenLedger: Send + Sync {
+    /// Records `jti` as used until `exp`. `false` when it already was.
+    fn consume(
+        &self,
+        jti: String,
+        exp: OffsetDateTime,
+    ) -> BoxFuture<'_, Result<bool, TokenLedgerError>>;
+
+    /// Forgets the tokens that have expired, which can't be replayed anyway.
+    /// Returns how many were removed.
+    fn evict(&self) -> BoxFuture<'_, Result<u64, TokenLedgerError>>;
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ConsumeError {
+    #[error("{0}")]
+    TemporalValidity(#[from] TemporalValidityError),
+
+    #[error("single-use token has no jti")]
+    MissingJti,
+
+    #[error("token already used :: jti={0}")]
+    Reused(String),
+
+    #[error("{0}")]
+    Ledger(TokenLedgerError),
+}
+
+/// Keeps the used tokens in this process. They are not shared with other processes and are forgotten on restart.
+#[derive(Debug, Default)]
+pub struct MemoryLedger {
+    consumed: Mutex<HashMap<String, OffsetDateTime>>,
+}
+
+impl MemoryLedger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl TokenLedger for MemoryLedger {
+    fn consume(
+        &self,
+        jti: String,
+        exp: OffsetDateTime,
+    ) -> BoxFuture<'_, Result<bool, TokenLedgerError>> {
+        Box::pin(async move {
+            let mut consumed = self.consumed.lock().unwrap();
+            match consumed.contains_key(&jti) {
+                true => Ok(false),
+                false => {
+                    consumed.insert(jti, exp);
+                    Ok(true)
+                }
+            }
+        })
+    }
+
+    fn evict(&self) -> BoxFuture<'_, Result<u64, TokenLedgerError>> {
+        Box::pin(async move {
+            let now = OffsetDateTime::now_utc();
+            let mut consumed = self.consumed.lock().unwrap();
+
+            let before = consumed.len();
+            consumed.retain(|_, exp| *exp > now);
+            Ok((before - consumed.len()) as u64)
+        })
+    }
+}
+
+impl<T> Signed<T> {
+    /// Like [`Signed::token_with`], and records the token as used in `ledger`,
+    /// so that it is only ever accepted once. The token must have a `jti`.
+    ///
+    /// It is recorded until `exp` plus [`Validation::leeway`], after which it is rejected anyway.
+    pub async fn consume(
+        self,
+        ledger: &dyn TokenLedger,
+        validation: &Validation,
+    ) -> Result<T, ConsumeError> {
+        self.consume_with(validation, |jti, exp| ledger.consume(jti, exp))
+            .await
+    }
+
+    /// Like [`Signed::consume`], with `record` in place of [`TokenLedger::consume`],
+    /// e.g. to record the token in the same database transaction as what it is used for.
+    pub async fn consume_with<F, Fut>(
+        self,
+        validation: &Validation,
+        record: F,
+    ) -> Result<T, ConsumeError>
+    where
+        F: FnOnce(String, OffsetDateTime) -> Fut,
+        Fut: Future<Output = Result<bool, TokenLedgerError>>,
+    {
+        validation.check_time(&self.header.claims(), OffsetDateTime::now_utc())?;
+
+        let jti = self.header.jti.clone().ok_or(ConsumeError::MissingJti)?;
+        let exp = self.header.exp + validation.leeway;
+
+        match record(jti.clone(), exp).await {
+            Ok(true) => Ok(self.token),
+            Ok(false) => Err(ConsumeError::Reused(jti)),
+            Err(err) => Err(ConsumeError::Ledger(err)),
+        }
+    }
+}
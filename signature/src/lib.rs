@@ -1,17 +1,26 @@
-use std::{convert::TryFrom, time::Duration};
+mod encrypted;
+pub mod jwt;
+mod key;
+mod ledger;
+mod validation;
 
-use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine};
+pub use encrypted::{Encrypted, EncryptionKey};
+pub use key::{Alg, KeyError, SigningKey, VerifyingKey};
+pub use ledger::{ConsumeError, MemoryLedger, TokenLedger, TokenLedgerError};
+pub use validation::Validation;
+
+use std::{borrow::Borrow, convert::TryFrom, time::Duration};
+
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
 use contextual::Context;
-use hmac::{Hmac, Mac};
 use serde::{Deserialize, Serialize};
-use sha2::{
-    digest::{InvalidLength, MacError},
-    Sha256,
-};
+use sha2::digest::{InvalidLength, MacError};
 use time::OffsetDateTime;
 
+use crate::validation::RegisteredClaims;
+
 /// A generic container for a token that is signed and has an expiration date.
-/// The token is signed using HMAC-SHA256.
+/// The token is signed using HMAC-SHA256 or Ed25519, see [`Alg`].
 #[derive(Debug, Clone)]
 pub struct Signed<T> {
     header: Header,
@@ -20,10 +29,49 @@ pub struct Signed<T> {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct Header {
+    /// signature algorithm, absent in tokens signed before Ed25519 was supported
+    #[serde(default)]
+    alg: Alg,
     /// issued at time
     iat: OffsetDateTime,
     /// expiry time
     exp: OffsetDateTime,
+    /// id of the key used to sign the token, absent in tokens signed before keys were versioned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kid: Option<String>,
+    /// issuer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    iss: Option<String>,
+    /// audience
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    aud: Option<String>,
+    /// subject
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sub: Option<String>,
+    /// not before time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    nbf: Option<OffsetDateTime>,
+    /// unique id of the token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    jti: Option<String>,
+    /// what the token may be used for, e.g. `verify-email`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    purpose: Option<String>,
+}
+
+impl Header {
+    fn claims(&self) -> RegisteredClaims<'_> {
+        RegisteredClaims {
+            iss: self.iss.as_deref(),
+            aud: self.aud.as_deref().into_iter().collect(),
+            sub: self.sub.as_deref(),
+            purpose: self.purpose.as_deref(),
+            jti: self.jti.as_deref(),
+            iat: Some(self.iat),
+            exp: Some(self.exp),
+            nbf: self.nbf,
+        }
+    }
 }
 
 impl<T> Signed<T> {
@@ -34,7 +82,18 @@ impl<T> Signed<T> {
     pub fn new(token: T) -> Self {
         let iat = OffsetDateTime::now_utc();
         let exp = iat + Self::DEFAULT_TTL;
-        let header = Header { iat, exp };
+        let header = Header {
+            alg: Alg::default(),
+            iat,
+            exp,
+            kid: None,
+            iss: None,
+            aud: None,
+            sub: None,
+            nbf: None,
+            jti: None,
+            purpose: None,
+        };
         Signed { header, token }
     }
 
@@ -43,40 +102,122 @@ impl<T> Signed<T> {
         self
     }
 
-    pub fn token(self) -> Result<T, TemporalValidityError> {
-        let now = OffsetDateTime::now_utc();
+    /// Records which key the token is signed with, so that [`Signed::decode_with`] can pick it.
+    pub fn with_kid(mut self, kid: impl Into<String>) -> Self {
+        self.header.kid = Some(kid.into());
+        self
+    }
 
-        if self.header.exp < now {
-            return Err(TemporalValidityError::Expired {
-                exp: self.header.exp,
-                now,
-            });
-        }
+    pub fn with_iss(mut self, iss: impl Into<String>) -> Self {
+        self.header.iss = Some(iss.into());
+        self
+    }
 
-        if self.header.iat > now {
-            return Err(TemporalValidityError::NotYetValid {
-                iat: self.header.iat,
-                now,
-            });
-        }
+    pub fn with_aud(mut self, aud: impl Into<String>) -> Self {
+        self.header.aud = Some(aud.into());
+        self
+    }
+
+    pub fn with_sub(mut self, sub: impl Into<String>) -> Self {
+        self.header.sub = Some(sub.into());
+        self
+    }
+
+    /// The token is not valid before `nbf`, which is `iat` when unset.
+    pub fn with_nbf(mut self, nbf: OffsetDateTime) -> Self {
+        self.header.nbf = Some(nbf);
+        self
+    }
+
+    pub fn with_jti(mut self, jti: impl Into<String>) -> Self {
+        self.header.jti = Some(jti.into());
+        self
+    }
+
+    /// Binds the token to a single use (e.g. `verify-email`), see [`Validation::purpose`].
+    pub fn with_purpose(mut self, purpose: impl Into<String>) -> Self {
+        self.header.purpose = Some(purpose.into());
+        self
+    }
+
+    pub fn kid(&self) -> Option<&str> {
+        self.header.kid.as_deref()
+    }
 
+    pub fn alg(&self) -> Alg {
+        self.header.alg
+    }
+
+    pub fn iss(&self) -> Option<&str> {
+        self.header.iss.as_deref()
+    }
+
+    pub fn aud(&self) -> Option<&str> {
+        self.header.aud.as_deref()
+    }
+
+    pub fn sub(&self) -> Option<&str> {
+        self.header.sub.as_deref()
+    }
+
+    pub fn jti(&self) -> Option<&str> {
+        self.header.jti.as_deref()
+    }
+
+    pub fn purpose(&self) -> Option<&str> {
+        self.header.purpose.as_deref()
+    }
+
+    pub fn iat(&self) -> OffsetDateTime {
+        self.header.iat
+    }
+
+    pub fn exp(&self) -> OffsetDateTime {
+        self.header.exp
+    }
+
+    pub fn nbf(&self) -> Option<OffsetDateTime> {
+        self.header.nbf
+    }
+
+    /// The token, if it is valid at this moment, with no clock skew allowed.
+    pub fn token(self) -> Result<T, TemporalValidityError> {
+        self.token_with(&Validation::default())
+    }
+
+    /// Like [`Signed::token`], allowing for [`Validation::leeway`] of clock skew.
+    pub fn token_with(self, validation: &Validation) -> Result<T, TemporalValidityError> {
+        validation.check_time(&self.header.claims(), OffsetDateTime::now_utc())?;
         Ok(self.token)
     }
 
-    /// Encodes the `Signed` token into a url-safe base64 encoded string with no padding.
+    /// Encodes the `Signed` token into a url-safe base64 encoded string with no padding,
+    /// signed with HMAC-SHA256.
     pub fn encode(&self, secret: &[u8]) -> Result<String, EncodeError>
     where
         T: AsRef<[u8]>,
     {
-        let header_json = serde_json::to_string(&self.header).context("header")?;
+        self.sign(&SigningKey::hs256(secret))
+    }
+
+    /// Like [`Signed::encode`], with the algorithm of `key` recorded in the header.
+    pub fn sign(&self, key: &SigningKey) -> Result<String, EncodeError>
+    where
+        T: AsRef<[u8]>,
+    {
+        let header = Header {
+            alg: key.alg(),
+            ..self.header.clone()
+        };
+
+        let header_json = serde_json::to_string(&header).context("header")?;
         let header_base64encoded = BASE64_URL_SAFE_NO_PAD.encode(header_json);
         let token_base64encoded = BASE64_URL_SAFE_NO_PAD.encode(self.token.as_ref());
         let signature_base64encoded = {
             let signing_input = format!("{header_base64encoded}.{token_base64encoded}");
-            let mut mac = Hmac::<Sha256>::new_from_slice(secret)
+            let signature_bytes = key
+                .sign(signing_input.as_bytes())
                 .map_err(|_: InvalidLength| EncodeError::InvalidKeyLength)?;
-            mac.update(signing_input.as_bytes());
-            let signature_bytes = mac.finalize().into_bytes();
             BASE64_URL_SAFE_NO_PAD.encode(signature_bytes)
         };
         Ok(format!(
@@ -89,6 +230,61 @@ impl<T> Signed<T> {
         s: &str,
         secret: &[u8],
     ) -> Result<Signed<T>, DecodeError<<T as TryFrom<Vec<u8>>>::Error>>
+    where
+        T: TryFrom<Vec<u8>>,
+        <T as TryFrom<Vec<u8>>>::Error: std::error::Error,
+    {
+        Self::decode_with(s, |_| vec![secret])
+    }
+
+    /// Like [`Signed::decode`], but with the secret looked up by the `kid` in the header.
+    /// `secrets` returns the candidates for a `kid` (e.g. every unretired key when it is `None`)
+    /// and the signature must match one of them.
+    ///
+    /// The header is only trusted once the signature is verified.
+    pub fn decode_with<K: AsRef<[u8]>>(
+        s: &str,
+        secrets: impl FnOnce(Option<&str>) -> Vec<K>,
+    ) -> Result<Signed<T>, DecodeError<<T as TryFrom<Vec<u8>>>::Error>>
+    where
+        T: TryFrom<Vec<u8>>,
+        <T as TryFrom<Vec<u8>>>::Error: std::error::Error,
+    {
+        Self::verify_with(
+            s,
+            |kid| {
+                secrets(kid)
+                    .iter()
+                    .map(|secret| VerifyingKey::hs256(secret.as_ref()))
+                    .collect::<Vec<_>>()
+            },
+            &Validation::default(),
+        )
+    }
+
+    /// Decodes a `Signed` token signed with the [`SigningKey`] that `key` belongs to.
+    pub fn verify(
+        s: &str,
+        key: &VerifyingKey,
+    ) -> Result<Signed<T>, DecodeError<<T as TryFrom<Vec<u8>>>::Error>>
+    where
+        T: TryFrom<Vec<u8>>,
+        <T as TryFrom<Vec<u8>>>::Error: std::error::Error,
+    {
+        Self::verify_with(s, |_| vec![key], &Validation::default())
+    }
+
+    /// Like [`Signed::decode_with`], for any kind of [`VerifyingKey`],
+    /// and with the claims checked against `validation` once the signature is verified.
+    /// Time is checked by [`Signed::token_with`].
+    ///
+    /// Only the keys of the `alg` in the header are tried, so that e.g. an Ed25519 public key
+    /// can never be mistaken for an HMAC secret by a token claiming to be HS256.
+    pub fn verify_with<K: Borrow<VerifyingKey>>(
+        s: &str,
+        keys: impl FnOnce(Option<&str>) -> Vec<K>,
+        validation: &Validation,
+    ) -> Result<Signed<T>, DecodeError<<T as TryFrom<Vec<u8>>>::Error>>
     where
         T: TryFrom<Vec<u8>>,
         <T as TryFrom<Vec<u8>>>::Error: std::error::Error,
@@ -97,14 +293,6 @@ impl<T> Signed<T> {
 
         match parts.as_slice() {
             [header_part, token_part, signature_part] => {
-                let signature = BASE64_URL_SAFE_NO_PAD
-                    .decode(signature_part)
-                    .context("signature")?;
-                let mut mac = Hmac::<Sha256>::new_from_slice(secret)
-                    .map_err(|InvalidLength| DecodeError::InvalidKeyLength)?;
-                mac.update(format!("{header_part}.{token_part}").as_bytes());
-                mac.verify_slice(&signature)?;
-
                 let header = {
                     let bytes = BASE64_URL_SAFE_NO_PAD
                         .decode(header_part)
@@ -116,6 +304,20 @@ impl<T> Signed<T> {
                     serde_json::from_str::<Header>(&json_string).context("header")?
                 };
 
+                let signature = BASE64_URL_SAFE_NO_PAD
+                    .decode(signature_part)
+                    .context("signature")?;
+
+                verify_signature(
+                    header.alg,
+                    &format!("{header_part}.{token_part}"),
+                    &signature,
+                    keys(header.kid.as_deref()),
+                    header.kid.as_deref(),
+                )?;
+
+                validation.check_claims(&header.claims())?;
+
                 let token = {
                     let bytes = BASE64_URL_SAFE_NO_PAD.decode(token_part).context("token")?;
                     T::try_from(bytes).map_err(DecodeError::TokenFromBytes)?
@@ -128,6 +330,43 @@ impl<T> Signed<T> {
     }
 }
 
+/// Checks `signature` against the candidate `keys` for `kid`, only trying those of `alg`.
+fn verify_signature<K: Borrow<VerifyingKey>, E: std::error::Error>(
+    alg: Alg,
+    signing_input: &str,
+    signature: &[u8],
+    keys: Vec<K>,
+    kid: Option<&str>,
+) -> Result<(), DecodeError<E>> {
+    if keys.is_empty() {
+        return Err(DecodeError::UnknownKid(kid.map(str::to_string)));
+    }
+
+    let keys = keys
+        .iter()
+        .map(Borrow::borrow)
+        .filter(|key| key.alg() == alg)
+        .collect::<Vec<&VerifyingKey>>();
+    if keys.is_empty() {
+        return Err(DecodeError::UnexpectedAlg(alg));
+    }
+
+    let mut verified = false;
+    for key in keys {
+        verified |= key
+            .verify(signing_input.as_bytes(), signature)
+            .map_err(|InvalidLength| DecodeError::InvalidKeyLength)?;
+    }
+
+    match verified {
+        true => Ok(()),
+        false => Err(match alg {
+            Alg::HS256 => DecodeError::MacMismatch(MacError),
+            Alg::EdDSA | Alg::ES256 => DecodeError::SignatureMismatch,
+        }),
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum TemporalValidityError {
     #[error("token expired at {exp} (now: {now})")]
@@ -141,6 +380,46 @@ pub enum TemporalValidityError {
         iat: OffsetDateTime,
         now: OffsetDateTime,
     },
+
+    #[error("token not valid before {nbf} (now: {now})")]
+    NotBefore {
+        nbf: OffsetDateTime,
+        now: OffsetDateTime,
+    },
+
+    #[error("token has no expiry time")]
+    MissingExp,
+}
+
+/// A claim that doesn't match its [`Validation`].
+#[derive(thiserror::Error, Debug)]
+pub enum ClaimError {
+    #[error("invalid issuer {actual:?}, expected {expected:?}")]
+    Issuer {
+        expected: String,
+        actual: Option<String>,
+    },
+
+    #[error("invalid audience {actual:?}, expected {expected:?}")]
+    Audience {
+        expected: String,
+        actual: Vec<String>,
+    },
+
+    #[error("invalid subject {actual:?}, expected {expected:?}")]
+    Subject {
+        expected: String,
+        actual: Option<String>,
+    },
+
+    #[error("invalid purpose {actual:?}, expected {expected:?}")]
+    Purpose {
+        expected: String,
+        actual: Option<String>,
+    },
+
+    #[error("missing jti")]
+    MissingJti,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -148,6 +427,9 @@ pub enum EncodeError {
     #[error("Invalid Key Length")]
     InvalidKeyLength,
 
+    #[error("Unable to encrypt")]
+    Encrypt,
+
     #[error("{0}")]
     Serde(#[from] contextual::Error<serde_json::Error>),
 }
@@ -166,6 +448,21 @@ where
     #[error("{0}")]
     MacMismatch(#[from] MacError),
 
+    #[error("Signature mismatch")]
+    SignatureMismatch,
+
+    #[error("Unable to decrypt, either the key is wrong or the token was tampered with")]
+    Decrypt,
+
+    #[error("no key for kid {0:?}")]
+    UnknownKid(Option<String>),
+
+    #[error("no key for alg {0:?}")]
+    UnexpectedAlg(Alg),
+
+    #[error("{0}")]
+    Claim(#[from] ClaimError),
+
     #[error("Non-UTF8 sequence for {0}")]
     NonUTF8(&'static str),
 
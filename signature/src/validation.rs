@@ -0,0 +1,148 @@
+use std::time::Duration;
+
+use time::OffsetDateTime;
+
+use crate::{ClaimError, TemporalValidityError};
+
+/// What a token must satisfy besides its signature.
+/// Claims left unset here are not checked, whatever the token carries.
+#[derive(Debug, Clone, Default)]
+pub struct Validation {
+    /// Clock skew tolerated between the issuer and the verifier,
+    /// applied to `exp`, `iat` and `nbf`.
+    pub leeway: Duration,
+
+    pub iss: Option<String>,
+    pub aud: Option<String>,
+    pub sub: Option<String>,
+
+    /// Keeps a token issued for one use (e.g. `verify-email`)
+    /// from being replayed against another endpoint that accepts the same key.
+    pub purpose: Option<String>,
+
+    /// For tokens meant to be used once, whose `jti` is then recorded.
+    pub require_jti: bool,
+}
+
+impl Validation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_leeway(mut self, leeway: Duration) -> Self {
+        self.leeway = leeway;
+        self
+    }
+
+    pub fn with_iss(mut self, iss: impl Into<String>) -> Self {
+        self.iss = Some(iss.into());
+        self
+    }
+
+    pub fn with_aud(mut self, aud: impl Into<String>) -> Self {
+        self.aud = Some(aud.into());
+        self
+    }
+
+    pub fn with_sub(mut self, sub: impl Into<String>) -> Self {
+        self.sub = Some(sub.into());
+        self
+    }
+
+    pub fn with_purpose(mut self, purpose: impl Into<String>) -> Self {
+        self.purpose = Some(purpose.into());
+        self
+    }
+
+    pub fn require_jti(mut self) -> Self {
+        self.require_jti = true;
+        self
+    }
+
+    pub(crate) fn check_claims(&self, claims: &RegisteredClaims) -> Result<(), ClaimError> {
+        if let Some(expected) = mismatch(&self.iss, claims.iss) {
+            return Err(ClaimError::Issuer {
+                expected,
+                actual: claims.iss.map(str::to_string),
+            });
+        }
+
+        if let Some(expected) = &self.aud
+            && !claims.aud.contains(&expected.as_str())
+        {
+            return Err(ClaimError::Audience {
+                expected: expected.clone(),
+                actual: claims.aud.iter().map(|aud| aud.to_string()).collect(),
+            });
+        }
+
+        if let Some(expected) = mismatch(&self.sub, claims.sub) {
+            return Err(ClaimError::Subject {
+                expected,
+                actual: claims.sub.map(str::to_string),
+            });
+        }
+
+        if let Some(expected) = mismatch(&self.purpose, claims.purpose) {
+            return Err(ClaimError::Purpose {
+                expected,
+                actual: claims.purpose.map(str::to_string),
+            });
+        }
+
+        if self.require_jti && claims.jti.is_none() {
+            return Err(ClaimError::MissingJti);
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn check_time(
+        &self,
+        claims: &RegisteredClaims,
+        now: OffsetDateTime,
+    ) -> Result<(), TemporalValidityError> {
+        let Some(exp) = claims.exp else {
+            return Err(TemporalValidityError::MissingExp);
+        };
+
+        if exp + self.leeway < now {
+            return Err(TemporalValidityError::Expired { exp, now });
+        }
+
+        if let Some(iat) = claims.iat
+            && iat - self.leeway > now
+        {
+            return Err(TemporalValidityError::NotYetValid { iat, now });
+        }
+
+        if let Some(nbf) = claims.nbf
+            && nbf - self.leeway > now
+        {
+            return Err(TemporalValidityError::NotBefore { nbf, now });
+        }
+
+        Ok(())
+    }
+}
+
+/// The claims a [`Validation`] looks at, whether they come from a [`Signed`](crate::Signed) header
+/// or from a [`jwt::Claims`](crate::jwt::Claims).
+pub(crate) struct RegisteredClaims<'a> {
+    pub iss: Option<&'a str>,
+    pub aud: Vec<&'a str>,
+    pub sub: Option<&'a str>,
+    pub purpose: Option<&'a str>,
+    pub jti: Option<&'a str>,
+    pub iat: Option<OffsetDateTime>,
+    pub exp: Option<OffsetDateTime>,
+    pub nbf: Option<OffsetDateTime>,
+}
+
+/// The expected value, when there is one and `actual` doesn't match it.
+fn mismatch(expected: &Option<String>, actual: Option<&str>) -> Option<String> {
+    match expected {
+        Some(expected) if actual != Some(expected.as_str()) => Some(expected.clone()),
+        _ => None,
+    }
+}
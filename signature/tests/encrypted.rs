@@ -0,0 +1,125 @@
+use std::time::Duration;
+
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use signature::{DecodeError, Encrypted, EncryptionKey, Signed, Validation};
+
+const TOKEN: &[u8] = b"user1@test.com";
+
+#[test]
+fn roundtrip() {
+    let key = EncryptionKey::derive(b"some secret");
+
+    let encrypted = Encrypted::new(TOKEN.to_vec())
+        .with_purpose("verify-email")
+        .encrypt(&key)
+        .unwrap();
+    let decrypted = Encrypted::<Vec<u8>>::decrypt(&encrypted, &key).unwrap();
+    assert_eq!(decrypted.purpose(), Some("verify-email"));
+    assert_eq!(decrypted.token().unwrap(), TOKEN);
+
+    // a fresh nonce every time
+    let again = Encrypted::new(TOKEN.to_vec()).encrypt(&key).unwrap();
+    assert_ne!(Encrypted::new(TOKEN.to_vec()).encrypt(&key).unwrap(), again);
+}
+
+#[test]
+fn claims_are_confidential() {
+    let key = EncryptionKey::derive(b"some secret");
+
+    let signed = Signed::new(TOKEN.to_vec())
+        .with_purpose("verify-email")
+        .encode(b"some secret")
+        .unwrap();
+    let encrypted = Encrypted::new(TOKEN.to_vec())
+        .with_purpose("verify-email")
+        .encrypt(&key)
+        .unwrap();
+
+    let encoded_token = BASE64_URL_SAFE_NO_PAD.encode(TOKEN);
+    assert!(signed.contains(&encoded_token));
+    assert!(!encrypted.contains(&encoded_token));
+
+    for part in encrypted.split('.') {
+        let decoded = BASE64_URL_SAFE_NO_PAD.decode(part).unwrap();
+        assert!(!decoded.windows(TOKEN.len()).any(|window| window == TOKEN));
+        assert!(!decoded.windows(6).any(|window| window == b"verify"));
+    }
+}
+
+#[test]
+fn tampered_or_foreign_tokens_are_rejected() {
+    let key = EncryptionKey::derive(b"some secret");
+    let other_key = EncryptionKey::derive(b"some other secret");
+
+    let encrypted = Encrypted::new(TOKEN.to_vec())
+        .with_kid("v1")
+        .encrypt(&key)
+        .unwrap();
+
+    assert!(matches!(
+        Encrypted::<Vec<u8>>::decrypt(&encrypted, &other_key),
+        Err(DecodeError::Decrypt)
+    ));
+
+    // flipping a bit of the ciphertext
+    let (protected, ciphertext) = encrypted.split_once('.').unwrap();
+    let mut ciphertext = BASE64_URL_SAFE_NO_PAD.decode(ciphertext).unwrap();
+    *ciphertext.last_mut().unwrap() ^= 1;
+    let tampered = format!("{protected}.{}", BASE64_URL_SAFE_NO_PAD.encode(ciphertext));
+    assert!(matches!(
+        Encrypted::<Vec<u8>>::decrypt(&tampered, &key),
+        Err(DecodeError::Decrypt)
+    ));
+
+    // the kid is authenticated along with the ciphertext
+    let other_kid = BASE64_URL_SAFE_NO_PAD.encode(r#"{"enc":"XC20P","kid":"v2"}"#);
+    let (_, ciphertext) = encrypted.split_once('.').unwrap();
+    assert!(matches!(
+        Encrypted::<Vec<u8>>::decrypt(&format!("{other_kid}.{ciphertext}"), &key),
+        Err(DecodeError::Decrypt)
+    ));
+
+    // the key is picked by kid
+    let decrypted = Encrypted::<Vec<u8>>::decrypt_with(
+        &encrypted,
+        |kid| match kid {
+            Some("v1") => vec![&key],
+            _ => vec![],
+        },
+        &Validation::default(),
+    )
+    .unwrap();
+    assert_eq!(decrypted.kid(), Some("v1"));
+
+    // signed tokens are not encrypted ones
+    let signed = Signed::new(TOKEN.to_vec()).encode(b"some secret").unwrap();
+    assert!(matches!(
+        Encrypted::<Vec<u8>>::decrypt(&signed, &key),
+        Err(DecodeError::InvalidFormat)
+    ));
+}
+
+#[test]
+fn claims_and_time_are_validated() {
+    let key = EncryptionKey::derive(b"some secret");
+    let validation = Validation::new().with_purpose("verify-email");
+
+    let encrypted = Encrypted::new(TOKEN.to_vec())
+        .with_purpose("reset-password")
+        .encrypt(&key)
+        .unwrap();
+    assert!(matches!(
+        Encrypted::<Vec<u8>>::decrypt_with(&encrypted, |_| vec![&key], &validation),
+        Err(DecodeError::Claim(_))
+    ));
+
+    let expired = Encrypted::new(TOKEN.to_vec())
+        .with_purpose("verify-email")
+        .with_ttl(Duration::ZERO)
+        .encrypt(&key)
+        .unwrap();
+    std::thread::sleep(Duration::from_millis(10));
+    let decrypted =
+        Encrypted::<Vec<u8>>::decrypt_with(&expired, |_| vec![&key], &validation).unwrap();
+    assert!(decrypted.token_with(&validation).is_err());
+}
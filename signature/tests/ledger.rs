@@ -0,0 +1,97 @@
+use std::time::Duration;
+
+use signature::{ConsumeError, MemoryLedger, Signed, TokenLedger, Validation};
+use time::OffsetDateTime;
+
+#[tokio::test]
+async fn tokens_are_consumed_once() {
+    let ledger = MemoryLedger::new();
+    let validation = Validation::default();
+
+    let token = || Signed::new(b"user1@test.com".to_vec()).with_jti("some-jti");
+
+    assert_eq!(
+        token().consume(&ledger, &validation).await.unwrap(),
+        b"user1@test.com"
+    );
+    assert!(matches!(
+        token().consume(&ledger, &validation).await,
+        Err(ConsumeError::Reused(jti)) if jti == "some-jti"
+    ));
+
+    let other = Signed::new(b"user1@test.com".to_vec()).with_jti("other-jti");
+    assert!(other.consume(&ledger, &validation).await.is_ok());
+
+    let without_jti = Signed::new(b"user1@test.com".to_vec());
+    assert!(matches!(
+        without_jti.consume(&ledger, &validation).await,
+        Err(ConsumeError::MissingJti)
+    ));
+
+    // expired tokens are rejected before being recorded
+    let expired = Signed::new(b"user1@test.com".to_vec())
+        .with_jti("expired-jti")
+        .with_ttl(Duration::ZERO);
+    std::thread::sleep(Duration::from_millis(10));
+    assert!(matches!(
+        expired.consume(&ledger, &validation).await,
+        Err(ConsumeError::TemporalValidity(_))
+    ));
+    assert!(
+        ledger
+            .consume("expired-jti".into(), OffsetDateTime::now_utc())
+            .await
+            .unwrap()
+    );
+}
+
+#[tokio::test]
+async fn expired_tokens_are_evicted() {
+    let ledger = MemoryLedger::new();
+    let now = OffsetDateTime::now_utc();
+
+    assert!(
+        ledger
+            .consume("expired".into(), now - Duration::from_secs(1))
+            .await
+            .unwrap()
+    );
+    assert!(
+        ledger
+            .consume("valid".into(), now + Duration::from_secs(60))
+            .await
+            .unwrap()
+    );
+
+    assert_eq!(ledger.evict().await.unwrap(), 1);
+    assert!(ledger.consume("expired".into(), now).await.unwrap());
+    assert!(!ledger.consume("valid".into(), now).await.unwrap());
+}
+
+#[tokio::test]
+async fn consume_with() {
+    let validation = Validation::default();
+    let token = || Signed::new(b"user1@test.com".to_vec()).with_jti("some-jti");
+
+    assert!(matches!(
+        token()
+            .consume_with(&validation, |jti, _| async move {
+                assert_eq!(jti, "some-jti");
+                Ok(true)
+            })
+            .await,
+        Ok(token) if token == b"user1@test.com"
+    ));
+    assert!(matches!(
+        token()
+            .consume_with(&validation, |_, _| async { Ok(false) })
+            .await,
+        Err(ConsumeError::Reused(jti)) if jti == "some-jti"
+    ));
+    assert!(matches!(
+        token()
+            .consume_with(&validation, |_, _| async { Err("unavailable".into()) })
+            .await,
+        Err(ConsumeError::Ledger(_))
+    ));
+}
@@ -0,0 +1,146 @@
+use std::{convert::Infallible, time::Duration};
+
+use signature::{ClaimError, DecodeError, Signed, TemporalValidityError, Validation, VerifyingKey};
+use time::OffsetDateTime;
+
+const SECRET: &[u8] = b"some secret";
+
+fn verify(
+    signed: Signed<Vec<u8>>,
+    validation: &Validation,
+) -> Result<Signed<Vec<u8>>, DecodeError<Infallible>> {
+    let encoded = signed.encode(SECRET).unwrap();
+    Signed::verify_with(&encoded, |_| vec![VerifyingKey::hs256(SECRET)], validation)
+}
+
+fn claim_error(signed: Signed<Vec<u8>>, validation: &Validation) -> ClaimError {
+    match verify(signed, validation) {
+        Err(DecodeError::Claim(err)) => err,
+        other => panic!("expected a claim error, got {other:?}"),
+    }
+}
+
+fn token() -> Signed<Vec<u8>> {
+    Signed::new(b"user1@test.com".to_vec())
+        .with_iss("auth")
+        .with_aud("app")
+        .with_sub("1")
+        .with_purpose("verify-email")
+        .with_jti("a")
+}
+
+#[test]
+fn matching_claims() {
+    let validation = Validation::new()
+        .with_iss("auth")
+        .with_aud("app")
+        .with_sub("1")
+        .with_purpose("verify-email")
+        .require_jti();
+
+    let verified = verify(token(), &validation).unwrap();
+    assert_eq!(verified.token_with(&validation).unwrap(), b"user1@test.com");
+
+    // claims left unset in the validation are not checked
+    let plain = Signed::new(b"user1@test.com".to_vec());
+    assert!(verify(plain, &Validation::default()).is_ok());
+}
+
+#[test]
+fn claim_mismatches() {
+    assert!(matches!(
+        claim_error(token(), &Validation::new().with_iss("other")),
+        ClaimError::Issuer { expected, actual } if expected == "other" && actual.as_deref() == Some("auth")
+    ));
+    assert!(matches!(
+        claim_error(token(), &Validation::new().with_aud("other")),
+        ClaimError::Audience { expected, actual } if expected == "other" && actual == ["app"]
+    ));
+    assert!(matches!(
+        claim_error(token(), &Validation::new().with_sub("2")),
+        ClaimError::Subject { expected, actual } if expected == "2" && actual.as_deref() == Some("1")
+    ));
+    assert!(matches!(
+        claim_error(token(), &Validation::new().with_purpose("reset-password")),
+        ClaimError::Purpose { expected, actual }
+            if expected == "reset-password" && actual.as_deref() == Some("verify-email")
+    ));
+}
+
+#[test]
+fn missing_claims() {
+    let plain = || Signed::new(b"user1@test.com".to_vec());
+
+    assert!(matches!(
+        claim_error(plain(), &Validation::new().with_iss("auth")),
+        ClaimError::Issuer { actual: None, .. }
+    ));
+    assert!(matches!(
+        claim_error(plain(), &Validation::new().with_aud("app")),
+        ClaimError::Audience { actual, .. } if actual.is_empty()
+    ));
+    assert!(matches!(
+        claim_error(plain(), &Validation::new().with_sub("1")),
+        ClaimError::Subject { actual: None, .. }
+    ));
+    assert!(matches!(
+        claim_error(plain(), &Validation::new().with_purpose("verify-email")),
+        ClaimError::Purpose { actual: None, .. }
+    ));
+    assert!(matches!(
+        claim_error(plain(), &Validation::new().require_jti()),
+        ClaimError::MissingJti
+    ));
+}
+
+#[test]
+fn expiry_leeway() {
+    let expired = verify(
+        Signed::new(b"user1@test.com".to_vec()).with_ttl(Duration::ZERO),
+        &Validation::default(),
+    )
+    .unwrap();
+    std::thread::sleep(Duration::from_millis(50));
+
+    assert!(matches!(
+        expired.clone().token(),
+        Err(TemporalValidityError::Expired { .. })
+    ));
+    assert!(matches!(
+        expired
+            .clone()
+            .token_with(&Validation::new().with_leeway(Duration::from_millis(10))),
+        Err(TemporalValidityError::Expired { .. })
+    ));
+    assert!(
+        expired
+            .token_with(&Validation::new().with_leeway(Duration::from_secs(30)))
+            .is_ok()
+    );
+}
+
+#[test]
+fn not_before_leeway() {
+    let not_yet = verify(
+        Signed::new(b"user1@test.com".to_vec())
+            .with_nbf(OffsetDateTime::now_utc() + Duration::from_secs(60)),
+        &Validation::default(),
+    )
+    .unwrap();
+
+    assert!(matches!(
+        not_yet.clone().token(),
+        Err(TemporalValidityError::NotBefore { .. })
+    ));
+    assert!(matches!(
+        not_yet
+            .clone()
+            .token_with(&Validation::new().with_leeway(Duration::from_secs(30))),
+        Err(TemporalValidityError::NotBefore { .. })
+    ));
+    assert!(
+        not_yet
+            .token_with(&Validation::new().with_leeway(Duration::from_secs(90)))
+            .is_ok()
+    );
+}
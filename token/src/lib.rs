@@ -1,6 +1,7 @@
 use std::fmt::Display;
 
 use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use hmac::{Hmac, Mac};
 use rand::RngCore;
 use sha2::{Digest, Sha256};
 use zeroize::Zeroizing;
@@ -8,6 +9,24 @@ use zeroize::Zeroizing;
 #[derive(Debug, Clone)]
 pub struct Token<const N: usize>(Zeroizing<[u8; N]>);
 
+/// base64 (no padding) of the big-endian CRC32 of a prefixed token, see [`Token::prefixed`].
+const CHECKSUM_LEN: usize = 6;
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum PrefixedTokenError {
+    #[error("token doesn't start with `{0}`")]
+    WrongPrefix(String),
+
+    #[error("token has the wrong length")]
+    Length,
+
+    #[error("token checksum mismatch")]
+    Checksum,
+
+    #[error("cannot base64 decode token")]
+    Base64Decode,
+}
+
 impl<const N: usize> Token<N> {
     pub fn random() -> Self {
         let mut rng = rand::rng();
@@ -22,6 +41,15 @@ impl<const N: usize> Token<N> {
         hasher.finalize().to_vec()
     }
 
+    /// Keyed with a server-side `pepper`, so that, unlike [`Token::hash_sha256`],
+    /// a leaked database of hashes can't be checked against tokens captured elsewhere.
+    pub fn hash_hmac_sha256(&self, pepper: &[u8]) -> Vec<u8> {
+        let mut mac =
+            <Hmac<Sha256> as Mac>::new_from_slice(pepper).expect("HMAC can take a key of any size");
+        mac.update(self.0.as_slice());
+        mac.finalize().into_bytes().to_vec()
+    }
+
     pub fn base64encoded(&self) -> String {
         BASE64_URL_SAFE_NO_PAD.encode(&self.0)
     }
@@ -32,6 +60,44 @@ impl<const N: usize> Token<N> {
         Ok(Self(Zeroizing::new(bytes)))
     }
 
+    /// `<prefix><base64 token><checksum>`, e.g. `mona_at_...` for an access token.
+    /// The prefix lets secret scanners recognize leaked tokens, and the CRC32 checksum
+    /// (of the prefix and the token) lets typos be caught without looking the token up.
+    pub fn prefixed(&self, prefix: &str) -> String {
+        let token = self.base64encoded();
+        let checksum = checksum(prefix, &token);
+        format!("{prefix}{token}{checksum}")
+    }
+
+    /// Parses a [`Token::prefixed`] token, rejecting those of another prefix, i.e. of another kind.
+    pub fn from_prefixed(s: &str, prefix: &str) -> Result<Self, PrefixedTokenError> {
+        let rest = s
+            .strip_prefix(prefix)
+            .ok_or_else(|| PrefixedTokenError::WrongPrefix(prefix.to_string()))?;
+
+        let token_len = (N * 4).div_ceil(3);
+        if !rest.is_ascii() || rest.len() != token_len + CHECKSUM_LEN {
+            return Err(PrefixedTokenError::Length);
+        }
+
+        let (token, checksum_part) = rest.split_at(token_len);
+        if checksum_part != checksum(prefix, token) {
+            return Err(PrefixedTokenError::Checksum);
+        }
+
+        Self::base64decode(token).map_err(|_| PrefixedTokenError::Base64Decode)
+    }
+
+    /// Like [`Token::from_prefixed`], also accepting the unprefixed [`Token::base64encoded`] tokens
+    /// issued before prefixes were introduced. Those are told apart by their length,
+    /// which is always shorter than that of a prefixed token.
+    pub fn from_prefixed_or_legacy(s: &str, prefix: &str) -> Result<Self, PrefixedTokenError> {
+        match s.len() == (N * 4).div_ceil(3) {
+            true => Self::base64decode(s).map_err(|_| PrefixedTokenError::Base64Decode),
+            false => Self::from_prefixed(s, prefix),
+        }
+    }
+
     #[inline]
     pub fn from_bytes(bytes: [u8; N]) -> Self {
         Token::from(bytes)
@@ -43,6 +109,13 @@ impl<const N: usize> Token<N> {
     }
 }
 
+fn checksum(prefix: &str, token: &str) -> String {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(prefix.as_bytes());
+    hasher.update(token.as_bytes());
+    BASE64_URL_SAFE_NO_PAD.encode(hasher.finalize().to_be_bytes())
+}
+
 impl<const N: usize> From<[u8; N]> for Token<N> {
     #[inline]
     fn from(bytes: [u8; N]) -> Self {
@@ -0,0 +1,17 @@
+use token::Token;
+
+#[test]
+fn hmac_hash_depends_on_the_pepper() {
+    let token = Token::<32>::from_bytes([7; 32]);
+
+    let hash = token.hash_hmac_sha256(b"pepper");
+    assert_eq!(hash.len(), 32);
+    assert_eq!(hash, token.hash_hmac_sha256(b"pepper"));
+
+    assert_ne!(hash, token.hash_hmac_sha256(b"other pepper"));
+    assert_ne!(hash, token.hash_sha256());
+    assert_ne!(
+        hash,
+        Token::<32>::from_bytes([8; 32]).hash_hmac_sha256(b"pepper")
+    );
+}
@@ -0,0 +1,56 @@
+use token::{PrefixedTokenError, Token};
+
+#[test]
+fn roundtrip() {
+    let token = Token::<32>::random();
+    let prefixed = token.prefixed("mona_at_");
+
+    assert!(prefixed.starts_with("mona_at_"));
+    assert_eq!(prefixed.len(), "mona_at_".len() + 43 + 6);
+
+    let parsed = Token::<32>::from_prefixed(&prefixed, "mona_at_").unwrap();
+    assert_eq!(parsed.into_bytes(), token.into_bytes());
+}
+
+#[test]
+fn wrong_prefix_is_rejected() {
+    let prefixed = Token::<32>::random().prefixed("mona_sess_");
+
+    assert_eq!(
+        Token::<32>::from_prefixed(&prefixed, "mona_at_").unwrap_err(),
+        PrefixedTokenError::WrongPrefix("mona_at_".into())
+    );
+
+    // same length, so that only the checksum tells them apart
+    let relabeled = prefixed.replacen("mona_sess_", "mona_xxxx_", 1);
+    assert_eq!(
+        Token::<32>::from_prefixed(&relabeled, "mona_xxxx_").unwrap_err(),
+        PrefixedTokenError::Checksum
+    );
+}
+
+#[test]
+fn typos_are_detected_offline() {
+    let prefixed = Token::<32>::random().prefixed("mona_at_");
+
+    for i in "mona_at_".len()..prefixed.len() {
+        let mut typo = prefixed.clone().into_bytes();
+        typo[i] = if typo[i] == b'A' { b'B' } else { b'A' };
+        let typo = String::from_utf8(typo).unwrap();
+
+        assert_eq!(
+            Token::<32>::from_prefixed(&typo, "mona_at_").unwrap_err(),
+            PrefixedTokenError::Checksum,
+            "{typo}"
+        );
+    }
+
+    assert_eq!(
+        Token::<32>::from_prefixed(&prefixed[..prefixed.len() - 1], "mona_at_").unwrap_err(),
+        PrefixedTokenError::Length
+    );
+
+    // an unprefixed token isn't a prefixed one
+    let unprefixed = Token::<32>::random().base64encoded();
+    assert!(Token::<32>::from_prefixed(&unprefixed, "mona_at_").is_err());
+}
@@ -0,0 +1,239 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    Data, DeriveInput, Fields, Ident, LitInt, LitStr, Type, ext::IdentExt, spanned::Spanned,
+};
+
+/// See `validation::Validate`.
+#[proc_macro_derive(Validate, attributes(validate))]
+pub fn derive_validate(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+enum Rule {
+    Username,
+    Password {
+        user_inputs: Vec<Ident>,
+    },
+    Email,
+    Length {
+        min: Option<usize>,
+        max: Option<usize>,
+    },
+    Regex(LitStr),
+}
+
+struct Field {
+    ident: Ident,
+    optional: bool,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let named = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new(
+                    input.span(),
+                    "Validate can only be derived for structs with named fields",
+                ));
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                input.span(),
+                "Validate can only be derived for structs",
+            ));
+        }
+    };
+
+    let fields = named
+        .iter()
+        .map(|field| Field {
+            ident: field.ident.clone().expect("named field"),
+            optional: is_option(&field.ty),
+        })
+        .collect::<Vec<_>>();
+
+    let mut checks = Vec::new();
+    for (field, syn_field) in fields.iter().zip(named) {
+        for attr in &syn_field.attrs {
+            if !attr.path().is_ident("validate") {
+                continue;
+            }
+            for rule in parse_rules(attr)? {
+                checks.push(check(field, &rule, &fields)?);
+            }
+        }
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::validation::Validate for #name #ty_generics #where_clause {
+            fn validate_with(
+                &self,
+                policies: ::validation::Policies<'_>,
+            ) -> ::core::result::Result<(), ::validation::ValidationErrors> {
+                let mut errors = ::validation::ValidationErrors::default();
+                #(#checks)*
+                errors.into_result()
+            }
+        }
+    })
+}
+
+fn parse_rules(attr: &syn::Attribute) -> syn::Result<Vec<Rule>> {
+    let mut rules = Vec::new();
+
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("username") {
+            rules.push(Rule::Username);
+        } else if meta.path.is_ident("email") {
+            rules.push(Rule::Email);
+        } else if meta.path.is_ident("password") {
+            let mut user_inputs = Vec::new();
+            if meta.input.peek(syn::token::Paren) {
+                meta.parse_nested_meta(|meta| match meta.path.is_ident("user_inputs") {
+                    true => meta.parse_nested_meta(|meta| {
+                        user_inputs.push(meta.path.require_ident()?.clone());
+                        Ok(())
+                    }),
+                    false => Err(meta.error("expected `user_inputs(..)`")),
+                })?;
+            }
+            rules.push(Rule::Password { user_inputs });
+        } else if meta.path.is_ident("length") {
+            let (mut min, mut max) = (None, None);
+            meta.parse_nested_meta(|meta| {
+                let bound = if meta.path.is_ident("min") {
+                    &mut min
+                } else if meta.path.is_ident("max") {
+                    &mut max
+                } else {
+                    return Err(meta.error("expected `min` or `max`"));
+                };
+                *bound = Some(meta.value()?.parse::<LitInt>()?.base10_parse::<usize>()?);
+                Ok(())
+            })?;
+            if let (Some(min), Some(max)) = (min, max)
+                && min > max
+            {
+                return Err(meta.error("`min` must not be greater than `max`"));
+            }
+            rules.push(Rule::Length { min, max });
+        } else if meta.path.is_ident("regex") {
+            let pattern = meta.value()?.parse::<LitStr>()?;
+            if let Err(err) = regex::Regex::new(&pattern.value()) {
+                return Err(syn::Error::new(pattern.span(), err));
+            }
+            rules.push(Rule::Regex(pattern));
+        } else {
+            return Err(meta.error(
+                "expected one of `username`, `password`, `email`, `length(min, max)` or `regex = \"...\"`",
+            ));
+        }
+        Ok(())
+    })?;
+
+    Ok(rules)
+}
+
+fn check(field: &Field, rule: &Rule, fields: &[Field]) -> syn::Result<TokenStream> {
+    let name = field.ident.unraw().to_string();
+
+    let rule = match rule {
+        Rule::Username => quote! {
+            ::validation::rules::username(&mut errors, #name, value, policies.username);
+        },
+        Rule::Password { user_inputs } => {
+            let user_inputs = user_inputs
+                .iter()
+                .map(|input| {
+                    let other = fields
+                        .iter()
+                        .find(|field| field.ident == *input)
+                        .ok_or_else(|| syn::Error::new(input.span(), "no such field"))?;
+                    Ok(match other.optional {
+                        true => quote! {
+                            self.#input.as_ref().map(::core::convert::AsRef::<str>::as_ref)
+                        },
+                        false => quote! {
+                            ::core::option::Option::Some(::core::convert::AsRef::<str>::as_ref(&self.#input))
+                        },
+                    })
+                })
+                .collect::<syn::Result<Vec<_>>>()?;
+            quote! {
+                let user_inputs = [#(#user_inputs),*]
+                    .into_iter()
+                    .flatten()
+                    .collect::<::std::vec::Vec<&str>>();
+                ::validation::rules::password(&mut errors, #name, value, &user_inputs, policies.password);
+            }
+        }
+        Rule::Email => quote! {
+            match <::email::Email as ::core::str::FromStr>::from_str(value) {
+                ::core::result::Result::Err(err) => errors.push(#name, "email.invalid", err),
+                ::core::result::Result::Ok(_) => {
+                    if let ::core::option::Option::Some(rule) = policies.email {
+                        ::validation::EmailRule::check(rule, &mut errors, #name, value);
+                    }
+                }
+            }
+        },
+        Rule::Length { min, max } => {
+            let min = option(min);
+            let max = option(max);
+            quote! {
+                ::validation::rules::length(&mut errors, #name, value, #min, #max);
+            }
+        }
+        Rule::Regex(pattern) => quote! {
+            static REGEX: ::std::sync::LazyLock<::regex::Regex> =
+                ::std::sync::LazyLock::new(|| ::regex::Regex::new(#pattern).unwrap());
+            if !REGEX.is_match(value) {
+                ::validation::rules::pattern(&mut errors, #name, REGEX.as_str());
+            }
+        },
+    };
+
+    let ident = &field.ident;
+    Ok(match field.optional {
+        true => quote! {
+            if let ::core::option::Option::Some(value) = &self.#ident {
+                let value: &str = ::core::convert::AsRef::<str>::as_ref(value);
+                #rule
+            }
+        },
+        false => quote! {
+            {
+                let value: &str = ::core::convert::AsRef::<str>::as_ref(&self.#ident);
+                #rule
+            }
+        },
+    })
+}
+
+fn option(value: &Option<usize>) -> TokenStream {
+    match value {
+        Some(value) => quote! { ::core::option::Option::Some(#value) },
+        None => quote! { ::core::option::Option::None },
+    }
+}
+
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
@@ -0,0 +1,184 @@
+use validation::{EmailRule, PasswordPolicy, Policies, UsernamePolicy, Validate, ValidationErrors};
+
+#[derive(Validate)]
+struct Signup {
+    #[validate(username)]
+    username: String,
+
+    #[validate(email)]
+    email: String,
+
+    #[validate(password(user_inputs(username, email)))]
+    password: String,
+
+    #[validate(length(min = 3, max = 8), regex = "^[a-z-]+$")]
+    team: Option<String>,
+}
+
+fn kinds(errors: &validation::ValidationErrors) -> Vec<(&'static str, &str)> {
+    errors
+        .iter()
+        .map(|error| (error.field, error.kind.as_str()))
+        .collect()
+}
+
+#[test]
+fn valid() {
+    let signup = Signup {
+        username: "joe".into(),
+        email: "joe@smith.com".into(),
+        password: "h?P7o]37".into(),
+        team: Some("blue".into()),
+    };
+    assert_eq!(signup.validate(), Ok(()));
+}
+
+#[test]
+fn every_field_error_at_once() {
+    let signup = Signup {
+        username: "j".into(),
+        email: "joe".into(),
+        password: "password".into(),
+        team: Some("Red Team".into()),
+    };
+    let errors = signup.validate().unwrap_err();
+    assert_eq!(
+        kinds(&errors),
+        vec![
+            ("username", "username.invalid"),
+            ("email", "email.invalid"),
+            ("password", "password.weak"),
+            ("team", "team.pattern"),
+        ]
+    );
+}
+
+#[test]
+fn length() {
+    let signup = |team: &str| Signup {
+        username: "joe".into(),
+        email: "joe@smith.com".into(),
+        password: "h?P7o]37".into(),
+        team: Some(team.into()),
+    };
+    assert_eq!(
+        kinds(&signup("ab").validate().unwrap_err()),
+        vec![("team", "team.length")]
+    );
+    assert_eq!(
+        kinds(&signup("abcdefghi").validate().unwrap_err()),
+        vec![("team", "team.length")]
+    );
+}
+
+#[test]
+fn optional_fields_are_skipped_when_absent() {
+    let signup = Signup {
+        username: "joe".into(),
+        email: "joe@smith.com".into(),
+        password: "h?P7o]37".into(),
+        team: None,
+    };
+    assert_eq!(signup.validate(), Ok(()));
+}
+
+#[test]
+fn user_inputs_and_policies() {
+    let signup = Signup {
+        username: "zahash".into(),
+        email: "zahash@smith.com".into(),
+        password: "zahash2025".into(),
+        team: None,
+    };
+    assert_eq!(
+        kinds(&signup.validate().unwrap_err()),
+        vec![("password", "password.weak")]
+    );
+
+    let lenient = PasswordPolicy {
+        min_score: 0,
+        ..PasswordPolicy::default()
+    };
+    let unicode = UsernamePolicy::unicode();
+    let policies = Policies {
+        username: &unicode,
+        password: &lenient,
+        email: None,
+    };
+    assert_eq!(signup.validate_with(policies), Ok(()));
+
+    let signup = Signup {
+        username: "admin".into(),
+        ..signup
+    };
+    assert_eq!(
+        kinds(&signup.validate_with(policies).unwrap_err()),
+        vec![("username", "username.reserved")]
+    );
+}
+
+#[derive(Debug)]
+struct NoExampleDomains;
+
+impl EmailRule for NoExampleDomains {
+    fn check(&self, errors: &mut ValidationErrors, field: &'static str, email: &str) {
+        if email.ends_with("@example.com") {
+            errors.push(field, "email.policy.denied", "example.com is denied");
+        }
+    }
+}
+
+#[test]
+fn email_rule() {
+    let policies = Policies {
+        email: Some(&NoExampleDomains),
+        ..Policies::default()
+    };
+
+    let signup = Signup {
+        username: "j".into(),
+        email: "joe@example.com".into(),
+        password: "h?P7o]37".into(),
+        team: None,
+    };
+    assert_eq!(
+        kinds(&signup.validate_with(policies).unwrap_err()),
+        vec![
+            ("username", "username.invalid"),
+            ("email", "email.policy.denied")
+        ]
+    );
+    assert_eq!(
+        kinds(&signup.validate().unwrap_err()),
+        vec![("username", "username.invalid")]
+    );
+
+    // only well-formed emails reach the rule
+    let signup = Signup {
+        email: "example.com".into(),
+        ..signup
+    };
+    assert_eq!(
+        kinds(&signup.validate_with(policies).unwrap_err()),
+        vec![("username", "username.invalid"), ("email", "email.invalid")]
+    );
+}
+
+#[test]
+fn password_user_inputs_are_normalized() {
+    let signup = Signup {
+        username: "ｚａｈａｓｈ".into(),
+        email: "joe@smith.com".into(),
+        password: "zahash2025".into(),
+        team: None,
+    };
+    let lenient = UsernamePolicy::unicode();
+    let policies = Policies {
+        username: &lenient,
+        ..Policies::default()
+    };
+    assert_eq!(
+        kinds(&signup.validate_with(policies).unwrap_err()),
+        vec![("password", "password.weak")]
+    );
+}
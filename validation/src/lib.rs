@@ -1,48 +1,23 @@
 #[cfg(feature = "wasm")]
 pub mod wasm;
 
-pub fn validate_username<T: AsRef<str>>(username: T) -> Result<T, &'static str> {
-    let username_ref = username.as_ref();
-
-    if username_ref.len() < 2 || username_ref.len() > 30 {
-        return Err("username must be between 2-30 in length");
-    }
-
-    if username_ref
-        .chars()
-        .any(|c| !c.is_ascii_alphanumeric() && c != '_')
-    {
-        return Err("username must only contain `A-Z` `a-z` `0-9` and `_`");
-    }
-
+mod password;
+mod username;
+mod validate;
+
+pub use password::{COMMON_PASSWORDS, PasswordError, PasswordPolicy, Strength};
+pub use username::{RESERVED_USERNAMES, UsernameError, UsernamePolicy, normalize_username};
+pub use validate::{EmailRule, FieldError, Policies, Validate, ValidationErrors, rules};
+pub use validation_derive::Validate;
+
+/// Checks `username` against the default (ASCII) [`UsernamePolicy`].
+pub fn validate_username<T: AsRef<str>>(username: T) -> Result<T, UsernameError> {
+    UsernamePolicy::default().check(username.as_ref())?;
     Ok(username)
 }
 
-pub fn validate_password<T: AsRef<str>>(password: T) -> Result<T, &'static str> {
-    let password_ref = password.as_ref();
-
-    if !password_ref.chars().any(|c| c.is_lowercase()) {
-        return Err("password must contain at least one lowercase letter");
-    }
-
-    if !password_ref.chars().any(|c| c.is_uppercase()) {
-        return Err("password must contain at least one uppercase letter");
-    }
-
-    if !password_ref.chars().any(|c| c.is_ascii_digit()) {
-        return Err("password must contain at least one digit");
-    }
-
-    if !password_ref
-        .chars()
-        .any(|c| r#"!@#$%^&*()_-+={}[]|\:;"'<>,.?/~`"#.contains(c))
-    {
-        return Err("password must contain at least one special character");
-    }
-
-    if password_ref.len() < 8 {
-        return Err("password must be at least 8 characters long");
-    }
-
+/// Checks `password` against the default [`PasswordPolicy`], without knowing whose it is.
+pub fn validate_password<T: AsRef<str>>(password: T) -> Result<T, PasswordError> {
+    PasswordPolicy::default().check(password.as_ref(), &[])?;
     Ok(password)
-}
\ No newline at end of file
+}
@@ -0,0 +1,573 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::LazyLock,
+};
+
+/// See `common_passwords.txt` at the root of this crate.
+pub const COMMON_PASSWORDS: &str = include_str!("../common_passwords.txt");
+
+const SPECIAL: &str = r#"!@#$%^&*()_-+={}[]|\:;"'<>,.?/~` "#;
+
+/// Passwords longer than this are only estimated as random characters past this point.
+const MAX_ANALYZED: usize = 128;
+
+/// Lower bound of the guesses each additional pattern takes, so that splitting a password
+/// into many tiny patterns is not mistaken for it being weak.
+const MIN_GUESSES_PER_PATTERN_LOG10: f64 = 4.0;
+
+const REFERENCE_YEAR: i32 = 2020;
+const MIN_YEAR_SPACE: i32 = 20;
+
+const KEYBOARD_ROWS: &[&str] = &[
+    "`1234567890-=",
+    "qwertyuiop[]\\",
+    "asdfghjkl;'",
+    "zxcvbnm,./",
+];
+
+const WARNING_COMMON: &str = "it is similar to a commonly used password";
+const WARNING_USER_INPUT: &str = "it contains the username or email";
+const WARNING_SEQUENCE: &str = "sequences like `abc` or `6543` are easy to guess";
+const WARNING_KEYBOARD: &str = "straight rows of keys like `qwerty` are easy to guess";
+const WARNING_REPEAT: &str = "repeats like `aaa` or `abcabc` are easy to guess";
+const WARNING_YEAR: &str = "recent years are easy to guess";
+
+static RANKS: LazyLock<HashMap<&'static str, usize>> = LazyLock::new(|| {
+    let mut ranks = HashMap::new();
+    for (rank, password) in COMMON_PASSWORDS
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .enumerate()
+    {
+        ranks.entry(password).or_insert(rank + 1);
+    }
+    ranks
+});
+
+/// What passwords are accepted.
+///
+/// Besides the length and character-class rules, the password must not be easy to guess,
+/// see [`Strength::estimate`]. The default follows NIST SP 800-63B: no character-class rules,
+/// at least 8 characters and a [`Strength::score`] of at least 3.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswordPolicy {
+    /// In characters.
+    pub min_length: usize,
+
+    /// In characters. Note that bcrypt only looks at the first 72 bytes.
+    pub max_length: usize,
+
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+
+    /// One of ``!@#$%^&*()_-+={}[]|\:;"'<>,.?/~` `` or a space.
+    pub require_special: bool,
+
+    /// 0 to 4, see [`Strength::score`].
+    pub min_score: u8,
+}
+
+/// How hard a password is to guess, estimated the way zxcvbn does:
+/// the password is split into the most guessable sequence of patterns (common passwords,
+/// the user's own details, sequences, keyboard rows, repeats, years and random characters)
+/// and the guesses each one takes are multiplied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Strength {
+    pub guesses_log10: f64,
+
+    /// 0 (too guessable, below 10^3 guesses) to 4 (very unguessable, 10^10 guesses or more).
+    pub score: u8,
+
+    /// What makes the password easy to guess, if anything in particular.
+    pub warning: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PasswordError {
+    TooShort(usize),
+    TooLong(usize),
+    MissingLowercase,
+    MissingUppercase,
+    MissingDigit,
+    MissingSpecial,
+    TooWeak(Strength),
+}
+
+impl PasswordPolicy {
+    /// `user_inputs` are what an attacker would try first, e.g. the username and email address.
+    pub fn check(&self, password: &str, user_inputs: &[&str]) -> Result<Strength, PasswordError> {
+        let length = password.chars().count();
+
+        if length < self.min_length {
+            return Err(PasswordError::TooShort(self.min_length));
+        }
+
+        if length > self.max_length {
+            return Err(PasswordError::TooLong(self.max_length));
+        }
+
+        if self.require_lowercase && !password.chars().any(|c| c.is_lowercase()) {
+            return Err(PasswordError::MissingLowercase);
+        }
+
+        if self.require_uppercase && !password.chars().any(|c| c.is_uppercase()) {
+            return Err(PasswordError::MissingUppercase);
+        }
+
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            return Err(PasswordError::MissingDigit);
+        }
+
+        if self.require_special && !password.chars().any(|c| SPECIAL.contains(c)) {
+            return Err(PasswordError::MissingSpecial);
+        }
+
+        let strength = Strength::estimate(password, user_inputs);
+        match strength.score >= self.min_score {
+            true => Ok(strength),
+            false => Err(PasswordError::TooWeak(strength)),
+        }
+    }
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            max_length: 64,
+            require_lowercase: false,
+            require_uppercase: false,
+            require_digit: false,
+            require_special: false,
+            min_score: 3,
+        }
+    }
+}
+
+impl Strength {
+    /// `user_inputs` are what an attacker would try first, e.g. the username and email address.
+    pub fn estimate(password: &str, user_inputs: &[&str]) -> Self {
+        let chars = password.chars().collect::<Vec<_>>();
+        let (analyzed, rest) = chars.split_at(chars.len().min(MAX_ANALYZED));
+
+        let (mut guesses_log10, warning) =
+            most_guessable(analyzed, &patterns(analyzed, user_inputs));
+        if !rest.is_empty() {
+            guesses_log10 += rest.len() as f64 * cardinality(rest).log10();
+        }
+
+        let score = match guesses_log10 {
+            ..3.0 => 0,
+            ..6.0 => 1,
+            ..8.0 => 2,
+            ..10.0 => 3,
+            _ => 4,
+        };
+
+        Self {
+            guesses_log10,
+            score,
+            warning,
+        }
+    }
+}
+
+impl std::fmt::Display for PasswordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PasswordError::TooShort(min) => {
+                write!(f, "password must be at least {min} characters long")
+            }
+            PasswordError::TooLong(max) => {
+                write!(f, "password must be at most {max} characters long")
+            }
+            PasswordError::MissingLowercase => {
+                write!(f, "password must contain at least one lowercase letter")
+            }
+            PasswordError::MissingUppercase => {
+                write!(f, "password must contain at least one uppercase letter")
+            }
+            PasswordError::MissingDigit => write!(f, "password must contain at least one digit"),
+            PasswordError::MissingSpecial => {
+                write!(f, "password must contain at least one special character")
+            }
+            PasswordError::TooWeak(Strength {
+                warning: Some(warning),
+                ..
+            }) => write!(f, "password is too easy to guess: {warning}"),
+            PasswordError::TooWeak(_) => write!(f, "password is too easy to guess"),
+        }
+    }
+}
+
+impl std::error::Error for PasswordError {}
+
+/// A part of the password, `[start, end)` in chars, that is guessable in fewer tries than random characters.
+struct Pattern {
+    start: usize,
+    end: usize,
+    guesses_log10: f64,
+    warning: &'static str,
+}
+
+fn patterns(chars: &[char], user_inputs: &[&str]) -> Vec<Pattern> {
+    let lower = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect::<Vec<_>>();
+
+    let mut patterns = vec![];
+    dictionary(chars, &lower, &user_words(user_inputs), &mut patterns);
+    sequences(&lower, &mut patterns);
+    keyboard_rows(&lower, &mut patterns);
+    repeats(chars, &lower, &mut patterns);
+    years(&lower, &mut patterns);
+    patterns
+}
+
+/// The inputs themselves and their alphanumeric parts, e.g. `joe.smith`, `joe` and `smith` for `joe.smith@example.com`.
+fn user_words(user_inputs: &[&str]) -> HashSet<String> {
+    user_inputs
+        .iter()
+        .map(|input| input.to_lowercase())
+        .flat_map(|input| {
+            let parts = input
+                .split(|c: char| !c.is_alphanumeric())
+                .map(String::from)
+                .collect::<Vec<_>>();
+            let local_part = input
+                .split_once('@')
+                .map(|(local_part, _)| local_part.to_string());
+            std::iter::once(input).chain(local_part).chain(parts)
+        })
+        .filter(|word| word.chars().count() >= 3)
+        .collect()
+}
+
+fn dictionary(
+    chars: &[char],
+    lower: &[char],
+    user_words: &HashSet<String>,
+    patterns: &mut Vec<Pattern>,
+) {
+    for start in 0..lower.len() {
+        for end in start + 3..=lower.len() {
+            let word = lower[start..end].iter().collect::<String>();
+            let unleeted = word.chars().map(unleet).collect::<String>();
+            let reversed = word.chars().rev().collect::<String>();
+
+            let substitutions = word
+                .chars()
+                .zip(unleeted.chars())
+                .filter(|(a, b)| a != b)
+                .count();
+
+            let candidates = [
+                (word.as_str(), 0.0),
+                (unleeted.as_str(), substitutions as f64 * 2f64.log10()),
+                (reversed.as_str(), 2f64.log10()),
+            ];
+
+            let variations_log10 = uppercase_variations_log10(&chars[start..end]);
+
+            for (candidate, extra_log10) in candidates {
+                let found = match user_words.contains(candidate) {
+                    true => Some((0.0, WARNING_USER_INPUT)),
+                    false => RANKS
+                        .get(candidate)
+                        .map(|rank| ((*rank as f64).log10(), WARNING_COMMON)),
+                };
+
+                if let Some((rank_log10, warning)) = found {
+                    patterns.push(Pattern {
+                        start,
+                        end,
+                        guesses_log10: rank_log10 + extra_log10 + variations_log10,
+                        warning,
+                    });
+                }
+            }
+        }
+    }
+}
+
+fn unleet(c: char) -> char {
+    match c {
+        '4' | '@' => 'a',
+        '8' => 'b',
+        '(' => 'c',
+        '3' => 'e',
+        '6' | '9' => 'g',
+        '1' | '!' => 'i',
+        '|' => 'l',
+        '0' => 'o',
+        '$' | '5' => 's',
+        '7' | '+' => 't',
+        '2' => 'z',
+        c => c,
+    }
+}
+
+/// `password`, `Password`, `PASSWORD` and `passworD` are tried before other capitalizations.
+fn uppercase_variations_log10(chars: &[char]) -> f64 {
+    let upper = chars.iter().filter(|c| c.is_uppercase()).count();
+    let lower = chars.iter().filter(|c| c.is_lowercase()).count();
+
+    if upper == 0 {
+        return 0.0;
+    }
+
+    let first_or_last_only = upper == 1
+        && (chars.first().is_some_and(|c| c.is_uppercase())
+            || chars.last().is_some_and(|c| c.is_uppercase()));
+    if lower == 0 || first_or_last_only {
+        return 2f64.log10();
+    }
+
+    (1..=upper.min(lower))
+        .map(|k| binomial(upper + lower, k))
+        .sum::<f64>()
+        .log10()
+}
+
+fn binomial(n: usize, k: usize) -> f64 {
+    (1..=k).fold(1.0, |acc, i| acc * (n + 1 - i) as f64 / i as f64)
+}
+
+/// Runs like `abc`, `1357` or `zyx`.
+fn sequences(lower: &[char], patterns: &mut Vec<Pattern>) {
+    fn class(c: char) -> Option<u8> {
+        match c {
+            'a'..='z' => Some(0),
+            '0'..='9' => Some(1),
+            _ => None,
+        }
+    }
+
+    let mut start = 0;
+    while start + 2 < lower.len() {
+        let delta = lower[start + 1] as i64 - lower[start] as i64;
+
+        let mut end = start + 1;
+        while end < lower.len()
+            && class(lower[end]) == class(lower[start])
+            && lower[end] as i64 - lower[end - 1] as i64 == delta
+        {
+            end += 1;
+        }
+
+        if end - start >= 3 && class(lower[start]).is_some() && (1..=5).contains(&delta.abs()) {
+            let base = match lower[start] {
+                'a' | 'z' | '0' | '1' | '9' => 4.0,
+                '0'..='9' => 10.0,
+                _ => 26.0,
+            };
+            let descending = if delta < 0 { 2.0 } else { 1.0 };
+
+            patterns.push(Pattern {
+                start,
+                end,
+                guesses_log10: (base * descending * (end - start) as f64).log10(),
+                warning: WARNING_SEQUENCE,
+            });
+        }
+
+        start = (end - 1).max(start + 1);
+    }
+}
+
+/// Runs like `qwerty` or `lkjhg`, at least 4 keys long.
+fn keyboard_rows(lower: &[char], patterns: &mut Vec<Pattern>) {
+    const STARTING_KEYS: f64 = 94.0;
+
+    for start in 0..lower.len() {
+        let longest = (start + 4..=lower.len()).rev().find(|end| {
+            let run = lower[start..*end].iter().collect::<String>();
+            let reversed = run.chars().rev().collect::<String>();
+            KEYBOARD_ROWS
+                .iter()
+                .any(|row| row.contains(&run) || row.contains(&reversed))
+        });
+
+        if let Some(end) = longest {
+            patterns.push(Pattern {
+                start,
+                end,
+                guesses_log10: (STARTING_KEYS * (end - start) as f64).log10(),
+                warning: WARNING_KEYBOARD,
+            });
+        }
+    }
+}
+
+/// Runs like `aaa` or `abcabc`, guessed as random characters repeated.
+fn repeats(chars: &[char], lower: &[char], patterns: &mut Vec<Pattern>) {
+    for start in 0..lower.len() {
+        let longest = (1..=(lower.len() - start) / 2)
+            .map(|len| {
+                let base = &lower[start..start + len];
+                let count = lower[start..]
+                    .chunks_exact(len)
+                    .take_while(|chunk| *chunk == base)
+                    .count();
+                (len, count)
+            })
+            .filter(|(len, count)| *count >= 2 && len * count >= 3)
+            .max_by_key(|(len, count)| (len * count, std::cmp::Reverse(*len)));
+
+        if let Some((len, count)) = longest {
+            patterns.push(Pattern {
+                start,
+                end: start + len * count,
+                guesses_log10: bruteforce_log10(&chars[start..start + len])
+                    + (count as f64).log10(),
+                warning: WARNING_REPEAT,
+            });
+        }
+    }
+}
+
+fn years(lower: &[char], patterns: &mut Vec<Pattern>) {
+    for start in 0..lower.len().saturating_sub(3) {
+        let digits = &lower[start..start + 4];
+        if !digits.iter().all(char::is_ascii_digit) {
+            continue;
+        }
+
+        let year = digits.iter().fold(0, |year, digit| {
+            year * 10 + digit.to_digit(10).unwrap_or(0) as i32
+        });
+        if (1900..=2099).contains(&year) {
+            patterns.push(Pattern {
+                start,
+                end: start + 4,
+                guesses_log10: ((year - REFERENCE_YEAR).abs().max(MIN_YEAR_SPACE) as f64).log10(),
+                warning: WARNING_YEAR,
+            });
+        }
+    }
+}
+
+fn cardinality(chars: &[char]) -> f64 {
+    let has = |predicate: fn(&char) -> bool| chars.iter().any(predicate);
+
+    let mut cardinality = 0.0;
+    if has(|c| c.is_ascii_lowercase()) {
+        cardinality += 26.0;
+    }
+    if has(|c| c.is_ascii_uppercase()) {
+        cardinality += 26.0;
+    }
+    if has(|c| c.is_ascii_digit()) {
+        cardinality += 10.0;
+    }
+    if has(|c| SPECIAL.contains(*c)) {
+        cardinality += 33.0;
+    }
+    if has(|c| !c.is_ascii_alphanumeric() && !SPECIAL.contains(*c)) {
+        cardinality += 100.0;
+    }
+    cardinality
+}
+
+fn bruteforce_log10(chars: &[char]) -> f64 {
+    let guesses_log10 = chars.len() as f64 * cardinality(chars).log10();
+    match chars.len() {
+        1 => guesses_log10.max(1.0),
+        _ => guesses_log10.max(50f64.log10()),
+    }
+}
+
+/// The guesses (`log10`) of the most guessable way to split `chars` into patterns and random characters,
+/// along with the warning of its first pattern.
+fn most_guessable(chars: &[char], patterns: &[Pattern]) -> (f64, Option<&'static str>) {
+    #[derive(Clone, Copy)]
+    struct Step {
+        guesses_log10: f64,
+        start: usize,
+        pattern: Option<usize>,
+    }
+
+    let n = chars.len();
+    if n == 0 {
+        return (0.0, None);
+    }
+
+    let mut ending_at = vec![vec![]; n + 1];
+    for (index, pattern) in patterns.iter().enumerate() {
+        ending_at[pattern.end].push(index);
+    }
+
+    // best[end][count]: the most guessable `count` steps covering `chars[..end]`
+    let mut best = vec![vec![None::<Step>; n + 1]; n + 1];
+
+    for end in 1..=n {
+        let candidates = (0..end)
+            .map(|start| (start, bruteforce_log10(&chars[start..end]), None))
+            .chain(ending_at[end].iter().map(|index| {
+                let pattern = &patterns[*index];
+                (pattern.start, pattern.guesses_log10, Some(*index))
+            }))
+            .collect::<Vec<_>>();
+
+        let (before, after) = best.split_at_mut(end);
+        let steps = &mut after[0];
+
+        for (start, guesses_log10, pattern) in candidates {
+            let mut update = |count: usize, guesses_log10: f64| {
+                let slot = &mut steps[count];
+                if slot.is_none_or(|step| guesses_log10 < step.guesses_log10) {
+                    *slot = Some(Step {
+                        guesses_log10,
+                        start,
+                        pattern,
+                    });
+                }
+            };
+
+            match start {
+                0 => update(1, guesses_log10),
+                _ => {
+                    for (count, step) in before[start].iter().enumerate().skip(1) {
+                        if let Some(step) = step {
+                            update(count + 1, step.guesses_log10 + guesses_log10);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let (count, guesses_log10) = (1..=n)
+        .filter_map(|count| {
+            let step = best[n][count]?;
+            // count! orderings of the patterns, and at least MIN_GUESSES_PER_PATTERN for every extra one
+            let ordered = (2..=count).map(|i| (i as f64).log10()).sum::<f64>() + step.guesses_log10;
+            let minimum = MIN_GUESSES_PER_PATTERN_LOG10 * (count - 1) as f64;
+            Some((count, log10_sum(ordered, minimum)))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .unwrap_or((1, bruteforce_log10(chars)));
+
+    let mut warning = None;
+    let (mut end, mut count) = (n, count);
+    while let Some(step) = best[end][count] {
+        if let Some(index) = step.pattern {
+            warning = Some(patterns[index].warning);
+        }
+        if step.start == 0 {
+            break;
+        }
+        (end, count) = (step.start, count - 1);
+    }
+
+    (guesses_log10, warning)
+}
+
+/// `log10(10^a + 10^b)`
+fn log10_sum(a: f64, b: f64) -> f64 {
+    let (max, min) = if a > b { (a, b) } else { (b, a) };
+    max + (1.0 + 10f64.powf(min - max)).log10()
+}
@@ -0,0 +1,153 @@
+use std::collections::HashSet;
+
+use unicode_normalization::{UnicodeNormalization, char::is_combining_mark};
+use unicode_security::{GeneralSecurityProfile, RestrictionLevel, RestrictionLevelDetection};
+
+/// See `reserved_usernames.txt` at the root of this crate.
+pub const RESERVED_USERNAMES: &str = include_str!("../reserved_usernames.txt");
+
+/// Which usernames are accepted, and when two of them belong to the same account.
+///
+/// By default only `A-Z` `a-z` `0-9` and `_` are accepted, and usernames are compared as is.
+/// With [`UsernamePolicy::unicode`], letters and digits of any script are accepted once NFKC normalized,
+/// as long as they don't mix scripts that are not normally mixed (e.g. `раypal` with a Cyrillic `р` and `а`),
+/// and usernames are compared by their case-folded confusable skeleton (UTS #39), so `Joe` is `joe`
+/// and `ѕсоре` (all Cyrillic) is `scope`.
+///
+/// Reserved names are compared by their skeleton either way.
+#[derive(Debug, Clone)]
+pub struct UsernamePolicy {
+    unicode: bool,
+    reserved: HashSet<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UsernameError {
+    InvalidLength,
+    InvalidCharacters { unicode: bool },
+    MixedScripts,
+    Reserved(String),
+}
+
+impl UsernamePolicy {
+    /// `A-Z` `a-z` `0-9` and `_`, except for the bundled [`RESERVED_USERNAMES`].
+    pub fn ascii() -> Self {
+        Self {
+            unicode: false,
+            reserved: HashSet::new(),
+        }
+        .with_reserved_names(RESERVED_USERNAMES)
+    }
+
+    /// Letters and digits of any script and `_`, except for the bundled [`RESERVED_USERNAMES`].
+    pub fn unicode() -> Self {
+        Self {
+            unicode: true,
+            ..Self::ascii()
+        }
+    }
+
+    /// Replaces the reserved names, one per line. Blank lines and lines starting with `#` are ignored.
+    pub fn with_reserved_names(mut self, list: &str) -> Self {
+        self.reserved = list
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(skeleton)
+            .collect();
+        self
+    }
+
+    /// The username as it is stored and shown, NFKC normalized with [`UsernamePolicy::unicode`].
+    pub fn check(&self, username: &str) -> Result<String, UsernameError> {
+        let username = match self.unicode {
+            true => normalize_username(username),
+            false => username.to_string(),
+        };
+
+        if !(2..=30).contains(&username.chars().count()) {
+            return Err(UsernameError::InvalidLength);
+        }
+
+        let allowed = |c: char| match self.unicode {
+            true => {
+                c == '_'
+                    || ((c.is_alphanumeric() || is_combining_mark(c)) && c.identifier_allowed())
+            }
+            false => c.is_ascii_alphanumeric() || c == '_',
+        };
+        if !username.chars().all(allowed) {
+            return Err(UsernameError::InvalidCharacters {
+                unicode: self.unicode,
+            });
+        }
+
+        if !username
+            .as_str()
+            .check_restriction_level(RestrictionLevel::HighlyRestrictive)
+        {
+            return Err(UsernameError::MixedScripts);
+        }
+
+        if self.reserved.contains(&skeleton(&username)) {
+            return Err(UsernameError::Reserved(username));
+        }
+
+        Ok(username)
+    }
+
+    /// The form two usernames are compared by, e.g. to tell whether one is already taken.
+    pub fn canonical(&self, username: &str) -> String {
+        match self.unicode {
+            true => skeleton(username),
+            false => username.to_string(),
+        }
+    }
+
+    /// Names how [`UsernamePolicy::canonical`] compares usernames (`as-is` or `skeleton`),
+    /// e.g. to store along with canonical forms and tell which ones another policy has to recompute.
+    pub fn canonical_kind(&self) -> &'static str {
+        match self.unicode {
+            true => "skeleton",
+            false => "as-is",
+        }
+    }
+}
+
+impl Default for UsernamePolicy {
+    fn default() -> Self {
+        Self::ascii()
+    }
+}
+
+impl std::fmt::Display for UsernameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UsernameError::InvalidLength => write!(f, "username must be between 2-30 in length"),
+            UsernameError::InvalidCharacters { unicode: false } => {
+                write!(f, "username must only contain `A-Z` `a-z` `0-9` and `_`")
+            }
+            UsernameError::InvalidCharacters { unicode: true } => {
+                write!(f, "username must only contain letters, digits and `_`")
+            }
+            UsernameError::MixedScripts => {
+                write!(f, "username must not mix letters of different scripts")
+            }
+            UsernameError::Reserved(username) => write!(f, "username `{username}` is reserved"),
+        }
+    }
+}
+
+impl std::error::Error for UsernameError {}
+
+/// NFKC, e.g. `ｊｏｅ` (fullwidth) is `joe`.
+/// Usernames entered to log in should be normalized the same way they were on signup.
+pub fn normalize_username(username: &str) -> String {
+    username.nfkc().collect()
+}
+
+/// Case folded before and after the UTS #39 skeleton, since the skeleton of `0` is `O`.
+fn skeleton(username: &str) -> String {
+    let folded = caseless::default_case_fold_str(&normalize_username(username));
+    caseless::default_case_fold_str(&unicode_security::skeleton(&folded).collect::<String>())
+}
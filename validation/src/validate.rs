@@ -0,0 +1,187 @@
+use std::sync::LazyLock;
+
+use crate::{PasswordPolicy, UsernamePolicy};
+
+/// Checks every field of a request body at once, usually through `#[derive(Validate)]`:
+///
+/// ```ignore
+/// #[derive(Validate)]
+/// struct RequestBody {
+///     #[validate(username)]
+///     username: String,
+///
+///     #[validate(email)]
+///     email: String,
+///
+///     /// penalized for containing the username or email, see [`PasswordPolicy::check`]
+///     #[validate(password(user_inputs(username, email)))]
+///     password: String,
+///
+///     #[validate(length(min = 1, max = 64), regex = "^[a-z0-9-]+$")]
+///     name: Option<String>,
+/// }
+/// ```
+///
+/// `Option` fields are only checked when present.
+/// Like `compiletime::regex!`, `#[validate(email)]` and `#[validate(regex = "...")]` expand to
+/// `::email` and `::regex`, which the deriving crate has to depend on.
+pub trait Validate {
+    /// With the default [`Policies`].
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        self.validate_with(Policies::default())
+    }
+
+    fn validate_with(&self, policies: Policies<'_>) -> Result<(), ValidationErrors>;
+}
+
+/// What `#[validate(username)]`, `#[validate(password)]` and `#[validate(email)]` check against,
+/// e.g. the ones configured on the server.
+#[derive(Debug, Clone, Copy)]
+pub struct Policies<'a> {
+    pub username: &'a UsernamePolicy,
+    pub password: &'a PasswordPolicy,
+
+    /// Checks well-formed emails, e.g. against an `email::EmailPolicy`. `None` only checks the syntax.
+    pub email: Option<&'a dyn EmailRule>,
+}
+
+/// Pushes the errors of an email that is well-formed but still not accepted,
+/// so that they are listed along with the errors of the other fields.
+pub trait EmailRule: std::fmt::Debug + Sync {
+    fn check(&self, errors: &mut ValidationErrors, field: &'static str, email: &str);
+}
+
+/// One failed rule of one field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    pub field: &'static str,
+
+    /// e.g. `username.invalid` or `name.length`.
+    pub kind: String,
+
+    pub message: String,
+}
+
+/// Every failed rule, in the order the fields are declared.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationErrors(pub Vec<FieldError>);
+
+impl Default for Policies<'static> {
+    fn default() -> Self {
+        static USERNAME: LazyLock<UsernamePolicy> = LazyLock::new(UsernamePolicy::default);
+        static PASSWORD: LazyLock<PasswordPolicy> = LazyLock::new(PasswordPolicy::default);
+
+        Self {
+            username: &USERNAME,
+            password: &PASSWORD,
+            email: None,
+        }
+    }
+}
+
+impl ValidationErrors {
+    pub fn push(&mut self, field: &'static str, kind: impl Into<String>, message: impl ToString) {
+        self.0.push(FieldError {
+            field,
+            kind: kind.into(),
+            message: message.to_string(),
+        });
+    }
+
+    pub fn into_result(self) -> Result<(), Self> {
+        match self.0.is_empty() {
+            true => Ok(()),
+            false => Err(self),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &FieldError> {
+        self.0.iter()
+    }
+}
+
+impl std::fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, error) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", error.message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+/// What `#[derive(Validate)]` expands to.
+#[doc(hidden)]
+pub mod rules {
+    use super::ValidationErrors;
+    use crate::{PasswordPolicy, UsernameError, UsernamePolicy, normalize_username};
+
+    pub fn username(
+        errors: &mut ValidationErrors,
+        field: &'static str,
+        value: &str,
+        policy: &UsernamePolicy,
+    ) {
+        match policy.check(value) {
+            Ok(_) => {}
+            Err(err @ UsernameError::Reserved(_)) => errors.push(field, "username.reserved", err),
+            Err(err) => errors.push(field, "username.invalid", err),
+        }
+    }
+
+    /// `user_inputs` are normalized like usernames are stored, see [`normalize_username`],
+    /// so that e.g. a fullwidth `ｊｏｅ` is penalized like the `joe` it signs up as.
+    pub fn password(
+        errors: &mut ValidationErrors,
+        field: &'static str,
+        value: &str,
+        user_inputs: &[&str],
+        policy: &PasswordPolicy,
+    ) {
+        let user_inputs = user_inputs
+            .iter()
+            .map(|input| normalize_username(input))
+            .collect::<Vec<_>>();
+        let user_inputs = user_inputs.iter().map(String::as_str).collect::<Vec<_>>();
+
+        if let Err(err) = policy.check(value, &user_inputs) {
+            errors.push(field, "password.weak", err);
+        }
+    }
+
+    /// In characters.
+    pub fn length(
+        errors: &mut ValidationErrors,
+        field: &'static str,
+        value: &str,
+        min: Option<usize>,
+        max: Option<usize>,
+    ) {
+        let length = value.chars().count();
+        let message = match (min, max) {
+            (Some(min), Some(max)) if !(min..=max).contains(&length) => {
+                format!("{field} must be between {min}-{max} characters long")
+            }
+            (Some(min), _) if length < min => {
+                format!("{field} must be at least {min} characters long")
+            }
+            (_, Some(max)) if length > max => {
+                format!("{field} must be at most {max} characters long")
+            }
+            _ => return,
+        };
+        errors.push(field, format!("{field}.length"), message);
+    }
+
+    pub fn pattern(errors: &mut ValidationErrors, field: &'static str, pattern: &str) {
+        errors.push(
+            field,
+            format!("{field}.pattern"),
+            format!("{field} must match `{pattern}`"),
+        );
+    }
+}
@@ -24,6 +24,127 @@ impl ValidationResult {
     }
 }
 
+/// The verdict of a [`PasswordPolicy`], along with how strong the password is
+/// even when it is rejected, so that the frontend can show a strength meter.
+#[wasm_bindgen]
+pub struct PasswordVerdict {
+    valid: bool,
+    error: Option<String>,
+    score: u8,
+    guesses_log10: f64,
+    warning: Option<String>,
+}
+
+#[wasm_bindgen]
+impl PasswordVerdict {
+    #[wasm_bindgen(getter)]
+    pub fn valid(&self) -> bool {
+        self.valid
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn error(&self) -> Option<String> {
+        self.error.clone()
+    }
+
+    /// 0 to 4.
+    #[wasm_bindgen(getter)]
+    pub fn score(&self) -> u8 {
+        self.score
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn guesses_log10(&self) -> f64 {
+        self.guesses_log10
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn warning(&self) -> Option<String> {
+        self.warning.clone()
+    }
+}
+
+/// What [`check_password`] checks against, the default [`crate::PasswordPolicy`] until changed.
+/// Set it to the server's `[password]` for the verdict to match the one on signup.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct PasswordPolicy(crate::PasswordPolicy);
+
+#[wasm_bindgen]
+impl PasswordPolicy {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> PasswordPolicy {
+        PasswordPolicy::default()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_min_length(&mut self, min_length: usize) {
+        self.0.min_length = min_length;
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_max_length(&mut self, max_length: usize) {
+        self.0.max_length = max_length;
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_require_lowercase(&mut self, require_lowercase: bool) {
+        self.0.require_lowercase = require_lowercase;
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_require_uppercase(&mut self, require_uppercase: bool) {
+        self.0.require_uppercase = require_uppercase;
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_require_digit(&mut self, require_digit: bool) {
+        self.0.require_digit = require_digit;
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_require_special(&mut self, require_special: bool) {
+        self.0.require_special = require_special;
+    }
+
+    /// 0 to 4.
+    #[wasm_bindgen(setter)]
+    pub fn set_min_score(&mut self, min_score: u8) {
+        self.0.min_score = min_score;
+    }
+}
+
+/// Same as the server's check on signup, when `policy` is set to the server's `[password]`.
+#[wasm_bindgen]
+pub fn check_password(
+    password: &str,
+    username: &str,
+    email: &str,
+    policy: &PasswordPolicy,
+) -> PasswordVerdict {
+    let user_inputs = [username, email];
+
+    let (strength, error) = match policy.0.check(password, &user_inputs) {
+        Ok(strength) => (strength, None),
+        Err(crate::PasswordError::TooWeak(strength)) => {
+            let error = crate::PasswordError::TooWeak(strength.clone()).to_string();
+            (strength, Some(error))
+        }
+        Err(err) => (
+            crate::Strength::estimate(password, &user_inputs),
+            Some(err.to_string()),
+        ),
+    };
+
+    PasswordVerdict {
+        valid: error.is_none(),
+        error,
+        score: strength.score,
+        guesses_log10: strength.guesses_log10,
+        warning: strength.warning.map(String::from),
+    }
+}
+
 #[wasm_bindgen]
 pub fn validate_password(password: &str) -> ValidationResult {
     match crate::validate_password(password) {
@@ -38,4 +159,19 @@ pub fn validate_username(username: String) -> ValidationResult {
         Ok(_) => ValidationResult::new(true, None),
         Err(err) => ValidationResult::new(false, Some(err.to_string())),
     }
-}
\ No newline at end of file
+}
+
+/// Same as the server's check on signup, with the bundled reserved names.
+/// `allow_unicode` should match the server's `[username] allow_unicode`.
+#[wasm_bindgen]
+pub fn check_username(username: &str, allow_unicode: bool) -> ValidationResult {
+    let policy = match allow_unicode {
+        true => crate::UsernamePolicy::unicode(),
+        false => crate::UsernamePolicy::ascii(),
+    };
+
+    match policy.check(username) {
+        Ok(_) => ValidationResult::new(true, None),
+        Err(err) => ValidationResult::new(false, Some(err.to_string())),
+    }
+}
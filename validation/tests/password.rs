@@ -0,0 +1,135 @@
+use validation::{PasswordError, PasswordPolicy, Strength, validate_password};
+
+fn score(password: &str) -> u8 {
+    Strength::estimate(password, &[]).score
+}
+
+#[test]
+fn passphrases_are_strong_and_common_passwords_are_not() {
+    assert!(validate_password("correct horse battery staple extra").is_ok());
+    assert!(validate_password("rhubarb lantern quietly").is_ok());
+
+    for weak in [
+        "Password1!",
+        "P@ssw0rd",
+        "qwerty123",
+        "iloveyou2024",
+        "drowssap1",
+        "aaaaaaaaaaaa",
+        "abcdefghijk",
+        "1234567890",
+        "asdfghjkl;",
+        "abc123abc123",
+    ] {
+        assert!(
+            matches!(validate_password(weak), Err(PasswordError::TooWeak(_))),
+            "{weak} ({:?})",
+            Strength::estimate(weak, &[])
+        );
+    }
+}
+
+#[test]
+fn scores_grow_with_guesses() {
+    assert_eq!(score(""), 0);
+    assert_eq!(score("password"), 0);
+    assert!(score("Password1!") <= 1);
+    assert!(score("kX9#mQ2$") >= 3);
+    assert_eq!(score("correct horse battery staple extra"), 4);
+
+    let weak = Strength::estimate("letmein", &[]);
+    let strong = Strength::estimate("letmein-vQ8r!zW", &[]);
+    assert!(weak.guesses_log10 < strong.guesses_log10);
+}
+
+#[test]
+fn warns_about_what_makes_it_guessable() {
+    let warning = |password: &str| {
+        Strength::estimate(password, &["joe_smith", "joe.smith@example.com"]).warning
+    };
+
+    assert_eq!(
+        warning("password"),
+        Some("it is similar to a commonly used password")
+    );
+    assert_eq!(
+        warning("JoeSmith"),
+        Some("it contains the username or email")
+    );
+    assert_eq!(
+        warning("abcdefgh"),
+        Some("sequences like `abc` or `6543` are easy to guess")
+    );
+    assert_eq!(
+        warning("zzzzzzzz"),
+        Some("repeats like `aaa` or `abcabc` are easy to guess")
+    );
+    assert_eq!(
+        warning("xkcd-tv-1999"),
+        Some("recent years are easy to guess")
+    );
+    assert_eq!(warning("Gh7#kPq2vL"), None);
+}
+
+#[test]
+fn user_inputs_are_penalized() {
+    let policy = PasswordPolicy::default();
+    let user_inputs = ["zahash", "zahash.z@gmail.com"];
+
+    assert!(policy.check("zahash2025", &[]).is_ok());
+    assert!(matches!(
+        policy.check("zahash2025", &user_inputs),
+        Err(PasswordError::TooWeak(_))
+    ));
+    assert!(matches!(
+        policy.check("Zahash.z2025", &user_inputs),
+        Err(PasswordError::TooWeak(_))
+    ));
+}
+
+#[test]
+fn rules() {
+    let policy = PasswordPolicy {
+        min_length: 10,
+        max_length: 20,
+        require_lowercase: true,
+        require_uppercase: true,
+        require_digit: true,
+        require_special: true,
+        min_score: 0,
+    };
+
+    assert_eq!(policy.check("Aa1!", &[]), Err(PasswordError::TooShort(10)));
+    assert_eq!(
+        policy.check("Aa1!aaaaaaaaaaaaaaaaaaaaa", &[]),
+        Err(PasswordError::TooLong(20))
+    );
+    assert_eq!(
+        policy.check("AAAAAAAAA1!", &[]),
+        Err(PasswordError::MissingLowercase)
+    );
+    assert_eq!(
+        policy.check("aaaaaaaaa1!", &[]),
+        Err(PasswordError::MissingUppercase)
+    );
+    assert_eq!(
+        policy.check("Aaaaaaaaaa!", &[]),
+        Err(PasswordError::MissingDigit)
+    );
+    assert_eq!(
+        policy.check("Aaaaaaaaaa1", &[]),
+        Err(PasswordError::MissingSpecial)
+    );
+    assert!(policy.check("Aaaaaaaaaa1!", &[]).is_ok());
+
+    // length is in characters, not bytes
+    assert!(
+        PasswordPolicy::default()
+            .check("ünïcödé-pässwörd", &[])
+            .is_ok()
+    );
+    assert_eq!(
+        PasswordPolicy::default().check("ääää", &[]),
+        Err(PasswordError::TooShort(8))
+    );
+}
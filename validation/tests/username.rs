@@ -0,0 +1,94 @@
+use validation::{UsernameError, UsernamePolicy, validate_username};
+
+#[test]
+fn ascii_by_default() {
+    let policy = UsernamePolicy::default();
+
+    assert_eq!(policy.check("joe_smith2"), Ok("joe_smith2".into()));
+    assert_eq!(policy.check("j"), Err(UsernameError::InvalidLength));
+    assert_eq!(
+        policy.check("jöe"),
+        Err(UsernameError::InvalidCharacters { unicode: false })
+    );
+    assert_eq!(
+        policy.check("ｊｏｅ"),
+        Err(UsernameError::InvalidCharacters { unicode: false })
+    );
+
+    // compared as is
+    assert_ne!(policy.canonical("Joe"), policy.canonical("joe"));
+    assert_ne!(
+        policy.canonical_kind(),
+        UsernamePolicy::unicode().canonical_kind()
+    );
+
+    assert!(validate_username("user1").is_ok());
+}
+
+#[test]
+fn reserved_names() {
+    for policy in [UsernamePolicy::ascii(), UsernamePolicy::unicode()] {
+        for reserved in ["admin", "Admin", "ROOT", "r00t", "support"] {
+            assert_eq!(
+                policy.check(reserved),
+                Err(UsernameError::Reserved(reserved.into())),
+                "{reserved}"
+            );
+        }
+        assert!(policy.check("admiral").is_ok());
+    }
+
+    // а and о are Cyrillic
+    assert!(UsernamePolicy::unicode().check("аdmin").is_err());
+    assert!(UsernamePolicy::unicode().check("rооt").is_err());
+
+    let policy = UsernamePolicy::ascii().with_reserved_names("# ours\nceo\n");
+    assert!(policy.check("CEO").is_err());
+    assert!(policy.check("admin").is_ok());
+}
+
+#[test]
+fn unicode_is_normalized() {
+    let policy = UsernamePolicy::unicode();
+
+    assert_eq!(policy.check("ｊｏｅ"), Ok("joe".into()));
+    assert_eq!(policy.check("jose\u{0301}"), Ok("josé".into()));
+    assert_eq!(policy.check("Zoë_42"), Ok("Zoë_42".into()));
+    assert_eq!(policy.check("山田太郎"), Ok("山田太郎".into()));
+    assert_eq!(policy.check("やまだ_タロウ"), Ok("やまだ_タロウ".into()));
+    assert_eq!(policy.check("Дмитрий"), Ok("Дмитрий".into()));
+
+    assert_eq!(
+        policy.check("joe smith"),
+        Err(UsernameError::InvalidCharacters { unicode: true })
+    );
+    assert_eq!(
+        policy.check("joe-smith"),
+        Err(UsernameError::InvalidCharacters { unicode: true })
+    );
+    assert_eq!(policy.check("ｊ"), Err(UsernameError::InvalidLength));
+}
+
+#[test]
+fn mixed_scripts_are_rejected() {
+    let policy = UsernamePolicy::unicode();
+
+    // р and а are Cyrillic
+    assert_eq!(policy.check("раypal"), Err(UsernameError::MixedScripts));
+    assert_eq!(policy.check("joeΑ"), Err(UsernameError::MixedScripts));
+}
+
+#[test]
+fn unicode_usernames_are_compared_by_skeleton() {
+    let policy = UsernamePolicy::unicode();
+    let canonical = |username: &str| policy.canonical(&policy.check(username).unwrap());
+
+    assert_eq!(canonical("Joe"), canonical("joe"));
+    assert_eq!(canonical("ＪＯＥ"), canonical("joe"));
+    assert_eq!(canonical("josé"), canonical("jose\u{0301}"));
+    // all Cyrillic
+    assert_eq!(canonical("ѕсоре"), canonical("scope"));
+    assert_eq!(canonical("Straße"), canonical("strasse"));
+
+    assert_ne!(canonical("joe"), canonical("jon"));
+}